@@ -0,0 +1,39 @@
+//! Reading the system clipboard.
+//!
+//! This goes through whichever clipboard program the platform provides.
+use std::io;
+
+use crate::external;
+
+#[cfg(target_os = "macos")]
+const PROGRAMS: [(&str, &[&str]); 1] = [("pbpaste", &[])];
+#[cfg(windows)]
+const PROGRAMS: [(&str, &[&str]); 1] = [(
+    "powershell",
+    &["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+)];
+#[cfg(not(any(target_os = "macos", windows)))]
+const PROGRAMS: [(&str, &[&str]); 3] = [
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+
+/// Read the text currently in the clipboard.
+///
+/// If none of the programs for reading it are installed, the error is `NotFound`.
+pub fn read() -> io::Result<String> {
+    for (program, args) in PROGRAMS.iter() {
+        match external::filter(program, args, Vec::new()) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+            Ok(out) => return Ok(String::from_utf8_lossy(&out).into_owned()),
+        }
+    }
+    let names: Vec<&str> = PROGRAMS.iter().map(|(program, _)| *program).collect();
+    let msg = format!(
+        "reading the clipboard needs one of {}, but none were found",
+        names.join(", ")
+    );
+    Err(io::Error::new(io::ErrorKind::NotFound, msg))
+}
@@ -0,0 +1,95 @@
+//! Reading compressed files as they're decompressed.
+//!
+//! Files are decompressed as they're read, without holding all of them at once. Gzip holds
+//! DEFLATE, like ZIP archives, so it's decoded the same way, and the others are delegated to
+//! the usual programs for each format.
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::external;
+use crate::inflate::Gzip;
+
+/// The kinds of compression files can be read with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Recognise compression from the extension of a file.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+
+    /// Recognise compression from the first bytes of a file.
+    pub fn from_magic(start: &[u8]) -> Option<Self> {
+        if start.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if start.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    fn decompress(self, file: File) -> io::Result<Box<dyn Read>> {
+        let program = match self {
+            Compression::Gzip => return Ok(Box::new(Gzip::new(io::BufReader::new(file)))),
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        };
+        Ok(Box::new(external::stream(program, &["-d", "-c"], file)?))
+    }
+}
+
+/// The name a file would have once decompressed, like `corpus.txt` for `corpus.txt.gz`.
+pub fn decompressed_name(path: &Path) -> PathBuf {
+    match Compression::from_extension(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Open a file, decompressing it as it's read if it's compressed.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read>> {
+    let mut file = File::open(path)?;
+    let mut start = [0; 4];
+    let read = file.read(&mut start)?;
+    file.seek(SeekFrom::Start(0))?;
+    let compression =
+        Compression::from_magic(&start[..read]).or_else(|| Compression::from_extension(path));
+    match compression {
+        None => Ok(Box::new(file)),
+        Some(c) => c.decompress(file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_is_recognised() {
+        let path = Path::new("jawiki.xml.bz2");
+        assert_eq!(Some(Compression::Bzip2), Compression::from_extension(path));
+        assert_eq!(Path::new("jawiki.xml"), decompressed_name(path));
+        assert_eq!(Path::new("a.txt"), decompressed_name(Path::new("a.txt")));
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::from_magic(&[0x1f, 0x8b, 8, 0])
+        );
+        assert_eq!(None, Compression::from_magic("猫".as_bytes()));
+    }
+}
@@ -0,0 +1,346 @@
+//! Reading defaults from a config file, like ~/.config/ginkou/config.toml.
+//!
+//! Only the part of TOML these settings need is read: comments, keys set to strings,
+//! integers, or booleans, each on its own line, and a table for each profile, like [profiles.anime].
+use crate::export;
+use crate::tokenizer::Backend;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A value given to a key.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+/// An error in a config file, on some line.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on line {}", self.message, self.line)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The defaults a config file can set, each left unset if the file doesn't mention it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// The database to use, instead of the usual one.
+    pub database: Option<PathBuf>,
+    pub tokenizer: Option<Backend>,
+    /// The dictionary for the tokenizer to use, instead of its own.
+    pub dictionary: Option<PathBuf>,
+    pub user_dictionary: Option<PathBuf>,
+    /// How many sentences to show for a search, or 0 to show them all.
+    pub limit: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_length: Option<usize>,
+    /// The characters ending a sentence, as given to --delimiters.
+    pub delimiters: Option<String>,
+    /// Whether to add sentences faster, at the risk of losing them if the computer crashes.
+    pub fast: Option<bool>,
+    /// The format to export sentences in.
+    pub format: Option<export::Format>,
+    /// A file holding the passphrase of an encrypted database.
+    pub key_file: Option<PathBuf>,
+    /// The profile these settings are from, if they're from one.
+    pub profile: Option<String>,
+}
+
+/// Everything in a config file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// The settings used without a profile.
+    pub settings: Settings,
+    /// Each profile, with the settings outside of any profile filled in.
+    pub profiles: Vec<Settings>,
+}
+
+impl Config {
+    pub fn profile(&self, name: &str) -> Option<&Settings> {
+        self.profiles
+            .iter()
+            .find(|p| p.profile.as_deref() == Some(name))
+    }
+}
+
+// Paths starting with ~ are in the home directory, as in a shell
+fn file(value: Value) -> Result<PathBuf, String> {
+    let path = string(value)?;
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => Ok(home.join(rest)),
+        _ => Ok(PathBuf::from(path)),
+    }
+}
+
+fn string(value: Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err("expected a string".into()),
+    }
+}
+
+fn number(value: Value) -> Result<usize, String> {
+    match value {
+        Value::Integer(n) if n >= 0 => Ok(n as usize),
+        _ => Err("expected a number that isn't negative".into()),
+    }
+}
+
+fn boolean(value: Value) -> Result<bool, String> {
+    match value {
+        Value::Boolean(b) => Ok(b),
+        _ => Err("expected true or false".into()),
+    }
+}
+
+impl Settings {
+    // Keys can be written like the flags they match too, as in max-length
+    fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        match key.replace('-', "_").as_str() {
+            "database" => self.database = Some(file(value)?),
+            "tokenizer" => self.tokenizer = Some(string(value)?.parse()?),
+            "dictionary" => self.dictionary = Some(file(value)?),
+            "user_dictionary" => self.user_dictionary = Some(file(value)?),
+            "limit" => self.limit = Some(number(value)?),
+            "max_length" => self.max_length = Some(number(value)?),
+            "min_length" => self.min_length = Some(number(value)?),
+            "delimiters" => self.delimiters = Some(string(value)?),
+            "fast" => self.fast = Some(boolean(value)?),
+            "format" => self.format = Some(string(value)?.parse()?),
+            "key_file" => self.key_file = Some(file(value)?),
+            _ => return Err(format!("unknown setting {}", key)),
+        }
+        Ok(())
+    }
+}
+
+fn is_bare_key(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+// Reads a basic string, after its opening quote, returning it along with what's after it
+fn basic_string(text: &str) -> Result<(String, &str), String> {
+    let mut string = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((string, &text[i + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('r') => string.push('\r'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                        .ok_or("invalid unicode escape")?;
+                    string.push(c);
+                }
+                _ => return Err("invalid escape".into()),
+            },
+            c => string.push(c),
+        }
+    }
+    Err("unterminated string".into())
+}
+
+// Reads the value of a key, returning it along with what's after it
+fn value(text: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = text.strip_prefix('"') {
+        let (s, rest) = basic_string(rest)?;
+        return Ok((Value::String(s), rest));
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    let end = text.find([' ', '\t', '#']).unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+    let value = match word {
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        _ => word
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("unsupported value {}", word))?,
+    };
+    Ok((value, rest))
+}
+
+// Profiles are named like keys, as in [profiles.anime] or [profiles."technical Japanese"]
+fn profile_name(line: &str) -> Result<String, String> {
+    let unknown = || format!("unknown table {}", line);
+    let inner = line.strip_suffix(']').ok_or("expected ]")?[1..].trim();
+    let name = inner.strip_prefix("profiles.").ok_or_else(unknown)?;
+    if let Some(quoted) = name.strip_prefix('"') {
+        return match basic_string(quoted)? {
+            (name, "") => Ok(name),
+            _ => Err(unknown()),
+        };
+    }
+    if name.is_empty() || !name.chars().all(is_bare_key) {
+        return Err(unknown());
+    }
+    Ok(name.to_string())
+}
+
+fn key_value(line: &str) -> Result<(&str, Value), String> {
+    let end = line.find(|c| !is_bare_key(c)).unwrap_or(line.len());
+    let (key, rest) = line.split_at(end);
+    if key.is_empty() {
+        return Err("expected a key".into());
+    }
+    let rest = rest.trim_start().strip_prefix('=').ok_or("expected =")?;
+    let (value, rest) = value(rest.trim_start())?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err("unexpected text after the value".into());
+    }
+    Ok((key, value))
+}
+
+/// Read the settings in a config file.
+pub fn parse(text: &str) -> Result<Config, ParseError> {
+    let mut config = Config::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message| ParseError {
+            line: i + 1,
+            message,
+        };
+        // Everything outside of a profile comes before the first one, so each starts from that
+        if line.starts_with('[') {
+            let name = profile_name(line).map_err(error)?;
+            if config.profile(&name).is_some() {
+                return Err(error(format!("profile {} is given twice", name)));
+            }
+            config.profiles.push(Settings {
+                profile: Some(name),
+                ..config.settings.clone()
+            });
+            continue;
+        }
+        let (key, value) = key_value(line).map_err(error)?;
+        let settings = config.profiles.last_mut().unwrap_or(&mut config.settings);
+        settings.set(key, value).map_err(error)?;
+    }
+    Ok(config)
+}
+
+/// Where the config file is, like ~/.config/ginkou/config.toml.
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ginkou").join("config.toml"))
+}
+
+/// Read a config file, with no settings or profiles if there's no file.
+pub fn load(path: &Path) -> Result<Config, String> {
+    match fs::read_to_string(path) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e.to_string()),
+        Ok(text) => parse(&text).map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_can_be_parsed() {
+        let text = r#"
+# Defaults for every command
+database = "/data/bank.db"
+tokenizer = "sudachi"
+dictionary = '/usr/lib/mecab/dic/mecab-ipadic-neologd'
+limit = 50 # instead of 200
+max-length = 1_000
+delimiters = "。！\n"
+fast = true
+format = "csv"
+"#;
+        let settings = parse(text).unwrap().settings;
+        assert_eq!(Some(PathBuf::from("/data/bank.db")), settings.database);
+        assert_eq!(Some(Backend::Sudachi), settings.tokenizer);
+        assert_eq!(
+            Some(PathBuf::from("/usr/lib/mecab/dic/mecab-ipadic-neologd")),
+            settings.dictionary
+        );
+        assert_eq!(Some(50), settings.limit);
+        assert_eq!(Some(1000), settings.max_length);
+        assert_eq!(None, settings.min_length);
+        assert_eq!(Some("。！\n".into()), settings.delimiters);
+        assert_eq!(Some(true), settings.fast);
+        assert_eq!(Some(export::Format::Csv), settings.format);
+    }
+
+    #[test]
+    fn mistakes_are_found_with_their_line() {
+        let error = |text| parse(text).unwrap_err();
+        assert_eq!(2, error("limit = 5\nlimt = 5").line);
+        assert_eq!(
+            "expected a number that isn't negative",
+            error("limit = -5").message
+        );
+        assert_eq!("expected a string", error("database = 5").message);
+        assert_eq!("unterminated string", error("delimiters = \"。").message);
+        assert_eq!(
+            "unknown tokenizer: kuromoji",
+            error("tokenizer = \"kuromoji\"").message
+        );
+        assert!(parse("fast = true false").is_err());
+        assert_eq!(
+            "unknown table [profile.anime]",
+            error("[profile.anime]").message
+        );
+        assert_eq!(3, error("[profiles.a]\n\n[profiles.a]").line);
+    }
+
+    #[test]
+    fn profiles_start_from_the_other_settings() {
+        let text = r#"
+limit = 50
+tokenizer = "sudachi"
+
+[profiles.anime]
+database = "/banks/anime.db"
+limit = 10
+
+[profiles.chats]
+database = "/banks/chats.db"
+key-file = "/keys/chats"
+
+[profiles."technical Japanese"]
+database = "/banks/technical.db"
+"#;
+        let config = parse(text).unwrap();
+        assert_eq!(None, config.settings.database);
+        let anime = config.profile("anime").unwrap();
+        assert_eq!(Some(PathBuf::from("/banks/anime.db")), anime.database);
+        assert_eq!(Some(10), anime.limit);
+        assert_eq!(Some(Backend::Sudachi), anime.tokenizer);
+        let chats = config.profile("chats").unwrap();
+        assert_eq!(Some(PathBuf::from("/keys/chats")), chats.key_file);
+        assert_eq!(None, anime.key_file);
+        let technical = config.profile("technical Japanese").unwrap();
+        assert_eq!(Some(50), technical.limit);
+        assert_eq!(None, config.profile("fiction"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Undoing the conjugation of a word, like 食べました back to 食べる, without a tokenizer.
+//!
+//! Each ending is taken off in every way that could make sense, so most of the forms
+//! found aren't real words. They're only guesses, to look up among the words indexed.
+use std::collections::VecDeque;
+
+const U_ROW: &str = "うくぐすつぬぶむる";
+const I_ROW: &str = "いきぎしちにびみり";
+const A_ROW: &str = "わかがさたなばまら";
+const E_ROW: &str = "えけげせてねべめれ";
+const O_ROW: &str = "おこごそとのぼもろ";
+
+/// The endings put on the stem of a verb, along with the row of kana a godan verb's stem ends in.
+///
+/// An ichidan verb's stem is just itself without る, and has no row.
+const VERB_ENDINGS: [(&str, &str); 19] = [
+    ("ます", I_ROW),
+    ("ません", I_ROW),
+    ("ました", I_ROW),
+    ("ませんでした", I_ROW),
+    ("ましょう", I_ROW),
+    ("たい", I_ROW),
+    ("ながら", I_ROW),
+    ("なさい", I_ROW),
+    ("そう", I_ROW),
+    ("ない", A_ROW),
+    ("なかった", A_ROW),
+    ("なくて", A_ROW),
+    ("ず", A_ROW),
+    ("れる", A_ROW),
+    ("せる", A_ROW),
+    ("ば", E_ROW),
+    ("る", E_ROW),
+    ("う", O_ROW),
+    ("よう", ""),
+];
+
+/// The endings of the te and ta forms, and of the forms made from them, like 食べている.
+const TE_ENDINGS: [&str; 7] = ["た", "て", "たら", "たり", "ている", "ていた", "てる"];
+
+const ADJECTIVE_ENDINGS: [&str; 7] = [
+    "かった",
+    "くない",
+    "くなかった",
+    "くて",
+    "ければ",
+    "く",
+    "さ",
+];
+
+/// How many endings are taken off at most, as in 食べたくなかった, to 食べたい, to 食べる.
+const DEPTH: usize = 3;
+
+fn without_last(text: &str) -> Option<(&str, char)> {
+    let last = text.chars().last()?;
+    Some((&text[..text.len() - last.len_utf8()], last))
+}
+
+// The dictionary form of a godan verb, from its stem ending in some row
+fn godan(stem: &str, row: &str) -> Option<String> {
+    let (rest, last) = without_last(stem)?;
+    let i = row.chars().position(|c| c == last)?;
+    U_ROW.chars().nth(i).map(|u| format!("{}{}", rest, u))
+}
+
+// The stems of する and 来る, as in 勉強します, or きた
+fn irregular(stem: &str, forms: &mut Vec<String>) {
+    if let Some((rest, last)) = without_last(stem) {
+        match last {
+            'し' | 'さ' | 'せ' => forms.push(format!("{}する", rest)),
+            'き' | 'こ' => forms.push(format!("{}くる", rest)),
+            _ => (),
+        }
+    }
+}
+
+// Godan verbs change the kana before the ending of the te form, like 書いて and 読んで
+fn undo_te(word: &str, forms: &mut Vec<String>) {
+    for ending in TE_ENDINGS.iter() {
+        if let Some(stem) = word.strip_suffix(ending) {
+            forms.push(format!("{}る", stem));
+            irregular(stem, forms);
+            if let Some((rest, last)) = without_last(stem) {
+                let dictionary: &[&str] = match last {
+                    'い' => &["く"],
+                    'し' => &["す"],
+                    'っ' => &["う", "つ", "る", "く"],
+                    _ => &[],
+                };
+                forms.extend(dictionary.iter().map(|u| format!("{}{}", rest, u)));
+            }
+        }
+        let voiced = ending.replacen('た', "だ", 1).replacen('て', "で", 1);
+        if let Some(stem) = word.strip_suffix(voiced.as_str()) {
+            if let Some((rest, last)) = without_last(stem) {
+                let dictionary: &[&str] = match last {
+                    'い' => &["ぐ"],
+                    'ん' => &["む", "ぶ", "ぬ"],
+                    _ => &[],
+                };
+                forms.extend(dictionary.iter().map(|u| format!("{}{}", rest, u)));
+            }
+        }
+    }
+}
+
+// Every form the word could come from, taking one ending off
+fn undo(word: &str) -> Vec<String> {
+    let mut forms = Vec::new();
+    for (ending, row) in VERB_ENDINGS.iter() {
+        if let Some(stem) = word.strip_suffix(ending) {
+            if stem.is_empty() {
+                continue;
+            }
+            forms.push(format!("{}る", stem));
+            forms.extend(godan(stem, row));
+            irregular(stem, &mut forms);
+        }
+    }
+    undo_te(word, &mut forms);
+    for ending in ADJECTIVE_ENDINGS.iter() {
+        if let Some(stem) = word.strip_suffix(ending) {
+            if !stem.is_empty() {
+                forms.push(format!("{}い", stem));
+            }
+        }
+    }
+    forms
+}
+
+/// The dictionary forms a word might be a conjugation of, the most likely first.
+///
+/// Forms needing fewer endings taken off come first, and the word itself is never one of them.
+pub fn candidates(word: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((word.to_string(), 0));
+    while let Some((form, depth)) = queue.pop_front() {
+        if depth == DEPTH {
+            continue;
+        }
+        for candidate in undo(&form) {
+            if candidate != word && !found.contains(&candidate) {
+                found.push(candidate.clone());
+                queue.push_back((candidate, depth + 1));
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conjugations_are_undone() {
+        let undone = |word: &str, dictionary: &str| {
+            assert!(
+                candidates(word).iter().any(|c| c == dictionary),
+                "{} should come from {}",
+                word,
+                dictionary
+            )
+        };
+        undone("食べた", "食べる");
+        undone("食べません", "食べる");
+        undone("食べています", "食べる");
+        undone("食べたくなかった", "食べる");
+        undone("書いた", "書く");
+        undone("泳いで", "泳ぐ");
+        undone("読んでいた", "読む");
+        undone("行って", "行く");
+        undone("待ちます", "待つ");
+        undone("話さない", "話す");
+        undone("勉強しなかった", "勉強する");
+        undone("きた", "くる");
+        undone("高かった", "高い");
+        assert_eq!(
+            Some("食べる"),
+            candidates("食べた").first().map(String::as_str)
+        );
+        assert!(candidates("猫").is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! Writing sentences out, with everything known about them, to analyze or share elsewhere.
+//!
+//! JSON is written as JSON Lines, with one object per sentence. CSV has a column for each part
+//! of a sentence, with lists like its tags separated by |, and the readings of one word by /.
+//! JSON exports can be read back, to import them into another database.
+use crate::json::{self, Value};
+
+/// The formats sentences can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown export format: {}", s)),
+        }
+    }
+}
+
+/// A sentence, along with everything stored with it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub id: u32,
+    pub sentence: String,
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub translations: Vec<String>,
+    /// The words the sentence is found by, each with the ways it can be read.
+    pub words: Vec<(String, Vec<String>)>,
+    pub created_at: Option<String>,
+}
+
+const COLUMNS: [&str; 8] = [
+    "id",
+    "sentence",
+    "source",
+    "tags",
+    "words",
+    "readings",
+    "translations",
+    "created_at",
+];
+
+fn strings(values: &[String]) -> Value {
+    Value::Array(values.iter().cloned().map(Value::String).collect())
+}
+
+fn optional(value: &Option<String>) -> Value {
+    value.clone().map_or(Value::Null, Value::String)
+}
+
+// Quotes are only added around fields that need them
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The first line of an export, which only CSV has.
+pub fn header(format: Format) -> Option<String> {
+    match format {
+        Format::Json => None,
+        Format::Csv => Some(COLUMNS.join(",")),
+    }
+}
+
+/// Write one sentence as a line of an export.
+pub fn line(format: Format, record: &Record) -> String {
+    match format {
+        Format::Json => {
+            let words = record
+                .words
+                .iter()
+                .map(|(word, readings)| {
+                    Value::Object(vec![
+                        ("word".into(), Value::String(word.clone())),
+                        ("readings".into(), strings(readings)),
+                    ])
+                })
+                .collect();
+            let object = Value::Object(vec![
+                ("id".into(), Value::Number(f64::from(record.id))),
+                ("sentence".into(), Value::String(record.sentence.clone())),
+                ("source".into(), optional(&record.source)),
+                ("tags".into(), strings(&record.tags)),
+                ("words".into(), Value::Array(words)),
+                ("translations".into(), strings(&record.translations)),
+                ("created_at".into(), optional(&record.created_at)),
+            ]);
+            object.to_string()
+        }
+        Format::Csv => {
+            let words: Vec<&str> = record.words.iter().map(|(w, _)| w.as_str()).collect();
+            let readings: Vec<String> = record.words.iter().map(|(_, r)| r.join("/")).collect();
+            let fields = [
+                record.id.to_string(),
+                record.sentence.clone(),
+                record.source.clone().unwrap_or_default(),
+                record.tags.join("|"),
+                words.join("|"),
+                readings.join("|"),
+                record.translations.join("|"),
+                record.created_at.clone().unwrap_or_default(),
+            ];
+            let quoted: Vec<String> = fields.iter().map(|f| quote(f)).collect();
+            quoted.join(",")
+        }
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Read back one line of a JSON export.
+pub fn parse_line(line: &str) -> Result<Record, String> {
+    let value = json::parse(line).map_err(|e| e.to_string())?;
+    let sentence = match value.get("sentence").and_then(Value::as_str) {
+        None => return Err("line has no sentence".into()),
+        Some(s) => s.to_string(),
+    };
+    let id = match value.get("id") {
+        Some(&Value::Number(n)) => n as u32,
+        _ => 0,
+    };
+    let words = match value.get("words") {
+        Some(Value::Array(words)) => words
+            .iter()
+            .filter_map(|w| {
+                let word = w.get("word").and_then(Value::as_str)?;
+                Some((word.to_string(), string_list(w.get("readings"))))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let optional = |key| value.get(key).and_then(Value::as_str).map(String::from);
+    Ok(Record {
+        id,
+        sentence,
+        source: optional("source"),
+        tags: string_list(value.get("tags")),
+        translations: string_list(value.get("translations")),
+        words,
+        created_at: optional("created_at"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Record {
+        Record {
+            id: 42,
+            sentence: "今日、猫を見た。".into(),
+            source: Some("日記".into()),
+            tags: vec!["casual".into()],
+            translations: vec!["I saw a \"cat\" today.".into()],
+            words: vec![
+                ("今日".into(), vec!["キョウ".into(), "コンニチ".into()]),
+                ("猫".into(), vec!["ネコ".into()]),
+            ],
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn records_are_written_as_json() {
+        let expected = r#"{"id":42,"sentence":"今日、猫を見た。","source":"日記","tags":["casual"],"words":[{"word":"今日","readings":["キョウ","コンニチ"]},{"word":"猫","readings":["ネコ"]}],"translations":["I saw a \"cat\" today."],"created_at":null}"#;
+        assert_eq!(expected, line(Format::Json, &record()));
+        assert_eq!(None, header(Format::Json));
+    }
+
+    #[test]
+    fn json_exports_can_be_read_back() {
+        assert_eq!(Ok(record()), parse_line(&line(Format::Json, &record())));
+        let sparse = parse_line(r#"{"sentence": "猫。"}"#).unwrap();
+        assert_eq!("猫。", sparse.sentence);
+        assert!(sparse.words.is_empty());
+        assert!(parse_line(r#"{"words": []}"#).is_err());
+    }
+
+    #[test]
+    fn records_are_written_as_csv() {
+        let expected = r#"42,今日、猫を見た。,日記,casual,今日|猫,キョウ/コンニチ|ネコ,"I saw a ""cat"" today.","#;
+        assert_eq!(expected, line(Format::Csv, &record()));
+        assert_eq!(
+            Some("id,sentence,source,tags,words,readings,translations,created_at".into()),
+            header(Format::Csv)
+        );
+    }
+}
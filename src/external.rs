@@ -0,0 +1,215 @@
+//! Running the external programs some formats are delegated to.
+use std::io;
+use std::io::{BufRead, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::thread;
+
+fn spawn(program: &str, args: &[&str], stdin: Stdio) -> io::Result<Child> {
+    spawn_with(program, args, stdin, Stdio::piped(), &[])
+}
+
+fn spawn_with(
+    program: &str,
+    args: &[&str],
+    stdin: Stdio,
+    stderr: Stdio,
+    vars: &[(&str, &str)],
+) -> io::Result<Child> {
+    Command::new(program)
+        .args(args)
+        .envs(vars.iter().copied())
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(stderr)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                let msg = format!("{} is needed for this, but wasn't found", program);
+                io::Error::new(io::ErrorKind::NotFound, msg)
+            }
+            _ => e,
+        })
+}
+
+fn failure(program: &str, stderr: &[u8]) -> io::Error {
+    let msg = format!(
+        "{} failed: {}",
+        program,
+        String::from_utf8_lossy(stderr).trim()
+    );
+    io::Error::other(msg)
+}
+
+/// Feed some input through an external program, returning what it prints.
+///
+/// The program failing to start, or exiting unsuccessfully, is reported as an error.
+pub fn filter(program: &str, args: &[&str], input: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut child = spawn(program, args, Stdio::piped())?;
+    let mut stdin = child.stdin.take().expect("child stdin was not piped");
+    // Writing from another thread avoids a deadlock when the output fills the pipe
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    if let Ok(Err(e)) = writer.join() {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e);
+        }
+    }
+    if !output.status.success() {
+        return Err(failure(program, &output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+/// What an external program prints, read as it runs.
+///
+/// The program exiting unsuccessfully is reported as an error once its output ends.
+pub struct Stream {
+    program: String,
+    child: Child,
+    stdout: ChildStdout,
+    /// What the program reports as errors, read on another thread so that it can't fill the pipe.
+    stderr: Option<thread::JoinHandle<Vec<u8>>>,
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            let status = self.child.wait()?;
+            if !status.success() {
+                let stderr = self.stderr.take().and_then(|t| t.join().ok());
+                return Err(failure(&self.program, &stderr.unwrap_or_default()));
+            }
+        }
+        Ok(read)
+    }
+}
+
+impl Drop for Stream {
+    // Stopping early shouldn't leave the program running
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Run an external program on some input, like an opened file, reading its output as it runs.
+pub fn stream<I: Into<Stdio>>(program: &str, args: &[&str], input: I) -> io::Result<Stream> {
+    let mut child = spawn(program, args, input.into())?;
+    let stdout = child.stdout.take().expect("child stdout was not piped");
+    let mut err = child.stderr.take().expect("child stderr was not piped");
+    let stderr = thread::spawn(move || {
+        let mut stderr = Vec::new();
+        let _ = err.read_to_end(&mut stderr);
+        stderr
+    });
+    Ok(Stream {
+        program: program.to_string(),
+        child,
+        stdout,
+        stderr: Some(stderr),
+    })
+}
+
+/// An external program kept running, which answers each line of input it's given.
+///
+/// An answer is everything printed before a line holding only some marker, like `EOS`.
+pub struct Pipe {
+    program: String,
+    end: &'static str,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: io::BufReader<ChildStdout>,
+}
+
+impl Pipe {
+    /// Give the program a line of input, returning its answer.
+    pub fn ask(&mut self, line: &str) -> io::Result<String> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()?;
+        let mut answer = String::new();
+        loop {
+            let mut next = String::new();
+            if self.stdout.read_line(&mut next)? == 0 {
+                let msg = format!("{} stopped before answering", self.program);
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, msg));
+            }
+            if next.trim_end() == self.end {
+                return Ok(answer);
+            }
+            answer.push_str(&next);
+        }
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Start an external program answering lines of input, each answer ending with a marker.
+///
+/// What the program reports as errors is shown directly, since it isn't waited on.
+pub fn pipe(program: &str, args: &[&str], end: &'static str) -> io::Result<Pipe> {
+    // Python buffers its output when it isn't a terminal, which would leave us waiting
+    let vars = [("PYTHONUNBUFFERED", "1")];
+    let mut child = spawn_with(program, args, Stdio::piped(), Stdio::inherit(), &vars)?;
+    let stdin = child.stdin.take().expect("child stdin was not piped");
+    let stdout = child.stdout.take().expect("child stdout was not piped");
+    Ok(Pipe {
+        program: program.to_string(),
+        end,
+        child,
+        stdin,
+        stdout: io::BufReader::new(stdout),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_returns_program_output() {
+        let out = filter("cat", &[], "猫。".as_bytes().to_vec()).unwrap();
+        assert_eq!("猫。".as_bytes().to_vec(), out);
+    }
+
+    #[test]
+    fn stream_reports_failures_at_the_end() {
+        let mut out = String::new();
+        let mut ok = stream("echo", &["猫"], Stdio::null()).unwrap();
+        ok.read_to_string(&mut out).unwrap();
+        assert_eq!("猫\n", out);
+        let mut failing = stream("false", &[], Stdio::null()).unwrap();
+        assert!(failing.read_to_string(&mut out).is_err());
+    }
+
+    #[test]
+    fn streams_keep_reading_however_much_is_reported() {
+        let script = "head -c 1000000 /dev/zero >&2; echo 猫; echo 失敗 >&2; exit 1";
+        let mut out = String::new();
+        let mut noisy = stream("sh", &["-c", script], Stdio::null()).unwrap();
+        let err = noisy.read_to_string(&mut out).unwrap_err();
+        assert_eq!("猫\n", out);
+        assert!(err.to_string().ends_with("失敗"));
+    }
+
+    #[test]
+    fn pipes_answer_each_line() {
+        let script = "while read line; do echo \"$line\"; echo \"$line\"; echo EOS; done";
+        let mut pipe = pipe("sh", &["-c", script], "EOS").unwrap();
+        assert_eq!("猫\n猫\n", pipe.ask("猫").unwrap());
+        assert_eq!("犬\n犬\n", pipe.ask("犬").unwrap());
+    }
+
+    #[test]
+    fn filter_reports_missing_programs() {
+        let err = filter("ginkou-no-such-program", &[], Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+    }
+}
@@ -0,0 +1,90 @@
+//! Reading the notes of decks exported from Anki.
+//!
+//! An `.apkg` is a zip archive holding the collection as an SQLite database.
+//! Each note stores its fields in a single column, separated by 0x1f.
+use std::io;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use super::markup::strip_tags;
+use super::sqlite::query_copy;
+use crate::zip::Archive;
+
+// Newer versions of Anki store the collection under the later names.
+const COLLECTIONS: [&str; 2] = ["collection.anki21", "collection.anki2"];
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+fn is_kana(c: char) -> bool {
+    ('\u{3040}'..='\u{30ff}').contains(&c)
+}
+
+// Remove furigana written like 漢字[かんじ].
+fn strip_furigana(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let reading = rest[start + 1..]
+            .find(']')
+            .map(|e| &rest[start + 1..start + 1 + e]);
+        match reading {
+            Some(r) if !r.is_empty() && r.chars().all(is_kana) => {
+                out.push_str(&rest[..start]);
+                rest = &rest[start + r.len() + 2..];
+            }
+            _ => {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn read_field(conn: &Connection, field: usize) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT flds FROM notes ORDER BY id")?;
+    let rows = stmt.query_map(NO_PARAMS, |row| row.get(0))?;
+    let mut texts = Vec::new();
+    for row in rows {
+        let fields: String = row?;
+        if let Some(text) = fields.split(FIELD_SEPARATOR).nth(field) {
+            texts.push(strip_furigana(&strip_tags(text)));
+        }
+    }
+    Ok(texts)
+}
+
+/// Read a given field, counting from 0, of every note in a deck.
+pub fn read_notes(data: Vec<u8>, field: usize) -> io::Result<Vec<String>> {
+    let archive = Archive::new(data)?;
+    let name = COLLECTIONS
+        .iter()
+        .find(|name| archive.names().any(|n| n == **name))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no collection in deck"))?;
+    query_copy(&archive.read(name)?, |conn| read_field(conn, field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn furigana_is_removed() {
+        assert_eq!(
+            "猫を 見た。[注]",
+            strip_furigana("猫[ねこ]を 見[み]た。[注]")
+        );
+    }
+
+    #[test]
+    fn fields_are_read_from_notes() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE notes(id INTEGER PRIMARY KEY, flds TEXT);
+             INSERT INTO notes VALUES(1, 'cat\u{1f}<b>猫</b>を 見[み]た。');
+             INSERT INTO notes VALUES(2, 'only one field');",
+        )?;
+        assert_eq!(vec!["猫を 見た。"], read_field(&conn, 1)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+//! Reading texts from Aozora Bunko.
+//!
+//! These are plain text, but annotated with ruby like `漢字《かんじ》`, with
+//! `｜` marking where a ruby base starts, and with editorial notes like
+//! `［＃改ページ］`. They also come with a header and a colophon.
+
+// The line of dashes surrounding the explanation of the notation.
+const RULE: &str = "-------------------------------------------------------";
+// The colophon, giving the source of the text, starts with this.
+const COLOPHON: &str = "底本：";
+
+fn strip_annotations(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut closing = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(close) = closing {
+            if c == close {
+                closing = None;
+            }
+            continue;
+        }
+        match c {
+            '《' => closing = Some('》'),
+            '［' if chars.peek() == Some(&'＃') => closing = Some('］'),
+            '｜' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Remove the header, ruby, notes, and colophon from an Aozora Bunko text.
+pub fn strip_markup(text: &str) -> String {
+    let mut body = text.trim_start_matches('\u{feff}');
+    let mut rules = body.match_indices(RULE);
+    if let (Some(_), Some((end, _))) = (rules.next(), rules.next()) {
+        body = &body[end + RULE.len()..];
+    }
+    if let Some(end) = body.find(COLOPHON) {
+        body = &body[..end];
+    }
+    strip_annotations(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_markup_keeps_only_the_text() {
+        let text = "羅生門\n芥川龍之介\n\n\
+                    -------------------------------------------------------\n\
+                    【テキスト中に現れる記号について】\n《》：ルビ\n\
+                    -------------------------------------------------------\n\
+                    ［＃８字下げ］一［＃「一」は中見出し］\n\
+                    ある日の暮方の事である。一人の｜下人《げにん》が、\
+                    羅生門《らしょうもん》の下で雨やみを待っていた。\n\n\
+                    底本：「芥川龍之介全集1」ちくま文庫、筑摩書房\n";
+        assert_eq!(
+            "\n一\nある日の暮方の事である。一人の下人が、羅生門の下で雨やみを待っていた。\n\n",
+            strip_markup(text)
+        );
+    }
+}
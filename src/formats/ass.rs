@@ -0,0 +1,76 @@
+//! Reading the dialogue out of Advanced SubStation Alpha subtitles.
+//!
+//! Only `Dialogue:` events are read. Their text is the last field of the
+//! event, and may contain override tags like `{\pos(10,10)}`.
+
+// The fields of an event when the file doesn't say otherwise.
+const DEFAULT_FIELDS: usize = 10;
+
+fn clean_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_override = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => in_override = true,
+            '}' if in_override => in_override = false,
+            _ if in_override => {}
+            '\\' => match chars.peek() {
+                // Line breaks just continue the same line of dialogue
+                Some('N') | Some('n') => {
+                    chars.next();
+                }
+                Some('h') => {
+                    chars.next();
+                    out.push(' ');
+                }
+                _ => out.push(c),
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Pull out the text of each line of dialogue, in order.
+pub fn cues(text: &str) -> Vec<String> {
+    let mut cues = Vec::new();
+    let mut fields = DEFAULT_FIELDS;
+    let mut in_events = false;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').count();
+        } else if let Some(event) = line.strip_prefix("Dialogue:") {
+            if let Some(text) = event.splitn(fields, ',').nth(fields - 1) {
+                let text = clean_text(text);
+                if !text.trim().is_empty() {
+                    cues.push(text);
+                }
+            }
+        }
+    }
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cues_read_only_dialogue_text() {
+        let ass = "[Script Info]\nTitle: テスト\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,無視\n\
+                   Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\pos(10,10)}待って、\\Nまだ行かないで\n\
+                   Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,{\\i1}{\\b1}\n";
+        assert_eq!(vec!["待って、まだ行かないで"], cues(ass));
+    }
+}
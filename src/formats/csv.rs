@@ -0,0 +1,103 @@
+//! Reading spreadsheets saved as CSV or TSV.
+//!
+//! Fields may be quoted, in which case they can contain the delimiter,
+//! newlines, and quotes written twice.
+use std::io;
+use std::io::BufRead;
+
+/// An iterator over the records of a CSV file.
+pub struct Records<R> {
+    reader: R,
+    delimiter: char,
+}
+
+impl<R: BufRead> Records<R> {
+    pub fn new(reader: R, delimiter: char) -> Self {
+        Records { reader, delimiter }
+    }
+
+    // Parse a record, returning None if it continues onto the next line.
+    fn parse(&self, line: &str) -> Option<Vec<String>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if quoted {
+                if c != '"' {
+                    field.push(c);
+                } else if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            } else if c == '"' && field.is_empty() {
+                quoted = true;
+            } else if c == self.delimiter {
+                fields.push(field);
+                field = String::new();
+            } else {
+                field.push(c);
+            }
+        }
+        if quoted {
+            return None;
+        }
+        fields.push(field);
+        Some(fields)
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = String::new();
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Err(e) => return Some(Err(e)),
+                // An unterminated quote just takes up the rest of the file
+                Ok(0) if !record.is_empty() => {
+                    record.push('"');
+                    return self.parse(&record).map(Ok);
+                }
+                Ok(0) => return None,
+                Ok(_) => {}
+            }
+            record.push_str(&line);
+            let trimmed = record.trim_end_matches(['\r', '\n']);
+            if let Some(fields) = self.parse(trimmed) {
+                return Some(Ok(fields));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_newlines_are_handled() {
+        let csv = "語,例文\r\n猫,\"猫が\n「ニャー」と\"\"鳴いた\"\"。\"\n犬,犬だ。\n";
+        let records: Vec<Vec<String>> = Records::new(csv.as_bytes(), ',')
+            .map(Result::unwrap)
+            .collect();
+        let expected: Vec<Vec<String>> = vec![
+            vec!["語".into(), "例文".into()],
+            vec!["猫".into(), "猫が\n「ニャー」と\"鳴いた\"。".into()],
+            vec!["犬".into(), "犬だ。".into()],
+        ];
+        assert_eq!(expected, records);
+    }
+
+    #[test]
+    fn tabs_can_be_delimiters() {
+        let mut records = Records::new("a,b\tc\n".as_bytes(), '\t');
+        let expected: Vec<String> = vec!["a,b".into(), "c".into()];
+        assert_eq!(expected, records.next().unwrap().unwrap());
+        assert!(records.next().is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Decoding text in the legacy Japanese encodings.
+//!
+//! Older texts, like most of Aozora Bunko, are in Shift-JIS or EUC-JP. These are
+//! recognised by which of them the bytes are valid in, and converted with `iconv`.
+use std::io;
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::external;
+
+// How much of the input is looked at to decide whether it's UTF-8
+const SNIFF_LENGTH: u64 = 64 * 1024;
+const BYTE_ORDER_MARK: &[u8] = b"\xef\xbb\xbf";
+
+/// The encodings text can be read in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    /// Shift-JIS, including the Windows extensions of CP932.
+    ShiftJis,
+    EucJp,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "shift-jis" | "shiftjis" | "sjis" | "cp932" | "windows-31j" => Ok(Encoding::ShiftJis),
+            "euc-jp" | "eucjp" => Ok(Encoding::EucJp),
+            _ => Err(format!("unknown encoding: {}", s)),
+        }
+    }
+}
+
+// Count the bytes that can't be part of a Shift-JIS character
+fn shift_jis_errors(bytes: &[u8]) -> usize {
+    let mut errors = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x00..=0x80 | 0xa1..=0xdf => i += 1,
+            0x81..=0x9f | 0xe0..=0xfc => {
+                match bytes.get(i + 1) {
+                    Some(0x40..=0x7e) | Some(0x80..=0xfc) => {}
+                    _ => errors += 1,
+                }
+                i += 2;
+            }
+            _ => {
+                errors += 1;
+                i += 1;
+            }
+        }
+    }
+    errors
+}
+
+// Count the bytes that can't be part of an EUC-JP character
+fn euc_jp_errors(bytes: &[u8]) -> usize {
+    let mut errors = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let trail = match bytes[i] {
+            0x00..=0x7f => 0,
+            // Half width katakana
+            0x8e => 1,
+            // JIS X 0212
+            0x8f => 2,
+            0xa1..=0xfe => 1,
+            _ => {
+                errors += 1;
+                i += 1;
+                continue;
+            }
+        };
+        for j in 1..=trail {
+            match bytes.get(i + j) {
+                Some(0xa1..=0xfe) => {}
+                _ => errors += 1,
+            }
+        }
+        i += 1 + trail;
+    }
+    errors
+}
+
+/// Guess the encoding some text is in.
+///
+/// Text that is valid UTF-8 is taken to be UTF-8, and otherwise whichever of
+/// Shift-JIS or EUC-JP fits best is chosen.
+pub fn detect(bytes: &[u8]) -> Encoding {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return Encoding::Utf8,
+        // The input might have been cut off in the middle of a character
+        Err(e) if e.error_len().is_none() => return Encoding::Utf8,
+        Err(_) => {}
+    }
+    // EUC-JP is almost always valid Shift-JIS too, read as half width katakana,
+    // while kana in Shift-JIS are never valid EUC-JP
+    let euc_jp = euc_jp_errors(bytes);
+    if euc_jp == 0 || euc_jp < shift_jis_errors(bytes) {
+        Encoding::EucJp
+    } else {
+        Encoding::ShiftJis
+    }
+}
+
+/// Convert text in some encoding to UTF-8.
+pub fn decode(bytes: Vec<u8>, encoding: Encoding) -> io::Result<Vec<u8>> {
+    let from = match encoding {
+        Encoding::Utf8 => return Ok(bytes),
+        Encoding::ShiftJis => "CP932",
+        Encoding::EucJp => "EUC-JP",
+    };
+    external::filter("iconv", &["-f", from, "-t", "UTF-8"], bytes)
+}
+
+/// Read some input as UTF-8, converting it if it's in another encoding.
+///
+/// Without an encoding, it is detected from the start of the input. UTF-8 input
+/// is still read as it comes, without its byte order mark, but anything else
+/// needs to be read in full first.
+pub fn to_utf8<'a, R: Read + 'a>(
+    mut input: R,
+    encoding: Option<Encoding>,
+) -> io::Result<Box<dyn Read + 'a>> {
+    let mut start = Vec::new();
+    input.by_ref().take(SNIFF_LENGTH).read_to_end(&mut start)?;
+    let encoding = encoding.unwrap_or_else(|| detect(&start));
+    if encoding == Encoding::Utf8 {
+        if start.starts_with(BYTE_ORDER_MARK) {
+            start.drain(..BYTE_ORDER_MARK.len());
+        }
+        return Ok(Box::new(io::Cursor::new(start).chain(input)));
+    }
+    input.read_to_end(&mut start)?;
+    Ok(Box::new(io::Cursor::new(decode(start, encoding)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings_are_detected() {
+        // 猫だ。
+        let shift_jis = [0x94, 0x4c, 0x82, 0xbe, 0x81, 0x42];
+        let euc_jp = [0xc7, 0xad, 0xa4, 0xc0, 0xa1, 0xa3];
+        assert_eq!(Encoding::ShiftJis, detect(&shift_jis));
+        assert_eq!(Encoding::EucJp, detect(&euc_jp));
+        assert_eq!(Encoding::Utf8, detect("猫だ。".as_bytes()));
+        assert_eq!(Encoding::Utf8, detect(&"猫だ。".as_bytes()[..4]));
+    }
+
+    #[test]
+    fn byte_order_marks_are_skipped() {
+        let input = io::Cursor::new(b"\xef\xbb\xbf\xe7\x8c\xab".to_vec());
+        let mut text = String::new();
+        to_utf8(input, None)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!("猫", text);
+    }
+
+    #[test]
+    fn encodings_can_be_named() {
+        assert_eq!(Ok(Encoding::ShiftJis), "Shift_JIS".parse());
+        assert_eq!(Ok(Encoding::EucJp), "euc-jp".parse());
+        assert_eq!(Ok(Encoding::Utf8), "UTF8".parse());
+        assert!("latin1".parse::<Encoding>().is_err());
+    }
+}
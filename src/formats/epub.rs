@@ -0,0 +1,114 @@
+//! Reading the text of EPUB books.
+//!
+//! An EPUB is a zip archive of XHTML chapters. The container file points at
+//! a package document, whose spine lists the chapters in reading order.
+use std::collections::HashMap;
+use std::io;
+
+use super::markup::{attribute, strip_tags, tags};
+use crate::zip::Archive;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn member_string(archive: &Archive, name: &str) -> io::Result<String> {
+    super::utf8(archive.read(name)?)
+}
+
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = href.get(i + 1..i + 3);
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(b) if bytes[i] == b'%' => {
+                out.push(b);
+                i += 3;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Resolve an href relative to the directory of the package document.
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    percent_decode(&parts.join("/"))
+}
+
+// The paths of the chapters listed in the spine of a package document.
+fn spine_paths(opf_path: &str, opf: &str) -> Vec<String> {
+    let base = match opf_path.rfind('/') {
+        None => "",
+        Some(i) => &opf_path[..i],
+    };
+    let mut manifest = HashMap::new();
+    for item in tags(opf, "item") {
+        if let (Some(id), Some(href)) = (attribute(item, "id"), attribute(item, "href")) {
+            manifest.insert(id, href);
+        }
+    }
+    tags(opf, "itemref")
+        .into_iter()
+        .filter_map(|itemref| attribute(itemref, "idref"))
+        .filter_map(|id| manifest.get(&id))
+        .map(|href| resolve(base, href))
+        .collect()
+}
+
+/// Read the text of every chapter in an EPUB, in reading order.
+pub fn read_text(data: Vec<u8>) -> io::Result<String> {
+    let archive = Archive::new(data)?;
+    let container = member_string(&archive, "META-INF/container.xml")?;
+    let opf_path = tags(&container, "rootfile")
+        .into_iter()
+        .filter_map(|rootfile| attribute(rootfile, "full-path"))
+        .next()
+        .ok_or_else(|| invalid("epub container has no rootfile"))?;
+    let opf = member_string(&archive, &opf_path)?;
+    let mut chapters = spine_paths(&opf_path, &opf);
+    if chapters.is_empty() {
+        chapters = archive
+            .names()
+            .filter(|n| n.ends_with(".xhtml") || n.ends_with(".html"))
+            .map(String::from)
+            .collect();
+    }
+    let mut text = String::new();
+    for chapter in chapters {
+        text.push_str(&strip_tags(&member_string(&archive, &chapter)?));
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spine_is_read_in_order() {
+        let opf = r#"<package><manifest>
+            <item id="b" href="text/b%20c.xhtml" media-type="application/xhtml+xml"/>
+            <item id="a" href="../a.xhtml" media-type="application/xhtml+xml"/>
+            </manifest><spine><itemref idref="a"/><itemref idref="b"/></spine></package>"#;
+        let paths = spine_paths("OEBPS/content.opf", opf);
+        assert_eq!(vec!["a.xhtml", "OEBPS/text/b c.xhtml"], paths);
+    }
+}
@@ -0,0 +1,68 @@
+//! Reading JSON Lines, with a sentence and its metadata on each line.
+//!
+//! Each line is an object like `{"sentence": "...", "source": "..."}`.
+//! Every member besides the sentence is kept as metadata.
+use crate::json::{self, Value};
+use crate::{Sentence, SentenceError};
+
+fn metadata_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(values) => {
+            let parts: Vec<String> = values.iter().filter_map(metadata_value).collect();
+            Some(parts.join(","))
+        }
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+/// Parse one line, which is `None` if the line is blank.
+pub fn parse_line(line: &str) -> Result<Option<Sentence>, SentenceError> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    let value = json::parse(line).map_err(|e| SentenceError::Invalid(e.to_string()))?;
+    let text = match value.get("sentence").and_then(Value::as_str) {
+        None => return Err(SentenceError::Invalid("line has no sentence".into())),
+        Some(t) => t,
+    };
+    let mut sentence = Sentence::from(text);
+    if let Value::Object(members) = &value {
+        for (key, value) in members {
+            if key == "sentence" {
+                continue;
+            }
+            if let Some(value) = metadata_value(value) {
+                sentence.metadata.push((key.clone(), value));
+            }
+        }
+    }
+    Ok(Some(sentence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_keep_their_metadata() {
+        let line = r#"{"sentence": "猫を 見た。", "source": "ノート", "tags": ["anime", "casual"], "page": 3}"#;
+        let sentence = parse_line(line).unwrap().unwrap();
+        assert_eq!("猫を見た。", sentence.text);
+        let expected: Vec<(String, String)> = vec![
+            ("source".into(), "ノート".into()),
+            ("tags".into(), "anime,casual".into()),
+            ("page".into(), "3".into()),
+        ];
+        assert_eq!(expected, sentence.metadata);
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert!(parse_line("  ").unwrap().is_none());
+        assert!(parse_line(r#"{"source": "ノート"}"#).is_err());
+        assert!(parse_line("{").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Reading the words looked up on a Kindle, from its `vocab.db`.
+//!
+//! Every lookup records the word, its dictionary form, and the sentence
+//! it was looked up in, which Kindle calls its usage.
+use std::io;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use super::sqlite::query_copy;
+use crate::Sentence;
+
+const SQL_JAPANESE_LOOKUPS: &str = "SELECT WORDS.stem, LOOKUPS.usage FROM LOOKUPS
+    JOIN WORDS ON WORDS.id = LOOKUPS.word_key
+    WHERE WORDS.lang = 'ja' AND LOOKUPS.usage IS NOT NULL
+    ORDER BY LOOKUPS.timestamp";
+
+fn read_lookups(conn: &Connection) -> rusqlite::Result<Vec<Sentence>> {
+    let mut stmt = conn.prepare(SQL_JAPANESE_LOOKUPS)?;
+    let rows = stmt.query_map(NO_PARAMS, |row| {
+        let stem: String = row.get(0)?;
+        let usage: String = row.get(1)?;
+        let mut sentence = Sentence::from(usage.as_str());
+        sentence.words.push(stem);
+        Ok(sentence)
+    })?;
+    rows.collect()
+}
+
+/// Read every Japanese lookup, as its sentence along with the word looked up.
+pub fn read_vocab(data: Vec<u8>) -> io::Result<Vec<Sentence>> {
+    query_copy(&data, read_lookups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_include_their_word() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE WORDS(id TEXT PRIMARY KEY, word TEXT, stem TEXT, lang TEXT);
+             CREATE TABLE LOOKUPS(id TEXT PRIMARY KEY, word_key TEXT, usage TEXT, timestamp INTEGER);
+             INSERT INTO WORDS VALUES('ja:見た', '見た', '見る', 'ja');
+             INSERT INTO WORDS VALUES('en:saw', 'saw', 'see', 'en');
+             INSERT INTO LOOKUPS VALUES('a', 'ja:見た', '猫を 見た。', 1);
+             INSERT INTO LOOKUPS VALUES('b', 'en:saw', 'I saw it.', 2);",
+        )?;
+        let lookups = read_lookups(&conn)?;
+        assert_eq!(1, lookups.len());
+        assert_eq!("猫を見た。", lookups[0].text);
+        assert_eq!(vec!["見る"], lookups[0].words);
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Reading the text of Markdown documents.
+//!
+//! Code blocks are dropped, and the syntax for headings, lists, quotes, links,
+//! and emphasis is removed, leaving the text around it.
+
+// Remove the marker starting a heading, quote, or list item.
+fn strip_block_marker(line: &str) -> &str {
+    let mut line = line.trim_start();
+    loop {
+        let stripped = if line.starts_with('#') {
+            line.trim_start_matches('#')
+        } else if let Some(quoted) = line.strip_prefix('>') {
+            quoted
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|m| line.strip_prefix(m)) {
+            item
+        } else {
+            let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+            let after = &line[digits..];
+            match after
+                .strip_prefix(". ")
+                .or_else(|| after.strip_prefix(") "))
+            {
+                Some(item) if digits > 0 => item,
+                _ => return line,
+            }
+        };
+        line = stripped.trim_start();
+    }
+}
+
+// Whether a line is a horizontal rule, or underlines a heading.
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3
+        && ['-', '=', '*', '_']
+            .iter()
+            .any(|&c| line.chars().all(|l| l == c))
+}
+
+// Remove the syntax inside of a line, keeping the text of links and code.
+fn strip_inline(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        match c {
+            '`' | '*' => {
+                rest = after;
+                continue;
+            }
+            '~' if after.starts_with('~') => {
+                rest = &after[1..];
+                continue;
+            }
+            '_' if !out.ends_with(|c: char| c.is_ascii_alphanumeric()) || after.is_empty() => {
+                rest = after;
+                continue;
+            }
+            '!' if after.starts_with('[') => {
+                // Images have no text of their own
+                if let Some((_, next)) = link(after) {
+                    rest = next;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((text, next)) = link(rest) {
+                    out.push_str(&strip_inline(text));
+                    rest = next;
+                    continue;
+                }
+            }
+            '<' if after.starts_with("http://") || after.starts_with("https://") => {
+                if let Some(end) = after.find('>') {
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        rest = after;
+    }
+    out
+}
+
+// Split a link like `[text](url)` or `[text][ref]` into its text, and what follows it.
+fn link(s: &str) -> Option<(&str, &str)> {
+    let close = s.find(']')?;
+    let text = &s[1..close];
+    let after = &s[close + 1..];
+    let end = if after.starts_with('(') {
+        after.find(')')? + 1
+    } else if after.starts_with('[') {
+        after.find(']')? + 1
+    } else {
+        return None;
+    };
+    Some((text, &after[end..]))
+}
+
+/// Read the blocks of text in a document, like paragraphs, headings, and list items.
+pub fn blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    let mut fence: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        // Lines in a quote continue it, but each heading or list item is its own block
+        let marked =
+            strip_block_marker(trimmed).len() != trimmed.len() && !trimmed.starts_with('>');
+        let fenced = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if (marked || fenced || trimmed.is_empty() || is_rule(trimmed)) && !current.is_empty() {
+            blocks.push(current);
+            current = String::new();
+        }
+        if fenced {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        // Reference definitions, like `[1]: https://example.com`
+        if trimmed.starts_with('[') && trimmed.contains("]:") {
+            continue;
+        }
+        if trimmed.is_empty() || is_rule(trimmed) {
+            continue;
+        }
+        current.push_str(&strip_inline(strip_block_marker(trimmed)));
+        current.push('\n');
+        if trimmed.starts_with('#') {
+            blocks.push(current);
+            current = String::new();
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_syntax_is_removed() {
+        assert_eq!(
+            "猫を見た。リンク、snake_case。",
+            strip_inline(
+                "**猫**を`見た`。[リンク](https://a.jp)、![絵](b.png)snake_case。<https://c.jp>"
+            )
+        );
+    }
+
+    #[test]
+    fn blocks_skip_code_and_markers() {
+        let doc = "# 日記\n\n今日は\n*雨*だった。\n\n```rust\nlet x = 1;\n```\n\n- 一つ目\n1. 二つ目\n\n> 引用\n> です\n\n---\n[1]: https://a.jp\n";
+        assert_eq!(
+            vec![
+                "日記\n",
+                "今日は\n雨だった。\n",
+                "一つ目\n",
+                "二つ目\n",
+                "引用\nです\n"
+            ],
+            blocks(doc)
+        );
+    }
+}
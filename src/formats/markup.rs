@@ -0,0 +1,320 @@
+//! Helpers for pulling text out of XML and HTML documents.
+//!
+//! These don't aim to be complete parsers, only to be forgiving enough to
+//! handle what real books and pages contain.
+
+// Elements whose contents are never part of the text, like ruby readings.
+const SKIPPED_ELEMENTS: [&str; 7] = [
+    "rt", "rp", "script", "style", "noscript", "template", "head",
+];
+// Elements around the article of a web page, holding menus and links instead.
+const PAGE_ELEMENTS: [&str; 5] = ["nav", "header", "footer", "aside", "form"];
+// Elements that separate blocks of text.
+const BLOCK_ELEMENTS: [&str; 13] = [
+    "p",
+    "div",
+    "br",
+    "li",
+    "tr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "section",
+];
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Find the contents of the opening tags with a given name.
+///
+/// For `<item id="a" href="b"/>`, this yields `item id="a" href="b"/`.
+pub fn tags<'a>(doc: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = doc;
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let end = match after.find('>') {
+            None => break,
+            Some(e) => e,
+        };
+        let tag = &after[..end];
+        if tag_name(tag) == name && !tag.starts_with('/') {
+            found.push(tag);
+        }
+        rest = &after[end + 1..];
+    }
+    found
+}
+
+/// Find the contents of the element with a given id, including its tags.
+///
+/// Elements of the same kind nested inside of it are skipped over, so that
+/// its contents end at its own closing tag.
+pub fn element_by_id<'a>(doc: &'a str, id: &str) -> Option<&'a str> {
+    let mut start = None;
+    let mut depth = 0;
+    let mut name = String::new();
+    let mut at = 0;
+    while let Some(open) = doc[at..].find('<') {
+        let tag_start = at + open + 1;
+        let end = tag_start + doc[tag_start..].find('>')?;
+        let tag = &doc[tag_start..end];
+        at = end + 1;
+        match start {
+            None => {
+                if !tag.starts_with('/') && attribute(tag, "id").as_deref() == Some(id) {
+                    start = Some(at);
+                    name = tag_name(tag);
+                    depth = 1;
+                }
+            }
+            Some(s) => {
+                if tag_name(tag) != name || tag.ends_with('/') {
+                    continue;
+                }
+                if tag.starts_with('/') {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&doc[s..tag_start - 1]);
+                    }
+                } else {
+                    depth += 1;
+                }
+            }
+        }
+    }
+    start.map(|s| &doc[s..])
+}
+
+/// Read the value of an attribute from the contents of a tag.
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let preceded = rest[..i].ends_with(char::is_whitespace);
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+        if !preceded || !after.starts_with('=') {
+            continue;
+        }
+        let value = after[1..].trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &value[1..];
+        let end = value.find(quote)?;
+        return Some(decode_entities(&value[..end]));
+    }
+    None
+}
+
+// The character a reference between `&` and `;` stands for, if it's known
+fn reference(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+            u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32)
+        }
+        _ if entity.starts_with('#') => entity[1..]
+            .parse::<u32>()
+            .ok()
+            .and_then(std::char::from_u32),
+        _ => None,
+    }
+}
+
+// Find the next known reference, with where it starts and ends, and what it stands for
+fn next_reference(text: &str) -> Option<(usize, usize, char)> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('&') {
+        let start = offset + start;
+        let rest = &text[start..];
+        if let Some(end) = rest.find(';').filter(|&e| e <= 10) {
+            if let Some(c) = reference(&rest[1..end]) {
+                return Some((start, start + end + 1, c));
+            }
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+/// Check whether some text contains any references that [`decode_entities`] would replace.
+///
+/// Text containing them was most likely scraped from the web without being unescaped.
+pub fn has_references(text: &str) -> bool {
+    next_reference(text).is_some()
+}
+
+/// Replace character and entity references with the characters they stand for.
+///
+/// Unknown entities are left untouched.
+pub fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end, c)) = next_reference(rest) {
+        out.push_str(&rest[..start]);
+        out.push(c);
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Extract the readable text from the body of an XHTML or HTML document.
+///
+/// Block level elements are separated by newlines. Ruby readings, scripts
+/// and styles are dropped, so that only the base text remains.
+pub fn strip_tags(doc: &str) -> String {
+    strip_element(doc, "body", &[])
+}
+
+/// Extract the text of a fragment of markup, also dropping the contents of some elements.
+pub fn strip_tags_skipping(doc: &str, skipped: &[&str]) -> String {
+    strip_element(doc, "body", skipped)
+}
+
+/// Extract the text of the article in a web page.
+///
+/// This is the contents of the `<article>` or `<main>` element, if the page has one,
+/// and otherwise the whole body, without the navigation, headers, and footers around it.
+pub fn article_text(doc: &str) -> String {
+    let lower = doc.to_ascii_lowercase();
+    let element = ["article", "main"]
+        .iter()
+        .find(|name| !tags(&lower, name).is_empty())
+        .unwrap_or(&"body");
+    strip_element(doc, element, &PAGE_ELEMENTS)
+}
+
+// The text between the first opening and last closing tag of an element,
+// or all of it if that element is missing
+fn strip_element(doc: &str, element: &str, skipped: &[&str]) -> String {
+    let lower = doc.to_ascii_lowercase();
+    let start = lower.find(&format!("<{}", element)).unwrap_or(0);
+    let end = lower
+        .rfind(&format!("</{}", element))
+        .filter(|&e| e >= start)
+        .unwrap_or(doc.len());
+    let body = &doc[start..end];
+    let mut out = String::new();
+    let mut skipping: Option<String> = None;
+    let mut rest = body;
+    while let Some(start) = rest.find('<') {
+        if skipping.is_none() {
+            out.push_str(&decode_entities(&rest[..start]));
+        }
+        let after = &rest[start + 1..];
+        if after.starts_with("!--") {
+            rest = match after.find("-->") {
+                None => "",
+                Some(end) => &after[end + 3..],
+            };
+            continue;
+        }
+        let end = match after.find('>') {
+            None => {
+                rest = "";
+                break;
+            }
+            Some(e) => e,
+        };
+        let tag = &after[..end];
+        let name = tag_name(tag);
+        let closing = tag.starts_with('/');
+        match skipping {
+            Some(ref skipped) => {
+                if closing && *skipped == name {
+                    skipping = None;
+                }
+            }
+            None => {
+                let skip =
+                    SKIPPED_ELEMENTS.contains(&name.as_str()) || skipped.contains(&name.as_str());
+                if !closing && !tag.ends_with('/') && skip {
+                    skipping = Some(name);
+                } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    out.push('\n');
+                }
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    if skipping.is_none() {
+        out.push_str(&decode_entities(rest));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_tags_keeps_only_body_text() {
+        let doc = "<html><head><title>題</title></head>\
+                   <body><p>猫が<ruby>鳴<rt>な</rt></ruby>いた。</p><p>A&amp;B</p></body></html>";
+        assert_eq!("\n猫が鳴いた。\n\nA&B\n", strip_tags(doc));
+    }
+
+    #[test]
+    fn strip_tags_drops_scripts_and_styles() {
+        let doc = "<HTML><BODY><style>p { color: red; }</style><P>本文。</P>\
+                   <script>if (a < b) { go(); }</script><!-- 注 --></BODY></HTML>";
+        assert_eq!("\n本文。\n", strip_tags(doc));
+    }
+
+    #[test]
+    fn article_text_skips_page_furniture() {
+        let doc = "<html><body><header><nav>ホーム</nav></header>\
+                   <main><h1>記事</h1><aside>広告</aside><p>本文。</p></main>\
+                   <footer>著作権</footer></body></html>";
+        assert_eq!("\n記事\n\n本文。\n", article_text(doc));
+        let doc = "<body><nav>メニュー</nav><div>本文。</div></body>";
+        assert_eq!("\n本文。\n", article_text(doc));
+    }
+
+    #[test]
+    fn elements_can_be_found_by_id() {
+        let doc = r#"<div><div id="body"><div>一</div><p>二</p></div><div>三</div></div>"#;
+        assert_eq!(Some("<div>一</div><p>二</p>"), element_by_id(doc, "body"));
+        assert_eq!(None, element_by_id(doc, "missing"));
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(
+            "。&。<&foo;",
+            decode_entities("&#12290;&amp;&#x3002;&lt;&foo;")
+        );
+        assert_eq!("& &&amp;", decode_entities("& &&amp;amp;"));
+        assert!(has_references("猫&#12290;"));
+        assert!(!has_references("AT&T; & co"));
+    }
+
+    #[test]
+    fn attributes_can_be_read_from_tags() {
+        let doc = r#"<manifest><item id="c1" href="text/c1.xhtml"/><itemref idref='c1'/>"#;
+        let items = tags(doc, "item");
+        assert_eq!(1, items.len());
+        assert_eq!(Some("text/c1.xhtml".into()), attribute(items[0], "href"));
+        assert_eq!(Some("c1".into()), attribute(items[0], "id"));
+        assert_eq!(None, attribute(items[0], "idref"));
+    }
+}
@@ -0,0 +1,562 @@
+//! Readers for the different kinds of files sentences can be added from.
+use std::cell::Cell;
+use std::io;
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::compression;
+use crate::splitter::{self, Cues, Lines, Punctuation, SentenceSplitter};
+use crate::{Sentence, SentenceError};
+
+pub mod anki;
+pub mod aozora;
+pub mod ass;
+pub mod csv;
+pub mod encoding;
+pub mod epub;
+pub mod jsonl;
+pub mod kindle;
+pub mod markdown;
+pub mod markup;
+pub mod mokuro;
+pub mod pdf;
+pub mod sqlite;
+pub mod srt;
+pub mod tatoeba;
+pub mod twitter;
+pub mod vtt;
+pub mod wikipedia;
+
+/// The kinds of input the `add` command knows how to read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Plain text, split directly into sentences.
+    Text,
+    /// An EPUB book, whose chapters are read in reading order.
+    Epub,
+    /// An HTML page, with its markup, scripts and styles removed.
+    Html,
+    /// A PDF document with a text layer.
+    Pdf,
+    /// SRT subtitles, where each cue is read separately.
+    Srt,
+    /// ASS or SSA subtitles, where each line of dialogue is read separately.
+    Ass,
+    /// WebVTT subtitles, where each cue is read separately.
+    Vtt,
+    /// A text from Aozora Bunko, with its ruby and notes removed.
+    Aozora,
+    /// The `sentences.csv` dump from Tatoeba, keeping only Japanese sentences.
+    Tatoeba,
+    /// A deck exported from Anki, reading one field of each note.
+    Anki,
+    /// The `vocab.db` of a Kindle, reading the sentence of each lookup.
+    Kindle,
+    /// JSON Lines, with one object holding a sentence and its metadata per line.
+    Jsonl,
+    /// A spreadsheet saved as CSV, reading one column of each row.
+    Csv,
+    /// A spreadsheet saved as TSV, reading one column of each row.
+    Tsv,
+    /// A Markdown document, without code blocks or syntax, where each block is read separately.
+    Markdown,
+    /// The OCR output of mokuro, reading each block of text in a manga volume.
+    Mokuro,
+    /// A Wikipedia dump, either in XML or from Cirrus search, reading the prose of each article.
+    Wikipedia,
+    /// The `tweets.js` from a Twitter archive, reading the text of each tweet.
+    Twitter,
+}
+
+/// Settings for how some formats are read.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// For formats with several fields or columns, the one holding the sentence, counting from 0.
+    pub field: usize,
+    /// The encoding of text, which is detected if not given.
+    pub encoding: Option<encoding::Encoding>,
+    /// The file being read, if the input is a file.
+    pub path: Option<PathBuf>,
+    /// Whether each line of text is a whole sentence, instead of splitting on punctuation.
+    pub lines: bool,
+    /// The strings ending a sentence, instead of the usual punctuation.
+    pub delimiters: Option<Vec<String>>,
+    /// Whether to keep the spacing inside of sentences, only removing new lines around it.
+    pub preserve_whitespace: bool,
+}
+
+impl Options {
+    /// How to split text into sentences, ending them on punctuation.
+    pub fn punctuation(&self) -> Punctuation {
+        Punctuation {
+            terminators: self
+                .delimiters
+                .clone()
+                .unwrap_or_else(splitter::default_terminators),
+            preserve_whitespace: self.preserve_whitespace,
+        }
+    }
+
+    /// How to split plain text into sentences.
+    pub fn splitter(&self) -> Box<dyn SentenceSplitter> {
+        if self.lines {
+            Box::new(Lines {
+                preserve_whitespace: self.preserve_whitespace,
+            })
+        } else {
+            Box::new(self.punctuation())
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(Format::Text),
+            "epub" => Ok(Format::Epub),
+            "html" | "htm" => Ok(Format::Html),
+            "pdf" => Ok(Format::Pdf),
+            "srt" => Ok(Format::Srt),
+            "ass" | "ssa" => Ok(Format::Ass),
+            "vtt" | "webvtt" => Ok(Format::Vtt),
+            "aozora" => Ok(Format::Aozora),
+            "tatoeba" => Ok(Format::Tatoeba),
+            "anki" | "apkg" => Ok(Format::Anki),
+            "kindle" => Ok(Format::Kindle),
+            "jsonl" | "ndjson" => Ok(Format::Jsonl),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "mokuro" => Ok(Format::Mokuro),
+            "wikipedia" | "wiki" => Ok(Format::Wikipedia),
+            "twitter" | "tweets" => Ok(Format::Twitter),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+impl Format {
+    /// Guess the format of a file from its name, if it looks like a known format.
+    ///
+    /// Compressed files are recognised by the name they have once decompressed.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let path = &compression::decompressed_name(path);
+        match path.file_name().and_then(|n| n.to_str()) {
+            Some("vocab.db") => return Some(Format::Kindle),
+            Some("tweets.js") | Some("tweet.js") => return Some(Format::Twitter),
+            Some(name) if name.starts_with("jawiki-") => return Some(Format::Wikipedia),
+            _ => {}
+        }
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match ext.as_deref() {
+            Some("epub") => Some(Format::Epub),
+            Some("html") | Some("htm") | Some("xhtml") => Some(Format::Html),
+            Some("pdf") => Some(Format::Pdf),
+            Some("srt") => Some(Format::Srt),
+            Some("ass") | Some("ssa") => Some(Format::Ass),
+            Some("vtt") => Some(Format::Vtt),
+            Some("apkg") => Some(Format::Anki),
+            Some("jsonl") | Some("ndjson") => Some(Format::Jsonl),
+            Some("csv") => Some(Format::Csv),
+            Some("tsv") => Some(Format::Tsv),
+            Some("md") | Some("markdown") => Some(Format::Markdown),
+            Some("mokuro") => Some(Format::Mokuro),
+            Some("txt") | Some("text") => Some(Format::Text),
+            _ => None,
+        }
+    }
+
+    /// Check whether this format is a binary file, rather than some kind of text.
+    pub fn is_binary(self) -> bool {
+        matches!(
+            self,
+            Format::Epub | Format::Pdf | Format::Anki | Format::Kindle
+        )
+    }
+
+    /// Guess the format of a file from its name, defaulting to plain text.
+    pub fn from_path(path: &Path) -> Self {
+        Format::detect(path).unwrap_or(Format::Text)
+    }
+}
+
+fn utf8(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn read_all<R: Read>(mut input: R) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// An iterator over the sentences read from some input.
+pub type SentenceIter<'a> = Box<dyn Iterator<Item = Result<Sentence, SentenceError>> + 'a>;
+
+fn split_sentences<'a, R: BufRead + 'a>(reader: R, options: &Options) -> SentenceIter<'a> {
+    options.punctuation().split(Box::new(reader))
+}
+
+fn split_text<'a, R: BufRead + 'a>(reader: R, options: &Options) -> SentenceIter<'a> {
+    let sentences = options.splitter().split(Box::new(reader));
+    decode_references(sentences, options.clone(), !options.lines)
+}
+
+/// Sentences decoded from text being read, along with how far into it they're whole.
+///
+/// A sentence that references split into several is only read past once the last of them is,
+/// so that resuming from the position never skips the ones that weren't added yet.
+struct Settling<'a> {
+    sentences: SentenceIter<'a>,
+    options: Options,
+    /// How many bytes have been read from the text.
+    read: Position,
+    /// How many bytes the sentences given so far were read from.
+    settled: Position,
+    pending: std::collections::VecDeque<Decoded>,
+}
+
+impl<'a> Iterator for Settling<'a> {
+    type Item = Decoded;
+
+    fn next(&mut self) -> Option<Decoded> {
+        while self.pending.is_empty() {
+            match self.sentences.next() {
+                None => {
+                    self.settled.set(self.read.get());
+                    return None;
+                }
+                Some(s) => {
+                    let split = !self.options.lines;
+                    self.pending = decode_sentence(s, &self.options, split).into();
+                }
+            }
+        }
+        let sentence = self.pending.pop_front();
+        if self.pending.is_empty() {
+            self.settled.set(self.read.get());
+        }
+        sentence
+    }
+}
+
+type Decoded = Result<Sentence, SentenceError>;
+
+// Text scraped from the web can still have references like `&#12290;` for 。, so those
+// are decoded, splitting the sentences they hid unless each one is known to be whole
+fn decode_sentence(sentence: Decoded, options: &Options, split: bool) -> Vec<Decoded> {
+    let sentence = match sentence {
+        Ok(s) if markup::has_references(&s.text) => s,
+        other => return vec![other],
+    };
+    let text = markup::decode_entities(&sentence.text);
+    if !split {
+        let text = splitter::tidy_whitespace(&text, options.preserve_whitespace);
+        return vec![Ok(Sentence { text, ..sentence })];
+    }
+    split_sentences(io::Cursor::new(text), options)
+        .map(|s| {
+            s.map(|s| Sentence {
+                text: s.text,
+                ..sentence.clone()
+            })
+        })
+        .collect()
+}
+
+fn decode_references(sentences: SentenceIter, options: Options, split: bool) -> SentenceIter {
+    Box::new(sentences.flat_map(move |sentence| decode_sentence(sentence, &options, split)))
+}
+
+/// How many bytes into some text have been read.
+pub type Position = Rc<Cell<u64>>;
+
+// Counts the bytes read through it, whether read directly or through its buffer
+struct Counting<R> {
+    inner: R,
+    position: Position,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position.set(self.position.get() + read as u64);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Counting<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position.set(self.position.get() + amt as u64);
+        self.inner.consume(amt)
+    }
+}
+
+/// Read the sentences of plain text, starting some number of bytes in.
+///
+/// The position returned follows the end of the last sentence read, so that reading
+/// can later be picked up again from there.
+pub fn read_text_from<'a, R: Read + 'a>(
+    options: &Options,
+    input: R,
+    start: u64,
+) -> io::Result<(SentenceIter<'a>, Position)> {
+    let mut input = encoding::to_utf8(input, options.encoding)?;
+    io::copy(&mut input.by_ref().take(start), &mut io::sink())?;
+    let read = Rc::new(Cell::new(start));
+    let reader = Counting {
+        inner: io::BufReader::new(input),
+        position: read.clone(),
+    };
+    let settled = Rc::new(Cell::new(start));
+    let sentences = Settling {
+        sentences: options.splitter().split(Box::new(reader)),
+        options: options.clone(),
+        read,
+        settled: settled.clone(),
+        pending: Default::default(),
+    };
+    Ok((Box::new(sentences), settled))
+}
+
+/// Read the sentences in some pieces of text, like cues or paragraphs.
+///
+/// The end of each piece also ends a sentence, even without punctuation.
+pub fn cue_sentences<'a>(cues: Vec<String>, options: &Options) -> SentenceIter<'a> {
+    let inner = options.punctuation();
+    Cues { inner }.split_cues(cues)
+}
+
+/// Attach the same metadata to every sentence.
+pub fn with_metadata<'a>(
+    sentences: SentenceIter<'a>,
+    metadata: Vec<(String, String)>,
+) -> SentenceIter<'a> {
+    Box::new(sentences.map(move |s| {
+        s.map(|mut s| {
+            s.metadata.extend(metadata.iter().cloned());
+            s
+        })
+    }))
+}
+
+/// Read the sentences in the article of a web page.
+pub fn read_article<'a>(page: Vec<u8>, options: &Options) -> io::Result<SentenceIter<'a>> {
+    let page = read_all(encoding::to_utf8(io::Cursor::new(page), options.encoding)?)?;
+    let text = markup::article_text(&utf8(page)?);
+    Ok(split_sentences(io::Cursor::new(text), options))
+}
+
+/// Read the sentences out of some input in a given format.
+pub fn read_sentences<'a, R: Read + 'a>(
+    format: Format,
+    options: &Options,
+    input: R,
+) -> io::Result<SentenceIter<'a>> {
+    let input: Box<dyn Read + 'a> = if format.is_binary() {
+        Box::new(input)
+    } else {
+        encoding::to_utf8(input, options.encoding)?
+    };
+    let text = match format {
+        Format::Text => return Ok(split_text(io::BufReader::new(input), options)),
+        Format::Epub => epub::read_text(read_all(input)?)?,
+        Format::Html => markup::strip_tags(&utf8(read_all(input)?)?),
+        Format::Pdf => pdf::read_text(read_all(input)?)?,
+        Format::Srt => return Ok(cue_sentences(srt::cues(&utf8(read_all(input)?)?), options)),
+        Format::Ass => return Ok(cue_sentences(ass::cues(&utf8(read_all(input)?)?), options)),
+        Format::Vtt => return Ok(cue_sentences(vtt::cues(&utf8(read_all(input)?)?), options)),
+        Format::Aozora => aozora::strip_markup(&utf8(read_all(input)?)?),
+        Format::Anki => {
+            let notes = anki::read_notes(read_all(input)?, options.field)?;
+            return Ok(cue_sentences(notes, options));
+        }
+        Format::Kindle => {
+            let lookups = kindle::read_vocab(read_all(input)?)?;
+            return Ok(Box::new(lookups.into_iter().map(Ok)));
+        }
+        Format::Jsonl => {
+            let iter = io::BufReader::new(input)
+                .lines()
+                .filter_map(|line| match line {
+                    Err(e) => Some(Err(e.into())),
+                    Ok(line) => jsonl::parse_line(&line).transpose(),
+                });
+            return Ok(decode_references(Box::new(iter), options.clone(), false));
+        }
+        Format::Mokuro => {
+            let doc = utf8(read_all(input)?)?;
+            let blocks = mokuro::read_blocks(&doc, options.path.as_deref())?;
+            return Ok(Box::new(blocks.into_iter().map(Ok)));
+        }
+        Format::Wikipedia => {
+            let articles = wikipedia::Articles::new(io::BufReader::new(input));
+            let options = options.clone();
+            let iter = articles.flat_map(move |article| -> SentenceIter<'a> {
+                match article {
+                    Err(e) => Box::new(std::iter::once(Err(e.into()))),
+                    Ok((title, paragraphs)) => with_metadata(
+                        cue_sentences(paragraphs, &options),
+                        vec![("title".into(), title)],
+                    ),
+                }
+            });
+            return Ok(Box::new(iter));
+        }
+        Format::Markdown => {
+            let blocks = markdown::blocks(&utf8(read_all(input)?)?);
+            return Ok(cue_sentences(blocks, options));
+        }
+        Format::Twitter => {
+            let tweets = twitter::read_tweets(&utf8(read_all(input)?)?)?;
+            return Ok(cue_sentences(tweets, options));
+        }
+        Format::Csv | Format::Tsv => {
+            let delimiter = if format == Format::Csv { ',' } else { '\t' };
+            let column = options.field;
+            let cell_options = options.clone();
+            // Each cell ends a sentence, like a cue does
+            let iter = csv::Records::new(io::BufReader::new(input), delimiter).flat_map(
+                move |record| -> SentenceIter<'a> {
+                    match record {
+                        Err(e) => Box::new(std::iter::once(Err(e.into()))),
+                        Ok(mut fields) if column < fields.len() => {
+                            let cell = io::Cursor::new(fields.swap_remove(column));
+                            split_sentences(cell, &cell_options)
+                        }
+                        Ok(_) => Box::new(std::iter::empty()),
+                    }
+                },
+            );
+            return Ok(decode_references(Box::new(iter), options.clone(), true));
+        }
+        Format::Tatoeba => {
+            let iter = io::BufReader::new(input)
+                .lines()
+                .filter_map(|row| match row {
+                    Err(e) => Some(Err(e.into())),
+                    Ok(row) => tatoeba::japanese(&row).map(|(id, text)| {
+                        let mut sentence = Sentence::from(text);
+                        sentence.metadata.push(("tatoeba_id".into(), id.into()));
+                        Ok(sentence)
+                    }),
+                });
+            return Ok(Box::new(iter));
+        }
+    };
+    Ok(split_text(io::Cursor::new(text), options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected_from_extensions() {
+        assert_eq!(Format::Epub, Format::from_path(Path::new("book.EPUB")));
+        assert_eq!(Format::Html, Format::from_path(Path::new("page.htm")));
+        assert_eq!(Some(Format::Srt), Format::detect(Path::new("ep1.srt.gz")));
+        assert_eq!(Format::Srt, Format::from_path(Path::new("ep01.srt")));
+        assert_eq!(Format::Ass, Format::from_path(Path::new("ep01.ass")));
+        assert_eq!(Format::Vtt, Format::from_path(Path::new("ep01.ja.vtt")));
+        assert_eq!(Format::Anki, Format::from_path(Path::new("deck.apkg")));
+        assert_eq!(
+            Format::Kindle,
+            Format::from_path(Path::new("kindle/vocab.db"))
+        );
+        assert_eq!(Format::Jsonl, Format::from_path(Path::new("mined.jsonl")));
+        assert_eq!(Format::Csv, Format::from_path(Path::new("handout.csv")));
+        assert_eq!(Format::Text, Format::from_path(Path::new("book.txt")));
+        assert_eq!(Format::Text, Format::from_path(Path::new("book")));
+        assert_eq!(Some(Format::Text), Format::detect(Path::new("book.txt")));
+        assert_eq!(None, Format::detect(Path::new("cover.jpg")));
+    }
+
+    #[test]
+    fn lines_can_be_read_as_sentences() {
+        let options = Options {
+            lines: true,
+            ..Options::default()
+        };
+        let input = io::Cursor::new("猫を見た\n\n犬だ。鳥だ。\n");
+        let sentences: Vec<String> = read_sentences(Format::Text, &options, input)
+            .unwrap()
+            .map(|s| s.unwrap().text)
+            .collect();
+        assert_eq!(vec!["猫を見た", "犬だ。鳥だ。"], sentences);
+    }
+
+    #[test]
+    fn scraped_references_are_decoded() {
+        let text = |format, input: &str| -> Vec<String> {
+            read_sentences(format, &Options::default(), input.as_bytes())
+                .unwrap()
+                .map(|s| s.unwrap().text)
+                .collect()
+        };
+        assert_eq!(
+            vec!["猫&犬。", "見た。"],
+            text(Format::Text, "猫&amp;犬&#12290;見た&#12290;")
+        );
+        assert_eq!(
+            vec!["\"猫\"。"],
+            text(Format::Jsonl, r#"{"sentence": "&quot;猫&quot;&#12290;"}"#)
+        );
+    }
+
+    #[test]
+    fn text_can_be_read_from_a_position() {
+        let text = "猫だ。犬だ。鳥だ。";
+        let read = |start| {
+            let (mut sentences, position) =
+                read_text_from(&Options::default(), io::Cursor::new(text), start).unwrap();
+            let first = sentences.next().unwrap().unwrap().text;
+            (first, position.get())
+        };
+        let (first, position) = read(0);
+        assert_eq!("猫だ。", first);
+        assert_eq!("猫だ。".len() as u64, position);
+        assert_eq!(("犬だ。".to_string(), position * 2), read(position));
+    }
+
+    #[test]
+    fn sentences_hidden_by_references_are_read_again_when_resuming() {
+        let text = "猫だ&#12290;犬だ。鳥だ。";
+        let start = |skip| {
+            let (mut sentences, position) =
+                read_text_from(&Options::default(), io::Cursor::new(text), 0).unwrap();
+            for _ in 0..skip {
+                sentences.next().unwrap().unwrap();
+            }
+            position.get()
+        };
+        assert_eq!(0, start(1));
+        let whole = start(2);
+        assert_eq!("猫だ&#12290;犬だ。".len() as u64, whole);
+        let (sentences, _) =
+            read_text_from(&Options::default(), io::Cursor::new(text), whole).unwrap();
+        let rest: Vec<String> = sentences.map(|s| s.unwrap().text).collect();
+        assert_eq!(vec!["鳥だ。"], rest);
+        let (sentences, _) = read_text_from(&Options::default(), io::Cursor::new(text), 0).unwrap();
+        assert_eq!(3, sentences.count());
+    }
+
+    #[test]
+    fn formats_can_be_named() {
+        assert_eq!(Ok(Format::Html), "HTML".parse());
+        assert_eq!(Ok(Format::Aozora), "aozora".parse());
+        assert_eq!(Ok(Format::Tatoeba), "Tatoeba".parse());
+        assert!("docx".parse::<Format>().is_err());
+    }
+}
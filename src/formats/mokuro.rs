@@ -0,0 +1,110 @@
+//! Reading the text mokuro finds in manga pages.
+//!
+//! mokuro writes a `.mokuro` file for each volume, holding every page, and older
+//! versions write a JSON file for each page, in a directory named after the volume.
+//! Each page has blocks of text, like speech bubbles, with one entry per line.
+use std::io;
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::Sentence;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn page_blocks(page: &Value, volume: Option<&str>, page_name: &str, out: &mut Vec<Sentence>) {
+    let blocks = match page.get("blocks") {
+        Some(Value::Array(blocks)) => blocks,
+        _ => return,
+    };
+    for block in blocks {
+        let lines: Vec<&str> = match block.get("lines") {
+            Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+            _ => continue,
+        };
+        // Vertical text is split into lines wherever the bubble wraps
+        let mut sentence = Sentence::from(lines.concat().as_str());
+        if sentence.text.is_empty() {
+            continue;
+        }
+        if let Some(volume) = volume {
+            sentence.metadata.push(("source".into(), volume.into()));
+        }
+        sentence.metadata.push(("page".into(), page_name.into()));
+        out.push(sentence);
+    }
+}
+
+/// Read every block of text, in page order, with the volume as its source.
+///
+/// The path of the file is used to name the volume when the file doesn't.
+pub fn read_blocks(doc: &str, path: Option<&Path>) -> io::Result<Vec<Sentence>> {
+    let value = json::parse(doc).map_err(|e| invalid(e.to_string()))?;
+    let mut sentences = Vec::new();
+    match value.get("pages") {
+        Some(Value::Array(pages)) => {
+            let stem = path.and_then(Path::file_stem).map(|s| s.to_string_lossy());
+            let volume = value
+                .get("volume")
+                .and_then(Value::as_str)
+                .or(stem.as_deref());
+            for (i, page) in pages.iter().enumerate() {
+                let name = match page.get("img_path").and_then(Value::as_str) {
+                    None => (i + 1).to_string(),
+                    Some(img) => img.to_string(),
+                };
+                page_blocks(page, volume, &name, &mut sentences);
+            }
+        }
+        _ if value.get("blocks").is_some() => {
+            let volume = path
+                .and_then(Path::parent)
+                .and_then(Path::file_name)
+                .map(|s| s.to_string_lossy());
+            let name = path
+                .and_then(Path::file_stem)
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            page_blocks(&value, volume.as_deref(), &name, &mut sentences);
+        }
+        _ => return Err(invalid("no mokuro pages or blocks found".into())),
+    }
+    Ok(sentences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_are_read_page_by_page() {
+        let doc = r#"{"version": "0.2.0", "volume": "第1巻", "pages": [
+            {"img_path": "001.jpg", "blocks": [{"vertical": true, "lines": ["おはよう", "ござい", "ます"]}]},
+            {"img_path": "002.jpg", "blocks": [{"lines": ["．．．"]}, {"lines": []}]}
+        ]}"#;
+        let sentences = read_blocks(doc, None).unwrap();
+        assert_eq!(2, sentences.len());
+        assert_eq!("おはようございます", sentences[0].text);
+        assert_eq!(
+            vec![
+                ("source".to_string(), "第1巻".to_string()),
+                ("page".to_string(), "001.jpg".to_string())
+            ],
+            sentences[0].metadata
+        );
+    }
+
+    #[test]
+    fn pages_are_named_by_their_path() {
+        let doc = r#"{"img_width": 100, "blocks": [{"lines": ["猫だ！"]}]}"#;
+        let sentences = read_blocks(doc, Some(Path::new("_ocr/巻二/005.json"))).unwrap();
+        assert_eq!(
+            vec![
+                ("source".to_string(), "巻二".to_string()),
+                ("page".to_string(), "005".to_string())
+            ],
+            sentences[0].metadata
+        );
+    }
+}
@@ -0,0 +1,13 @@
+//! Reading the text layer of PDF documents.
+//!
+//! Extraction is delegated to `pdftotext`, from poppler, since PDF fonts
+//! make recovering Japanese text anything but simple.
+use std::io;
+
+use crate::external;
+
+/// Extract the embedded text of a PDF document.
+pub fn read_text(data: Vec<u8>) -> io::Result<String> {
+    let text = external::filter("pdftotext", &["-q", "-enc", "UTF-8", "-", "-"], data)?;
+    super::utf8(text)
+}
@@ -0,0 +1,24 @@
+//! Reading formats which are themselves SQLite databases.
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+use rusqlite::{Connection, OpenFlags};
+
+/// Open a copy of a database read from some input, and query it.
+///
+/// SQLite needs the database as a file of its own, so this goes through a
+/// temporary file, removed once the query is done.
+pub fn query_copy<T, F>(data: &[u8], query: F) -> io::Result<T>
+where
+    F: FnOnce(&Connection) -> rusqlite::Result<T>,
+{
+    let path = env::temp_dir().join(format!("ginkou-{}.db", process::id()));
+    fs::write(&path, data)?;
+    let result = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| query(&conn))
+        .map_err(io::Error::other);
+    fs::remove_file(&path)?;
+    result
+}
@@ -0,0 +1,50 @@
+//! Reading the dialogue out of SRT subtitle files.
+//!
+//! Each cue is a block of lines made up of a cue number, a timing line,
+//! and then the text shown on screen.
+use super::markup::strip_tags;
+
+fn is_cue_number(line: &str) -> bool {
+    !line.is_empty() && line.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Pull out the text of each cue, joining cues spanning multiple lines.
+pub fn cues(text: &str) -> Vec<String> {
+    let mut cues = Vec::new();
+    let mut current = String::new();
+    let mut block_start = true;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        let first = block_start;
+        block_start = line.is_empty();
+        if line.is_empty() {
+            if !current.is_empty() {
+                cues.push(strip_tags(&current));
+                current.clear();
+            }
+            continue;
+        }
+        if line.contains("-->") || (first && is_cue_number(line)) {
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.is_empty() {
+        cues.push(strip_tags(&current));
+    }
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cues_skip_numbers_and_timings() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>待って</i>\r\n\r\n\
+                   2\n00:00:03,000 --> 00:00:04,000\n行かないで\nください\n\n3\n00:00:05,000 --> 00:00:06,000\n2020\n";
+        let cues = cues(srt);
+        assert_eq!(vec!["待って\n", "行かないで\nください\n", "2020\n"], cues);
+    }
+}
@@ -0,0 +1,101 @@
+//! Reading the sentence dumps published by Tatoeba.
+//!
+//! `sentences.csv` is actually tab separated, with a row of
+//! `id, language, text` for every sentence in every language. `links.csv`
+//! pairs up the ids of sentences which translate each other.
+use std::collections::HashMap;
+use std::io;
+use std::io::BufRead;
+
+use crate::Sentence;
+
+// The language code Tatoeba uses for Japanese.
+const JAPANESE: &str = "jpn";
+
+/// Parse a row of the dump, returning the id and text of Japanese sentences.
+pub fn japanese(row: &str) -> Option<(&str, &str)> {
+    let mut fields = row.split('\t');
+    let id = fields.next()?;
+    let lang = fields.next()?;
+    let text = fields.next()?;
+    if lang == JAPANESE {
+        Some((id, text))
+    } else {
+        None
+    }
+}
+
+/// The translations of Japanese sentences, keyed by Tatoeba id.
+pub struct Translations(HashMap<String, Vec<String>>);
+
+impl Translations {
+    /// Pair up sentences with their translations, using the rows of `links.csv`.
+    ///
+    /// Only the non Japanese sentences from the translation dump are used.
+    pub fn load<L: BufRead, S: BufRead>(links: L, sentences: S) -> io::Result<Self> {
+        let mut texts = HashMap::new();
+        for row in sentences.lines() {
+            let row = row?;
+            let mut fields = row.split('\t');
+            if let (Some(id), Some(lang), Some(text)) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                if lang != JAPANESE {
+                    texts.insert(id.to_string(), text.to_string());
+                }
+            }
+        }
+        let mut translations: HashMap<String, Vec<String>> = HashMap::new();
+        for row in links.lines() {
+            let row = row?;
+            let mut fields = row.split('\t');
+            if let (Some(id), Some(translation)) = (fields.next(), fields.next()) {
+                if let Some(text) = texts.get(translation) {
+                    translations
+                        .entry(id.to_string())
+                        .or_default()
+                        .push(text.clone());
+                }
+            }
+        }
+        Ok(Translations(translations))
+    }
+
+    /// Add the translations of a sentence imported from Tatoeba.
+    pub fn attach(&self, sentence: &mut Sentence) {
+        let id = sentence
+            .metadata
+            .iter()
+            .find(|(key, _)| key == "tatoeba_id")
+            .map(|(_, id)| id);
+        if let Some(found) = id.and_then(|id| self.0.get(id)) {
+            sentence.translations.extend(found.iter().cloned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_japanese_rows_are_kept() {
+        assert_eq!(
+            Some(("4704", "きみにちょっとしたものをもってきたよ。")),
+            japanese("4704\tjpn\tきみにちょっとしたものをもってきたよ。")
+        );
+        assert_eq!(None, japanese("1276\teng\tLet's try something."));
+        assert_eq!(None, japanese("garbage"));
+    }
+
+    #[test]
+    fn translations_are_attached_by_id() {
+        let links = "4704\t1276\n1276\t4704\n4704\t9999\n";
+        let sentences = "1276\teng\tLet's try something.\n4704\tjpn\t何かしてみましょう。\n";
+        let translations = Translations::load(links.as_bytes(), sentences.as_bytes()).unwrap();
+        let mut sentence = Sentence::from("何かしてみましょう。");
+        sentence.metadata.push(("tatoeba_id".into(), "4704".into()));
+        translations.attach(&mut sentence);
+        assert_eq!(vec!["Let's try something."], sentence.translations);
+    }
+}
@@ -0,0 +1,95 @@
+//! Reading the tweets in a Twitter archive.
+//!
+//! The archive's `tweets.js` assigns a JSON array to a variable, like
+//! `window.YTD.tweets.part0 = [{"tweet": {"full_text": "..."}}]`.
+use std::io;
+
+use super::markup::decode_entities;
+use crate::json::{self, Value};
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Remove the links and mentions in a tweet, which aren't part of any sentence
+fn strip_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((i, c)) = rest.char_indices().find(|&(i, c)| {
+        let mention = c == '@' && !rest[..i].ends_with(|c: char| c.is_ascii_alphanumeric());
+        mention || rest[i..].starts_with("http://") || rest[i..].starts_with("https://")
+    }) {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + c.len_utf8()..];
+        let end = if c == '@' {
+            after
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(after.len())
+        } else {
+            after.find(char::is_whitespace).unwrap_or(after.len())
+        };
+        if c == '@' && end == 0 {
+            out.push('@');
+        }
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Read the text of every tweet, without links or mentions.
+pub fn read_tweets(script: &str) -> io::Result<Vec<String>> {
+    let start = script
+        .find('[')
+        .ok_or_else(|| invalid("no tweets found in archive".into()))?;
+    let tweets = match json::parse(script[start..].trim_end().trim_end_matches(';')) {
+        Err(e) => return Err(invalid(e.to_string())),
+        Ok(Value::Array(tweets)) => tweets,
+        Ok(_) => return Err(invalid("no tweets found in archive".into())),
+    };
+    let texts = tweets
+        .iter()
+        .map(|t| t.get("tweet").unwrap_or(t))
+        .filter_map(|t| t.get("full_text").or_else(|| t.get("text")))
+        .filter_map(Value::as_str)
+        .map(|text| {
+            // Retweets start with who was retweeted
+            let text = match text
+                .strip_prefix("RT ")
+                .and_then(|t| t.find(": ").map(|i| &t[i + 2..]))
+            {
+                None => text,
+                Some(t) => t,
+            };
+            strip_links(&decode_entities(text)).trim().to_string()
+        })
+        .filter(|text| !text.is_empty())
+        .collect();
+    Ok(texts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_and_mentions_are_removed() {
+        assert_eq!(
+            " 今日は晴れ。 me@example.com @",
+            strip_links("@friend_1 今日は晴れ。https://t.co/abc me@example.com @")
+        );
+    }
+
+    #[test]
+    fn tweets_are_read_from_archives() {
+        let script = r#"window.YTD.tweets.part0 = [
+            {"tweet": {"id_str": "1", "full_text": "@a 猫を見た &amp; 撫でた。 https://t.co/x"}},
+            {"tweet": {"id_str": "2", "full_text": "RT @b: 犬も見た。"}},
+            {"tweet": {"id_str": "3", "full_text": "https://t.co/y"}}
+        ];"#;
+        assert_eq!(
+            vec!["猫を見た & 撫でた。", "犬も見た。"],
+            read_tweets(script).unwrap()
+        );
+    }
+}
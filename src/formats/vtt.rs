@@ -0,0 +1,61 @@
+//! Reading the dialogue out of WebVTT subtitles.
+//!
+//! A file starts with a `WEBVTT` header, followed by blocks separated by
+//! blank lines. Blocks are either cues, or notes, styles and regions.
+use super::markup::strip_tags;
+
+const SKIPPED_BLOCKS: [&str; 4] = ["WEBVTT", "NOTE", "STYLE", "REGION"];
+
+/// Pull out the text of each cue, joining cues spanning multiple lines.
+///
+/// Lines repeating the line just before them are dropped, since automatic
+/// captions scroll each line through several cues.
+pub fn cues(text: &str) -> Vec<String> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    let mut last_line = String::new();
+    for block in text.split("\n\n") {
+        let lines: Vec<&str> = block
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        let first = match lines.first() {
+            None => continue,
+            Some(l) => l,
+        };
+        if SKIPPED_BLOCKS.iter().any(|b| first.starts_with(b)) {
+            continue;
+        }
+        let timing = match lines.iter().position(|l| l.contains("-->")) {
+            None => continue,
+            Some(i) => i,
+        };
+        let mut cue = String::new();
+        for line in &lines[timing + 1..] {
+            let line = strip_tags(line);
+            if line != last_line {
+                cue.push_str(&line);
+                cue.push('\n');
+                last_line = line;
+            }
+        }
+        if !cue.is_empty() {
+            cues.push(cue);
+        }
+    }
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cues_skip_headers_notes_and_timings() {
+        let vtt = "WEBVTT\nKind: captions\nLanguage: ja\n\nNOTE 注釈\n\n\
+                   intro\n00:00:01.000 --> 00:00:02.000 align:start\n<v 太郎>待って</v>\n\n\
+                   00:00:02.000 --> 00:00:03.000\n待って\n<00:00:02.500><c>行かないで</c>\n";
+        assert_eq!(vec!["待って\n", "行かないで\n"], cues(vtt));
+    }
+}
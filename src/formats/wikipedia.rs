@@ -0,0 +1,335 @@
+//! Reading the articles in a Wikipedia dump.
+//!
+//! Both the XML dumps of every page and the JSON Lines of the Cirrus search dumps
+//! are read. Dumps are huge, so pages are read one at a time, and only the
+//! prose of articles is kept, without templates, tables, or references.
+use std::io;
+use std::io::BufRead;
+use std::mem;
+
+use super::markup::{decode_entities, strip_tags_skipping};
+use crate::json::{self, Value};
+
+// Elements holding something other than prose
+const SKIPPED_ELEMENTS: [&str; 7] = [
+    "ref",
+    "math",
+    "gallery",
+    "timeline",
+    "syntaxhighlight",
+    "source",
+    "score",
+];
+// Links to these namespaces are images or categories, rather than text
+const NON_TEXT_LINKS: [&str; 8] = [
+    "file:",
+    "image:",
+    "category:",
+    "ファイル:",
+    "画像:",
+    "カテゴリ:",
+    "media:",
+    "メディア:",
+];
+
+// Remove everything between nested pairs of delimiters, like `{{` and `}}`
+fn remove_nested(text: &str, open: &str, close: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut rest = text;
+    loop {
+        let next_open = rest.find(open);
+        let next_close = if depth > 0 { rest.find(close) } else { None };
+        match (next_open, next_close) {
+            (Some(o), c) if c.is_none_or(|c| o < c) => {
+                if depth == 0 {
+                    out.push_str(&rest[..o]);
+                }
+                depth += 1;
+                rest = &rest[o + open.len()..];
+            }
+            (_, Some(c)) => {
+                depth -= 1;
+                rest = &rest[c + close.len()..];
+            }
+            _ => {
+                if depth == 0 {
+                    out.push_str(rest);
+                }
+                return out;
+            }
+        }
+    }
+}
+
+// Replace internal links with their text, and drop links to images and categories
+fn replace_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        // Image captions can contain links of their own
+        let mut depth = 1;
+        let mut end = None;
+        let mut i = 0;
+        while i < after.len() {
+            if after[i..].starts_with("[[") {
+                depth += 1;
+                i += 2;
+            } else if after[i..].starts_with("]]") {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+                i += 2;
+            } else {
+                i += after[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        let end = match end {
+            None => {
+                rest = after;
+                continue;
+            }
+            Some(e) => e,
+        };
+        let link = &after[..end];
+        let lower = link.trim_start_matches(':').to_lowercase();
+        if !NON_TEXT_LINKS.iter().any(|ns| lower.starts_with(ns)) {
+            out.push_str(link.rsplit('|').next().unwrap_or(link));
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Replace external links like `[https://example.com text]` with their text
+fn replace_external_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[http") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = match after.find(']') {
+            None => {
+                out.push('[');
+                rest = after;
+                continue;
+            }
+            Some(e) => e,
+        };
+        if let Some(space) = after[..end].find(' ') {
+            out.push_str(&after[space + 1..end]);
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Turn the wiki markup of an article into its paragraphs of prose.
+pub fn paragraphs(markup: &str) -> Vec<String> {
+    let text = remove_nested(markup, "{{", "}}");
+    let text = remove_nested(&text, "{|", "|}");
+    let text = strip_tags_skipping(&text, &SKIPPED_ELEMENTS);
+    let text = replace_links(&text);
+    let text = replace_external_links(&text);
+    text.lines()
+        .map(|line| line.replace("'''", "").replace("''", ""))
+        .filter_map(|line| {
+            let line = line.trim();
+            // Headings, tables, and the like aren't prose
+            if line.is_empty() || line.starts_with(['=', '|', '!', '_']) {
+                return None;
+            }
+            let line = line.trim_start_matches(['*', '#', ':', ';']).trim();
+            if line.is_empty() {
+                None
+            } else {
+                Some(line.to_string())
+            }
+        })
+        .collect()
+}
+
+fn element_text<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let start = line.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + line[start..].find(&format!("</{}>", name))?;
+    Some(&line[start..end])
+}
+
+/// The articles in a dump, as their title and paragraphs.
+pub struct Articles<R> {
+    reader: R,
+    line: String,
+    // Whether the dump is a Cirrus dump, once the first line has been read
+    cirrus: Option<bool>,
+}
+
+impl<R: BufRead> Articles<R> {
+    pub fn new(reader: R) -> Self {
+        Articles {
+            reader,
+            line: String::new(),
+            cirrus: None,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.line.clear();
+        Ok(self.reader.read_line(&mut self.line)? > 0)
+    }
+
+    // The line read to tell which kind of dump this is comes first
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        if self.line.is_empty() && !self.read_line()? {
+            return Ok(None);
+        }
+        Ok(Some(mem::take(&mut self.line)))
+    }
+
+    fn next_cirrus(&mut self) -> io::Result<Option<(String, Vec<String>)>> {
+        while let Some(line) = self.next_line()? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = json::parse(line.trim())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            // Documents alternate with lines saying how to index them
+            let text = match value.get("text").and_then(Value::as_str) {
+                None => continue,
+                Some(t) => t,
+            };
+            match value.get("namespace") {
+                Some(Value::Number(n)) if *n != 0.0 => continue,
+                _ => {}
+            }
+            let title = value.get("title").and_then(Value::as_str).unwrap_or("");
+            return Ok(Some((title.to_string(), vec![text.to_string()])));
+        }
+        Ok(None)
+    }
+
+    fn next_xml(&mut self) -> io::Result<Option<(String, Vec<String>)>> {
+        let mut title = String::new();
+        let mut article = true;
+        let mut text: Option<String> = None;
+        let mut in_text = false;
+        while let Some(line) = self.next_line()? {
+            let (start, opening) = if in_text {
+                (0, false)
+            } else {
+                let trimmed = line.trim();
+                if trimmed.starts_with("<page>") {
+                    title.clear();
+                    article = true;
+                    text = None;
+                } else if let Some(t) = element_text(trimmed, "title") {
+                    title = decode_entities(t);
+                } else if let Some(ns) = element_text(trimmed, "ns") {
+                    article = ns == "0";
+                } else if trimmed.starts_with("<redirect") {
+                    article = false;
+                } else if trimmed.starts_with("</page>") && article {
+                    if let Some(markup) = text.take() {
+                        return Ok(Some((title, paragraphs(&decode_entities(&markup)))));
+                    }
+                }
+                if !trimmed.starts_with("<text") || trimmed.ends_with("/>") {
+                    continue;
+                }
+                (line.find('>').map_or(line.len(), |i| i + 1), true)
+            };
+            let rest = &line[start..];
+            let (content, closed) = match rest.find("</text>") {
+                None => (rest, false),
+                Some(end) => (&rest[..end], true),
+            };
+            // Only the text of articles is kept, and only for the current page
+            if article {
+                if opening {
+                    text = Some(String::new());
+                }
+                if let Some(ref mut text) = text {
+                    text.push_str(content);
+                }
+            }
+            in_text = !closed;
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for Articles<R> {
+    type Item = io::Result<(String, Vec<String>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cirrus = match self.cirrus {
+            Some(c) => c,
+            None => {
+                match self.read_line() {
+                    Err(e) => return Some(Err(e)),
+                    Ok(false) => return None,
+                    Ok(true) => {}
+                }
+                let c = self.line.trim_start().starts_with('{');
+                self.cirrus = Some(c);
+                c
+            }
+        };
+        let next = if cirrus {
+            self.next_cirrus()
+        } else {
+            self.next_xml()
+        };
+        next.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_is_reduced_to_prose() {
+        let markup =
+            "{{Infobox|name={{lang|ja|猫}}}}\n'''猫'''は[[哺乳類|哺乳綱]]の[[動物]]である。\
+                      <ref>{{cite}}</ref>\n[[ファイル:Cat.jpg|thumb|[[猫]]の写真]]\n== 歴史 ==\n\
+                      {|\n| 表 |}\n* [https://example.com 外部]の説明。\n[[Category:猫]]";
+        assert_eq!(
+            vec!["猫は哺乳綱の動物である。", "外部の説明。"],
+            paragraphs(markup)
+        );
+    }
+
+    #[test]
+    fn only_articles_are_read_from_xml() {
+        let dump = "<mediawiki>\n<page>\n<title>猫</title>\n<ns>0</ns>\n<revision>\n\
+                    <text bytes=\"10\" xml:space=\"preserve\">猫は&lt;ref&gt;注&lt;/ref&gt;動物。\n\
+                    二行目。</text>\n</revision>\n</page>\n\
+                    <page>\n<title>ノート:猫</title>\n<ns>1</ns>\n<text>議論。</text>\n</page>\n\
+                    <page>\n<title>ねこ</title>\n<ns>0</ns>\n<redirect title=\"猫\" />\n\
+                    <text>#転送 [[猫]]</text>\n</page>\n</mediawiki>\n";
+        let articles: Vec<_> = Articles::new(io::Cursor::new(dump))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let paragraphs = vec!["猫は動物。".to_string(), "二行目。".to_string()];
+        assert_eq!(vec![("猫".to_string(), paragraphs)], articles);
+    }
+
+    #[test]
+    fn cirrus_documents_are_read() {
+        let dump = "{\"index\":{\"_id\":\"1\"}}\n\
+                    {\"namespace\":0,\"title\":\"猫\",\"text\":\"猫は動物。\"}\n\
+                    {\"index\":{\"_id\":\"2\"}}\n\
+                    {\"namespace\":14,\"title\":\"猫\",\"text\":\"分類。\"}\n";
+        let articles: Vec<_> = Articles::new(io::Cursor::new(dump))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let paragraphs = vec!["猫は動物。".to_string()];
+        assert_eq!(vec![("猫".to_string(), paragraphs)], articles);
+    }
+}
@@ -0,0 +1,571 @@
+//! A small decoder for DEFLATE streams, as described in RFC 1951, and the gzip files
+//! holding them, as described in RFC 1952.
+//!
+//! This is used to read compressed archive members and gzip files, decompressing them
+//! as they're read, and favours simplicity over speed.
+use std::io;
+use std::io::{BufRead, Read};
+
+const MAX_BITS: usize = 15;
+
+/// How far back a DEFLATE stream can copy from what it already decompressed.
+const WINDOW: usize = 32 * 1024;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Bytes are only taken from the input as their bits are needed, so once aligned,
+// the rest of the input can be read as bytes again
+struct Bits<R> {
+    input: R,
+    buf: u32,
+    count: u32,
+}
+
+impl<R: BufRead> Bits<R> {
+    fn new(input: R) -> Self {
+        Bits {
+            input,
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = match self.input.fill_buf()?.first() {
+            None => return Err(invalid("unexpected end of compressed stream")),
+            Some(&b) => b,
+        };
+        self.input.consume(1);
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from(self.byte()?) | u16::from(self.byte()?) << 8)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from(self.u16()?) | u32::from(self.u16()?) << 16)
+    }
+
+    fn bits(&mut self, need: u32) -> io::Result<u32> {
+        while self.count < need {
+            let byte = self.byte()?;
+            self.buf |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let val = self.buf & ((1u64 << need) - 1) as u32;
+        self.buf = if need == 32 { 0 } else { self.buf >> need };
+        self.count -= need;
+        Ok(val)
+    }
+
+    fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+}
+
+// A canonical huffman code, stored as the number of codes of each length,
+// along with the symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode<R: BufRead>(&self, bits: &mut Bits<R>) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..=MAX_BITS {
+            code |= bits.bits(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid("bad huffman code in deflate stream"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (i, len) in lengths.iter_mut().enumerate() {
+        *len = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes<R: BufRead>(bits: &mut Bits<R>) -> io::Result<(Huffman, Huffman)> {
+    let nlen = bits.bits(5)? as usize + 257;
+    let ndist = bits.bits(5)? as usize + 1;
+    let ncode = bits.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(ncode) {
+        code_lengths[index] = bits.bits(3)? as u8;
+    }
+    let code_huffman = Huffman::new(&code_lengths);
+    let mut lengths = Vec::with_capacity(nlen + ndist);
+    while lengths.len() < nlen + ndist {
+        let symbol = code_huffman.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => match lengths.last() {
+                None => return Err(invalid("repeat with no previous length")),
+                Some(&last) => (last, 3 + bits.bits(2)?),
+            },
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > nlen + ndist {
+        return Err(invalid("too many code lengths in deflate stream"));
+    }
+    let (lit, dist) = lengths.split_at(nlen);
+    Ok((Huffman::new(lit), Huffman::new(dist)))
+}
+
+// Decode the next literal, or copy of earlier bytes, returning whether the block ended instead
+fn inflate_symbol<R: BufRead>(
+    bits: &mut Bits<R>,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> io::Result<bool> {
+    let symbol = lit.decode(bits)? as usize;
+    if symbol < 256 {
+        out.push(symbol as u8);
+        return Ok(false);
+    }
+    if symbol == 256 {
+        return Ok(true);
+    }
+    let symbol = symbol - 257;
+    if symbol >= LENGTH_BASE.len() {
+        return Err(invalid("bad length symbol in deflate stream"));
+    }
+    let len = LENGTH_BASE[symbol] as usize + bits.bits(u32::from(LENGTH_EXTRA[symbol]))? as usize;
+    let symbol = dist.decode(bits)? as usize;
+    if symbol >= DIST_BASE.len() {
+        return Err(invalid("bad distance symbol in deflate stream"));
+    }
+    let back = DIST_BASE[symbol] as usize + bits.bits(u32::from(DIST_EXTRA[symbol]))? as usize;
+    if back > out.len() {
+        return Err(invalid("distance too far back in deflate stream"));
+    }
+    let start = out.len() - back;
+    for i in 0..len {
+        let byte = out[start + i];
+        out.push(byte);
+    }
+    Ok(false)
+}
+
+/// Where a DEFLATE stream is, between its blocks.
+enum Block {
+    /// The header of the next block is to be read.
+    Header,
+    /// A stored block, with how many of its bytes haven't been read yet.
+    Stored(usize),
+    /// A compressed block, with its codes for literals and lengths, and for distances.
+    Codes(Huffman, Huffman),
+    /// The last block has been read.
+    Done,
+}
+
+/// A raw DEFLATE stream, decompressed as it's read.
+pub struct Inflate<R> {
+    bits: Bits<R>,
+    block: Block,
+    /// Whether the block being read is the last one.
+    last: bool,
+    /// The last bytes decompressed which were already read, which later ones can copy,
+    /// followed by those that haven't been read yet.
+    out: Vec<u8>,
+    read: usize,
+}
+
+impl<R: BufRead> Inflate<R> {
+    pub fn new(input: R) -> Self {
+        Inflate {
+            bits: Bits::new(input),
+            block: Block::Header,
+            last: false,
+            out: Vec::new(),
+            read: 0,
+        }
+    }
+
+    // Going on to another stream in the same input, like the next member of a gzip file
+    fn restart(&mut self) {
+        self.bits.align();
+        self.block = Block::Header;
+        self.last = false;
+        self.out.clear();
+        self.read = 0;
+    }
+
+    fn header(&mut self) -> io::Result<Block> {
+        self.last = self.bits.bits(1)? == 1;
+        match self.bits.bits(2)? {
+            0 => {
+                self.bits.align();
+                let len = self.bits.u16()?;
+                if self.bits.u16()? != !len {
+                    return Err(invalid("stored block length doesn't match its complement"));
+                }
+                Ok(Block::Stored(usize::from(len)))
+            }
+            1 => {
+                let (lit, dist) = fixed_codes();
+                Ok(Block::Codes(lit, dist))
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut self.bits)?;
+                Ok(Block::Codes(lit, dist))
+            }
+            _ => Err(invalid("bad block type in deflate stream")),
+        }
+    }
+
+    // Decompress more of the stream, unless it has ended, only keeping what can still be copied
+    fn fill(&mut self) -> io::Result<()> {
+        if self.read > 2 * WINDOW {
+            self.out.drain(..self.read - WINDOW);
+            self.read = WINDOW;
+        }
+        while self.out.len() - self.read < WINDOW {
+            let ended = match self.block {
+                Block::Done => return Ok(()),
+                Block::Header => {
+                    self.block = self.header()?;
+                    false
+                }
+                Block::Stored(0) => true,
+                Block::Stored(ref mut left) => {
+                    let available = self.bits.input.fill_buf()?;
+                    if available.is_empty() {
+                        return Err(invalid("unexpected end of compressed stream"));
+                    }
+                    let n = available.len().min(*left);
+                    self.out.extend_from_slice(&available[..n]);
+                    self.bits.input.consume(n);
+                    *left -= n;
+                    false
+                }
+                Block::Codes(ref lit, ref dist) => {
+                    inflate_symbol(&mut self.bits, &mut self.out, lit, dist)?
+                }
+            };
+            if ended {
+                self.block = if self.last {
+                    Block::Done
+                } else {
+                    Block::Header
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Inflate<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.read == self.out.len() {
+            self.fill()?;
+        }
+        let n = buf.len().min(self.out.len() - self.read);
+        buf[..n].copy_from_slice(&self.out[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
+
+/// Decompress a raw DEFLATE stream, returning the decompressed bytes.
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    Inflate::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Continue the CRC-32 gzip checks its contents with, over some more bytes.
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in bytes {
+        crc = CRC_TABLE[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// A gzip file, decompressed as it's read.
+///
+/// Files of several gzip members one after another, like those `pigz` makes, are read as one,
+/// and each member's contents are checked against its checksum and length.
+pub struct Gzip<R> {
+    inflate: Inflate<R>,
+    started: bool,
+    crc: u32,
+    size: u32,
+}
+
+impl<R: BufRead> Gzip<R> {
+    pub fn new(input: R) -> Self {
+        Gzip {
+            inflate: Inflate::new(input),
+            started: false,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        let bits = &mut self.inflate.bits;
+        if bits.u16()? != 0x8b1f {
+            return Err(invalid("not a gzip file"));
+        }
+        if bits.byte()? != 8 {
+            return Err(invalid("unsupported gzip compression method"));
+        }
+        let flags = bits.byte()?;
+        // The modification time, extra flags, and operating system aren't needed
+        for _ in 0..6 {
+            bits.byte()?;
+        }
+        if flags & 4 != 0 {
+            for _ in 0..bits.u16()? {
+                bits.byte()?;
+            }
+        }
+        // The original name of the file, and a comment, each end with a zero byte
+        for flag in &[8, 16] {
+            if flags & flag != 0 {
+                while bits.byte()? != 0 {}
+            }
+        }
+        if flags & 2 != 0 {
+            bits.u16()?;
+        }
+        self.started = true;
+        self.crc = 0;
+        self.size = 0;
+        Ok(())
+    }
+
+    // Returns whether another member follows
+    fn trailer(&mut self) -> io::Result<bool> {
+        self.inflate.bits.align();
+        let crc = self.inflate.bits.u32()?;
+        let size = self.inflate.bits.u32()?;
+        if crc != self.crc || size != self.size {
+            return Err(invalid("gzip file doesn't match its checksum"));
+        }
+        Ok(!self.inflate.bits.input.fill_buf()?.is_empty())
+    }
+}
+
+impl<R: BufRead> Read for Gzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.started {
+            self.header()?;
+        }
+        loop {
+            let n = self.inflate.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                self.crc = crc32(self.crc, &buf[..n]);
+                self.size = self.size.wrapping_add(n as u32);
+                return Ok(n);
+            }
+            if !self.trailer()? {
+                return Ok(0);
+            }
+            self.inflate.restart();
+            self.header()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflate_handles_stored_blocks() {
+        let data = [1, 3, 0, 252, 255, b'a', b'b', b'c'];
+        assert_eq!(b"abc".to_vec(), inflate(&data).unwrap());
+    }
+
+    #[test]
+    fn inflate_handles_fixed_blocks() {
+        // "猫猫猫猫猫猫。" compressed with zlib at level 9
+        let data = [123, 222, 179, 250, 57, 42, 122, 220, 208, 4, 0];
+        assert_eq!(
+            "猫猫猫猫猫猫。".as_bytes().to_vec(),
+            inflate(&data).unwrap()
+        );
+    }
+
+    #[test]
+    fn inflate_handles_dynamic_blocks() {
+        let data = [
+            221, 205, 193, 9, 128, 48, 20, 3, 208, 213, 205, 167, 23, 105, 11, 5, 189, 87, 65, 180,
+            162, 224, 2, 218, 101, 66, 105, 199, 240, 227, 24, 222, 194, 35, 36, 37, 228, 118, 239,
+            196, 85, 221, 65, 108, 132, 80, 44, 59, 41, 193, 151, 222, 171, 19, 153, 152, 170, 153,
+            9, 163, 78, 104, 121, 208, 102, 29, 35, 197, 17, 145, 176, 68, 162, 40, 166, 182, 218,
+            242, 104, 80, 95, 62, 63, 191, 169, 63, 92, 188,
+        ];
+        let text = "吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。".repeat(3);
+        assert_eq!(text.into_bytes(), inflate(&data).unwrap());
+    }
+
+    #[test]
+    fn inflate_rejects_truncated_input() {
+        assert!(inflate(&[123, 222, 179]).is_err());
+    }
+
+    #[test]
+    fn inflate_rejects_stored_blocks_with_the_wrong_length() {
+        let data = [1, 3, 0, 252, 254, b'a', b'b', b'c'];
+        assert!(inflate(&data).is_err());
+    }
+
+    #[test]
+    fn inflate_keeps_copying_from_far_back() {
+        // Stored blocks of 50000 bytes each, then a copy of 258 bytes from 32768 back
+        let text: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let mut data = Vec::new();
+        for block in text.chunks(50_000) {
+            data.push(0);
+            data.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            data.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+            data.extend_from_slice(block);
+        }
+        // A last fixed block: length 258 is 285, distance 32768 is 29 with 8191 extra
+        let mut bits: Vec<bool> = vec![true, true, false];
+        let mut push = |value: u32, count: u32, reversed: bool| {
+            for i in 0..count {
+                let bit = if reversed { count - 1 - i } else { i };
+                bits.push(value >> bit & 1 == 1);
+            }
+        };
+        push(0b1100_0101, 8, true);
+        push(29, 5, true);
+        push(8191, 13, false);
+        push(0, 7, true);
+        let mut packed = vec![0u8; bits.len().div_ceil(8)];
+        for (i, &bit) in bits.iter().enumerate() {
+            packed[i / 8] |= (bit as u8) << (i % 8);
+        }
+        data.extend_from_slice(&packed);
+        let mut expected = text.clone();
+        expected.extend_from_slice(&text[text.len() - 32768..text.len() - 32768 + 258]);
+        let mut out = Vec::new();
+        let mut reader = Inflate::new(&data[..]);
+        let mut buf = [0; 1000];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(expected, out);
+    }
+
+    // "犬だ。" compressed with gzip
+    const GZIP: [u8; 29] = [
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 123, 222, 181, 230, 113, 227, 130, 199, 13, 77, 0, 49, 37,
+        2, 56, 9, 0, 0, 0,
+    ];
+
+    #[test]
+    fn gzip_members_are_read_one_after_another() {
+        let data = [GZIP, GZIP].concat();
+        let mut out = String::new();
+        Gzip::new(&data[..]).read_to_string(&mut out).unwrap();
+        assert_eq!("犬だ。犬だ。", out);
+    }
+
+    #[test]
+    fn gzip_files_are_checked() {
+        let mut data = GZIP;
+        data[21] ^= 1;
+        assert!(Gzip::new(&data[..]).read_to_end(&mut Vec::new()).is_err());
+        let mut data = GZIP;
+        data[0] = 0;
+        assert!(Gzip::new(&data[..]).read_to_end(&mut Vec::new()).is_err());
+    }
+}
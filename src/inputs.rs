@@ -0,0 +1,171 @@
+//! Finding the files to read sentences from.
+use std::fs;
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+const WILDCARDS: [char; 3] = ['*', '?', '['];
+
+/// List every file under a directory, recursively, in a stable order.
+pub fn walk(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Check whether a string is a glob pattern, rather than a plain path.
+pub fn is_pattern(s: &str) -> bool {
+    s.contains(&WILDCARDS[..])
+}
+
+// The position of the `]` closing a character class, after its opening `[`.
+//
+// A `]` right at the start of the class is part of it, rather than closing it.
+fn class_end(rest: &[char]) -> Option<usize> {
+    let start = match rest.first() {
+        Some('!') | Some('^') => 2,
+        _ => 1,
+    };
+    let end = rest.get(start..)?.iter().position(|&c| c == ']')?;
+    Some(start + end)
+}
+
+// Match a single path component against a pattern with `*`, `?`, and `[...]`.
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+        Some(('[', rest)) if class_end(rest).is_some() => {
+            let end = class_end(rest).unwrap_or(0);
+            let c = match name.first() {
+                None => return false,
+                Some(&c) => c,
+            };
+            let negated = rest[0] == '!' || rest[0] == '^';
+            let class = &rest[if negated { 1 } else { 0 }..end];
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == c;
+                    i += 1;
+                }
+            }
+            found != negated && matches(&rest[end + 1..], &name[1..])
+        }
+        Some((p, rest)) => name.first() == Some(p) && matches(rest, &name[1..]),
+    }
+}
+
+// Match path components against pattern components, where `**` matches any number of them.
+fn matches_components(pattern: &[&str], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| matches_components(rest, &path[i..])),
+        Some((p, rest)) => match path.split_first() {
+            None => false,
+            Some((name, path)) => {
+                let p: Vec<char> = p.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                matches(&p, &name) && matches_components(rest, path)
+            }
+        },
+    }
+}
+
+/// Find every file matching a glob pattern, like `novels/**/*.txt`, in a stable order.
+///
+/// Only the directory named by the part of the pattern before any wildcard is searched.
+pub fn glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let components: Vec<&str> = pattern.split(path::is_separator).collect();
+    let literal = components.iter().take_while(|c| !is_pattern(c)).count();
+    let mut base = components[..literal].join("/");
+    if base.is_empty() && pattern.starts_with(path::is_separator) {
+        base.push('/');
+    }
+    let wild: Vec<&str> = components[literal..]
+        .iter()
+        .cloned()
+        .filter(|c| !c.is_empty())
+        .collect();
+    let root = if base.is_empty() { "." } else { base.as_str() };
+    let mut found = Vec::new();
+    for file in walk(Path::new(root))? {
+        let relative = match file.strip_prefix(root) {
+            Err(_) => continue,
+            Ok(r) => r,
+        };
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if matches_components(&wild, &parts) {
+            found.push(Path::new(&base).join(relative));
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn walk_finds_nested_files() -> io::Result<()> {
+        let root = env::temp_dir().join(format!("ginkou-walk-{}", process::id()));
+        fs::create_dir_all(root.join("b/c"))?;
+        fs::write(root.join("b/c/2.txt"), "")?;
+        fs::write(root.join("a.txt"), "")?;
+        let files = walk(&root);
+        fs::remove_dir_all(&root)?;
+        assert_eq!(vec![root.join("a.txt"), root.join("b/c/2.txt")], files?);
+        Ok(())
+    }
+
+    #[test]
+    fn components_match_wildcards() {
+        let m = |p: &str, n: &str| {
+            let p: Vec<char> = p.chars().collect();
+            let n: Vec<char> = n.chars().collect();
+            matches(&p, &n)
+        };
+        assert!(m("*.txt", "猫.txt"));
+        assert!(!m("*.txt", "a.epub"));
+        assert!(m("巻?.txt", "巻1.txt"));
+        assert!(m("[a-c]*", "book"));
+        assert!(!m("[!a-c]*", "book"));
+        assert!(m("[]]", "]"));
+        assert!(m("[!]", "[!]"));
+    }
+
+    #[test]
+    fn glob_finds_matching_files() -> io::Result<()> {
+        let root = env::temp_dir().join(format!("ginkou-glob-{}", process::id()));
+        fs::create_dir_all(root.join("a/b"))?;
+        fs::write(root.join("1.txt"), "")?;
+        fs::write(root.join("a/b/2.txt"), "")?;
+        fs::write(root.join("a/3.epub"), "")?;
+        let pattern = format!("{}/**/*.txt", root.display());
+        let files = glob(&pattern);
+        let shallow = glob(&format!("{}/*/*", root.display()));
+        fs::remove_dir_all(&root)?;
+        assert_eq!(vec![root.join("1.txt"), root.join("a/b/2.txt")], files?);
+        assert_eq!(vec![root.join("a/3.epub")], shallow?);
+        Ok(())
+    }
+}
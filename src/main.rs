@@ -1,30 +1,165 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::Write;
+use std::ops::AddAssign;
 use std::path::{Path, PathBuf};
 use std::string::FromUtf8Error;
+use std::thread;
+use std::time::{Duration, Instant};
 extern crate dirs;
 #[macro_use]
 extern crate rusqlite;
-use rusqlite::Connection;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, ToSql, TransactionBehavior};
 extern crate structopt;
 use structopt::StructOpt;
 extern crate mecab;
-use mecab::Tagger;
 
+mod clipboard;
+mod compression;
+mod config;
+mod deinflect;
+mod export;
+mod external;
+mod formats;
+mod inflate;
+mod inputs;
+mod json;
+mod nhk;
+mod normalize;
+mod passphrase;
+mod romaji;
+mod similar;
+mod splitter;
+mod store;
+mod suggest;
+mod tokenizer;
+mod web;
+mod zip;
 
-const DAKUTEN_BYTES: [u8; 3] = [227, 128, 130];
-const SQL_ADD_SENTENCE: &'static str = include_str!("sql/add_sentence.sql");
-const SQL_ADD_WORD_JUNCTION: &'static str = include_str!("sql/add_word_junction.sql");
-const SQL_ADD_WORD: &'static str = include_str!("sql/add_word.sql");
-const SQL_ALL_WORD_SENTENCES: &'static str = include_str!("sql/all_word_sentences.sql");
-const SQL_BEST_WORD_SENTENCES: &'static str = include_str!("sql/best_word_sentences.sql");
-const SQL_SETUP: &'static str = include_str!("sql/setup.sql");
+use formats::encoding::Encoding;
+use formats::{tatoeba, Format};
+use normalize::normalize;
+use store::{Location, SentenceStore};
+use tokenizer::{Token, Tokenizer};
+
+/// How many sentences a search shows, unless chosen otherwise.
+const LIMIT: usize = 200;
+/// How many characters a sentence can have before it's skipped, unless chosen otherwise.
+const MAX_LENGTH: usize = 500;
+/// How many letters a sentence needs to not be skipped, unless chosen otherwise.
+const MIN_LENGTH: usize = 2;
+/// How many sentences are added between commits, unless chosen otherwise.
+const BATCH_SIZE: usize = 5000;
+/// How many of the words the tokenizer didn't know are shown after adding sentences.
+const UNKNOWN_SHOWN: usize = 20;
+const SQL_ADD_ALIAS: &str = include_str!("sql/add_alias.sql");
+const SQL_ADD_HISTORY: &str = include_str!("sql/add_history.sql");
+const SQL_ADD_METADATA: &str = include_str!("sql/add_metadata.sql");
+const SQL_ADD_PART_OF_SPEECH: &str = include_str!("sql/add_part_of_speech.sql");
+const SQL_ADD_READING: &str = include_str!("sql/add_reading.sql");
+const SQL_ADD_SENTENCE: &str = include_str!("sql/add_sentence.sql");
+const SQL_ADD_TAG: &str = include_str!("sql/add_tag.sql");
+const SQL_ADD_TAG_JUNCTION: &str = include_str!("sql/add_tag_junction.sql");
+const SQL_ADD_TOKEN: &str = include_str!("sql/add_token.sql");
+const SQL_ADD_TRANSLATION: &str = include_str!("sql/add_translation.sql");
+const SQL_ADD_WORD_JUNCTION: &str = include_str!("sql/add_word_junction.sql");
+const SQL_ADD_WORD: &str = include_str!("sql/add_word.sql");
+const SQL_ALL_WORD_SENTENCES: &str = include_str!("sql/all_word_sentences.sql");
+const SQL_ATTACH: &str = include_str!("sql/attach.sql");
+const SQL_ATTACH_BANK: &str = include_str!("sql/attach_bank.sql");
+const SQL_BACKUP: &str = include_str!("sql/backup.sql");
+const SQL_BEST_WORD_SENTENCES: &str = include_str!("sql/best_word_sentences.sql");
+const SQL_CANONICAL_WORD: &str = include_str!("sql/canonical_word.sql");
+const SQL_CHECKPOINT: &str = include_str!("sql/checkpoint.sql");
+const SQL_CHECK_PHRASE_INDEX: &str = include_str!("sql/check_phrase_index.sql");
+const SQL_COMPACT: &str = include_str!("sql/compact.sql");
+const SQL_COUNT_COPIES: &str = include_str!("sql/count_copies.sql");
+const SQL_COUNT_OTHER_SENTENCES: &str = include_str!("sql/count_other_sentences.sql");
+const SQL_COUNT_PRUNED: &str = include_str!("sql/count_pruned.sql");
+const SQL_COUNT_REMOVED: &str = include_str!("sql/count_removed.sql");
+const SQL_COUNT_SENTENCES: &str = include_str!("sql/count_sentences.sql");
+const SQL_DATABASE_SIZE: &str = include_str!("sql/database_size.sql");
+const SQL_EXPORT_SENTENCES: &str = include_str!("sql/export_sentences.sql");
+const SQL_FIND_COPIES: &str = include_str!("sql/find_copies.sql");
+const SQL_FIND_ORPHAN_WORDS: &str = include_str!("sql/find_orphan_words.sql");
+const SQL_HAS_COLUMN: &str = include_str!("sql/has_column.sql");
+const SQL_HAS_METADATA: &str = include_str!("sql/has_metadata.sql");
+const SQL_HAS_PHRASE_INDEX: &str = include_str!("sql/has_phrase_index.sql");
+const SQL_HAS_SENTENCE: &str = include_str!("sql/has_sentence.sql");
+const SQL_HAS_TABLE: &str = include_str!("sql/has_table.sql");
+const SQL_HAS_WORD: &str = include_str!("sql/has_word.sql");
+const SQL_HISTORY: &str = include_str!("sql/history.sql");
+const SQL_IMPORT_DATABASE: &str = include_str!("sql/import_database.sql");
+const SQL_INDEX_PHRASES: &str = include_str!("sql/index_phrases.sql");
+const SQL_INTEGRITY_CHECK: &str = include_str!("sql/integrity_check.sql");
+const SQL_MOVE_ALIASES: &str = include_str!("sql/move_aliases.sql");
+const SQL_ORPHANS: &str = include_str!("sql/orphans.sql");
+const SQL_PHRASE_SENTENCES: &str = include_str!("sql/phrase_sentences.sql");
+const SQL_PRUNE_WORDS: &str = include_str!("sql/prune_words.sql");
+const SQL_RANDOM_SENTENCES: &str = include_str!("sql/random_sentences.sql");
+const SQL_REINDEX: &str = include_str!("sql/reindex.sql");
+const SQL_REMOVE_BY_ID: &str = include_str!("sql/remove_by_id.sql");
+const SQL_REMOVE_BY_SOURCE: &str = include_str!("sql/remove_by_source.sql");
+const SQL_REMOVE_COPIES: &str = include_str!("sql/remove_copies.sql");
+const SQL_REMOVE_MATCHING: &str = include_str!("sql/remove_matching.sql");
+const SQL_REMOVE_SENTENCES: &str = include_str!("sql/remove_sentences.sql");
+const SQL_RENAME_SOURCE: &str = include_str!("sql/rename_source.sql");
+const SQL_REMOVE_TAG: &str = include_str!("sql/remove_tag.sql");
+const SQL_SAVE_CHECKPOINT: &str = include_str!("sql/save_checkpoint.sql");
+const SQL_SAVE_SCHEMA_VERSION: &str = include_str!("sql/save_schema_version.sql");
+const SQL_SCAN_PHRASE_SENTENCES: &str = include_str!("sql/scan_phrase_sentences.sql");
+const SQL_SCHEMA_VERSION: &str = include_str!("sql/schema_version.sql");
+const SQL_SENTENCE: &str = include_str!("sql/sentence.sql");
+const SQL_SENTENCE_ID: &str = include_str!("sql/sentence_id.sql");
+const SQL_SENTENCE_METADATA: &str = include_str!("sql/sentence_metadata.sql");
+const SQL_SENTENCE_PIECES: &str = include_str!("sql/sentence_pieces.sql");
+const SQL_SENTENCE_TAGS: &str = include_str!("sql/sentence_tags.sql");
+#[cfg(test)]
+const SQL_SENTENCE_TOKENS: &str = include_str!("sql/sentence_tokens.sql");
+const SQL_SENTENCE_TRANSLATIONS: &str = include_str!("sql/sentence_translations.sql");
+const SQL_SENTENCE_WORDS: &str = include_str!("sql/sentence_words.sql");
+const SQL_SET_CREATED_AT: &str = include_str!("sql/set_created_at.sql");
+const SQL_SETUP: &str = include_str!("sql/setup.sql");
+const SQL_SETUP_HISTORY: &str = include_str!("sql/setup_history.sql");
+const SQL_SETUP_PHRASES: &str = include_str!("sql/setup_phrases.sql");
+const SQL_SETUP_REMOVED: &str = include_str!("sql/setup_removed.sql");
+const SQL_SETUP_SCHEMA_VERSION: &str = include_str!("sql/setup_schema_version.sql");
+const SQL_SETUP_SENTENCE_INDEX: &str = include_str!("sql/setup_sentence_index.sql");
+const SQL_SETUP_TOKEN_POS: &str = include_str!("sql/setup_token_pos.sql");
+const SQL_SETUP_UNIQUE: &str = include_str!("sql/setup_unique.sql");
+const SQL_SOURCES: &str = include_str!("sql/sources.sql");
+const SQL_TOKEN_PARTS_OF_SPEECH: &str = include_str!("sql/token_parts_of_speech.sql");
+const SQL_WORD_ID_READINGS: &str = include_str!("sql/word_id_readings.sql");
+const SQL_WORD_PARTS_OF_SPEECH: &str = include_str!("sql/word_parts_of_speech.sql");
+const SQL_WORD_POSITIONS: &str = include_str!("sql/word_positions.sql");
+const SQL_WORD_READINGS: &str = include_str!("sql/word_readings.sql");
+const SQL_WORD_SENTENCE_IDS: &str = include_str!("sql/word_sentence_ids.sql");
+const SQL_WORDS_MATCHING: &str = include_str!("sql/words_matching.sql");
+const SQL_WORDS_READ_AS: &str = include_str!("sql/words_read_as.sql");
 
 #[derive(Debug)]
 enum SentenceError {
     Utf8(FromUtf8Error),
     IO(io::Error),
+    /// A record in the input couldn't be understood.
+    Invalid(String),
+}
+
+impl fmt::Display for SentenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SentenceError::Utf8(e) => write!(f, "{}", e),
+            SentenceError::IO(e) => write!(f, "{}", e),
+            SentenceError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
 }
 
 impl From<FromUtf8Error> for SentenceError {
@@ -39,263 +174,4767 @@ impl From<io::Error> for SentenceError {
     }
 }
 
-struct Sentences<R> {
-    bytes: io::Bytes<R>,
-    done: bool,
+/// A sentence ready to be added, along with anything else known about it.
+#[derive(Clone, Debug, PartialEq)]
+struct Sentence {
+    text: String,
+    /// Extra information about the sentence, like its id in another corpus.
+    metadata: Vec<(String, String)>,
+    /// Translations of the sentence into other languages.
+    translations: Vec<String>,
+    /// Words to index the sentence under, beyond those found in it.
+    words: Vec<String>,
+    /// Labels to find the sentence by, like anime or news.
+    tags: Vec<String>,
 }
 
-impl<B: io::BufRead> Iterator for Sentences<B> {
-    type Item = Result<String, SentenceError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.done {
-            return None;
+impl From<String> for Sentence {
+    fn from(text: String) -> Self {
+        Sentence {
+            text,
+            metadata: Vec::new(),
+            translations: Vec::new(),
+            words: Vec::new(),
+            tags: Vec::new(),
         }
-        let mut buf = Vec::new();
-        let mut match_index = 0;
-        while match_index < 3 {
-            let byte = match self.bytes.next() {
-                None => break,
-                Some(Err(e)) => return Some(Err(e.into())),
-                Some(Ok(b)) => b,
-            };
-            buf.push(byte);
-            if byte == DAKUTEN_BYTES[match_index] {
-                match_index += 1;
-            } else {
-                match_index = 0;
-            }
+    }
+}
+
+impl<'a> From<&'a str> for Sentence {
+    fn from(text: &'a str) -> Self {
+        Sentence::from(strip_whitespace(text))
+    }
+}
+
+fn strip_whitespace(text: &str) -> String {
+    text.replace(|x: char| x.is_whitespace(), "")
+}
+
+/// Check whether some text contains any kana or kanji.
+fn has_japanese(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c, '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ff66}'..='\u{ff9f}')
+    })
+}
+
+// Delimiters are given as the characters ending a sentence, where `\n` is a new line
+fn parse_delimiters(delimiters: &str) -> Vec<String> {
+    delimiters
+        .replace("\\n", "\n")
+        .chars()
+        .map(String::from)
+        .collect()
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.query_row(SQL_HAS_COLUMN, params![table, column], |row| row.get(0))
+}
+
+// Tables made by older versions are missing the columns added since,
+// which are left empty for the rows already there
+fn add_missing_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    kind: &str,
+) -> rusqlite::Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, kind
+        ))?;
+    }
+    Ok(())
+}
+
+fn has_phrase_index(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(SQL_HAS_PHRASE_INDEX, params![], |row| row.get(0))
+}
+
+/// Every change made to the schema, in order, so a database at version n has had the first n.
+///
+/// Each is described for when an older database is upgraded. New changes go at the end,
+/// and ones already made never change.
+const MIGRATIONS: [(&str, &str); 3] = [
+    (
+        "Set up the tables for sentences, words, and everything known about them",
+        SQL_SETUP,
+    ),
+    (
+        "Keep a history of every time sentences were added",
+        SQL_SETUP_HISTORY,
+    ),
+    (
+        "Keep the part of speech each word is used as in every sentence",
+        SQL_SETUP_TOKEN_POS,
+    ),
+];
+
+fn has_table(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.query_row(SQL_HAS_TABLE, params![table], |row| row.get(0))
+}
+
+fn schema_version(conn: &Connection) -> rusqlite::Result<usize> {
+    let version: i64 = conn.query_row(SQL_SCHEMA_VERSION, params![], |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+// Databases from before versions were kept could be missing any table or column of the first version,
+// which only creates what's missing
+fn apply_migrations(conn: &Connection, version: usize) -> rusqlite::Result<Vec<&'static str>> {
+    if version == 0 && has_table(conn, "Sentences")? {
+        add_missing_column(conn, "Sentences", "created_at", "TEXT")?;
+        add_missing_column(conn, "Words", "created_at", "TEXT")?;
+    }
+    let mut changes = Vec::new();
+    for (i, &(change, migration)) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(migration)?;
+        conn.execute(SQL_SAVE_SCHEMA_VERSION, params![(i + 1) as i64])?;
+        changes.push(change);
+    }
+    Ok(changes)
+}
+
+/// Bring a database up to the latest version of the schema, returning what changed.
+///
+/// Every migration is made in one transaction, so a database is never left halfway between versions.
+fn migrate(conn: &Connection) -> rusqlite::Result<Vec<&'static str>> {
+    conn.execute_batch(SQL_SETUP_SCHEMA_VERSION)?;
+    let version = schema_version(conn)?;
+    if version >= MIGRATIONS.len() {
+        return Ok(Vec::new());
+    }
+    conn.execute_batch("BEGIN;")?;
+    match apply_migrations(conn, version) {
+        Err(e) => {
+            conn.execute_batch("ROLLBACK;")?;
+            Err(e)
         }
-        if buf.len() == 0 {
-            self.done = true;
-            return None;
+        Ok(changes) => {
+            conn.execute_batch("COMMIT;")?;
+            Ok(changes)
         }
-        let next = String::from_utf8(buf).map_err(SentenceError::from);
-        Some(next.map(|x| x.replace(|x: char| x.is_whitespace(), "")))
     }
 }
 
-fn sentences<R: io::BufRead>(reader: R) -> Sentences<R> {
-    Sentences {
-        bytes: reader.bytes(),
-        done: false,
+fn backup_path(path: &Path, version: usize) -> PathBuf {
+    PathBuf::from(format!("{}.v{}.bak", path.display(), version))
+}
+
+// The copy is made by SQLite, so it also has whatever is still in the write-ahead log
+fn backup(conn: &Connection, backup: &Path) -> rusqlite::Result<()> {
+    if backup.exists() {
+        let _ = fs::remove_file(backup);
+    }
+    conn.execute(SQL_BACKUP, params![backup.to_string_lossy()])?;
+    Ok(())
+}
+
+// The write-ahead log of the upgraded database doesn't belong with the copy put back
+fn restore(path: &Path, backup: &Path) {
+    let restored = fs::copy(backup, path).and_then(|_| {
+        for suffix in &["-wal", "-shm"] {
+            let log = PathBuf::from(format!("{}{}", path.display(), suffix));
+            if log.exists() {
+                fs::remove_file(log)?;
+            }
+        }
+        Ok(())
+    });
+    match restored {
+        Err(e) => println!(
+            "Couldn't put back {} as it was, which is still at {}:\n {}",
+            path.display(),
+            backup.display(),
+            e
+        ),
+        Ok(()) => println!(
+            "Couldn't upgrade {}, so it was put back as it was",
+            path.display()
+        ),
     }
 }
 
+// A copy of an older database is taken before upgrading it, and put back if upgrading fails
+fn upgrade(conn: Connection, path: &Path, version: usize) -> rusqlite::Result<Connection> {
+    let copy = backup_path(path, version);
+    backup(&conn, &copy)?;
+    match migrate(&conn) {
+        Err(e) => {
+            drop(conn);
+            restore(path, &copy);
+            Err(e)
+        }
+        Ok(changes) => {
+            println!(
+                "Upgraded {} from version {} to {}, keeping a copy from before at {}:",
+                path.display(),
+                version,
+                MIGRATIONS.len(),
+                copy.display()
+            );
+            for change in changes {
+                println!("    {}", change);
+            }
+            Ok(conn)
+        }
+    }
+}
 
+// Sentences already added are indexed when the full text index is first created.
+// SQLite might be built without FTS5, in which case phrases are searched for without it.
 fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
-    conn.execute_batch(SQL_SETUP)
+    migrate(conn)?;
+    // Databases from before sentences were kept unique can hold copies already,
+    // which still need an index to look new sentences up with
+    if conn.execute_batch(SQL_SETUP_UNIQUE).is_err() {
+        conn.execute_batch(SQL_SETUP_SENTENCE_INDEX)?;
+    }
+    let indexed = has_phrase_index(conn)?;
+    if conn.execute_batch(SQL_SETUP_PHRASES).is_ok() && !indexed {
+        conn.execute_batch(SQL_INDEX_PHRASES)?;
+    }
+    Ok(())
+}
+
+// SQLCipher needs the key before anything else is done with the database.
+// Plain SQLite ignores it, so ginkou refuses to take a passphrase when built without SQLCipher.
+fn unlock(conn: &Connection, key: Option<&str>) -> rusqlite::Result<()> {
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", &key)?;
+    }
+    Ok(())
 }
 
 fn conn_from_disk<P: AsRef<Path>>(path: P) -> rusqlite::Result<Connection> {
-    let existed = path.as_ref().exists();
-    let conn = Connection::open(path)?;
-    if !existed {
-        create_tables(&conn)?;
+    conn_with_key(path, None)
+}
+
+// Older databases are upgraded to the latest version of the schema when opened
+fn conn_with_key<P: AsRef<Path>>(path: P, key: Option<&str>) -> rusqlite::Result<Connection> {
+    let path = path.as_ref();
+    let mut conn = Connection::open(path)?;
+    unlock(&conn, key)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.execute_batch(SQL_SETUP_SCHEMA_VERSION)?;
+    let version = schema_version(&conn)?;
+    if version < MIGRATIONS.len() && has_table(&conn, "Sentences")? {
+        conn = upgrade(conn, path, version)?;
+    }
+    create_tables(&conn)?;
+    Ok(conn)
+}
+
+fn cant_open(message: String) -> rusqlite::Error {
+    let error = rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN);
+    rusqlite::Error::SqliteFailure(error, Some(message))
+}
+
+/// Open a database only to read from it, which never changes it, or creates one that isn't there.
+///
+/// Databases made by older versions can't be upgraded without writing to them, so they aren't opened.
+fn conn_read_only(path: &Path, key: Option<&str>) -> rusqlite::Result<Connection> {
+    if !path.exists() {
+        return Err(cant_open(format!(
+            "there's no database at {}",
+            path.display()
+        )));
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    unlock(&conn, key)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    let current = has_table(&conn, "SchemaVersion")? && schema_version(&conn)? >= MIGRATIONS.len();
+    if !current {
+        return Err(cant_open(format!(
+            "{} needs upgrading first, which any command that changes it, like compact, does",
+            path.display()
+        )));
     }
     Ok(conn)
 }
 
+/// How long to wait for another process to be done with the database, before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Another process writing to the database for longer than the timeout, like a large import
+fn is_busy(e: &rusqlite::Error) -> bool {
+    match e {
+        rusqlite::Error::SqliteFailure(e, _) => {
+            e.code == ErrorCode::DatabaseBusy || e.code == ErrorCode::DatabaseLocked
+        }
+        _ => false,
+    }
+}
+
+/// How much memory SQLite can cache pages in when going fast, in KiB.
+const FAST_CACHE_SIZE: i64 = 64 * 1024;
+
+// Write-ahead logging lets searches run during an import, and makes commits much cheaper.
+// Some filesystems, like network shares, can't use WAL, and SQLite keeps its usual journal.
+fn write_ahead(conn: &Connection) -> rusqlite::Result<()> {
+    let mode: String = conn.query_row("PRAGMA journal_mode=WAL", params![], |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        println!(
+            "Write-ahead logging isn't supported here, using {} instead",
+            mode
+        );
+    }
+    Ok(())
+}
+
+// Syncing less often can only lose the last commits on a power loss, never corrupt anything.
+fn go_fast(conn: &Connection) -> rusqlite::Result<()> {
+    write_ahead(conn)?;
+    conn.execute_batch("PRAGMA synchronous=NORMAL;")?;
+    conn.execute_batch(&format!("PRAGMA cache_size=-{};", FAST_CACHE_SIZE))
+}
+
+#[cfg(test)]
 fn conn_from_memory() -> rusqlite::Result<Connection> {
     let conn = Connection::open_in_memory()?;
     create_tables(&conn)?;
     Ok(conn)
 }
 
+// Adding runs for every sentence and word, so statements are prepared once and cached
 fn add_sentence(conn: &Connection, sentence: &str) -> rusqlite::Result<u32> {
-    conn.execute(SQL_ADD_SENTENCE, params![sentence])?;
-    Ok(conn.last_insert_rowid() as u32)
+    let mut stmt = conn.prepare_cached(SQL_ADD_SENTENCE)?;
+    Ok(stmt.insert(params![sentence])? as u32)
 }
 
-fn add_word(conn: &Connection, word: &str, sentence_id: u32) -> rusqlite::Result<()> {
-    conn.execute(SQL_ADD_WORD, params![word])?;
-    conn.execute(SQL_ADD_WORD_JUNCTION, params![word, sentence_id])?;
+fn add_tag(conn: &Connection, sentence_id: u32, tag: &str) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_TAG)?.execute(params![tag])?;
+    conn.prepare_cached(SQL_ADD_TAG_JUNCTION)?
+        .execute(params![sentence_id, tag])?;
     Ok(())
 }
 
-fn matching_word(conn: &Connection, word: &str) -> rusqlite::Result<Vec<String>> {
-    let mut stmt = conn.prepare_cached(SQL_ALL_WORD_SENTENCES)?;
-    let mut buffer = Vec::new();
-    let results = stmt.query_map(params![word], |row| row.get(0))?;
-    for r in results {
-        let s: String = r?;
-        buffer.push(s);
-    }
-    Ok(buffer)
+// Whether the sentence had the tag to remove
+fn remove_tag(conn: &Connection, sentence_id: u32, tag: &str) -> rusqlite::Result<bool> {
+    let removed = conn.execute(SQL_REMOVE_TAG, params![sentence_id, tag])?;
+    Ok(removed > 0)
 }
 
-// This will ignore broken pipes, to support unix piping into things like head
-fn print_matching_words(conn: &Connection, word: &str, all: bool) -> rusqlite::Result<()> {
-    let query = if all {
-        SQL_ALL_WORD_SENTENCES
-    } else {
-        SQL_BEST_WORD_SENTENCES
-    };
-    let mut stmt = conn.prepare_cached(query)?;
-    let results = stmt.query_map(params![word], |row| row.get(0))?;
-    for r in results {
-        let r: String = r?;
-        if let Err(e) = write!(io::stdout(), "{}\n", r) {
-            if e.kind() != io::ErrorKind::BrokenPipe {
-                panic!(e);
-            }
+fn sentence(conn: &Connection, sentence_id: u32) -> rusqlite::Result<Option<String>> {
+    conn.prepare_cached(SQL_SENTENCE)?
+        .query_row(params![sentence_id], |row| row.get(0))
+        .optional()
+}
+
+fn has_sentence(conn: &Connection, sentence: &str) -> rusqlite::Result<bool> {
+    conn.prepare_cached(SQL_HAS_SENTENCE)?
+        .query_row(params![sentence], |row| row.get(0))
+}
+
+// The first of each set of identical sentences is kept, along with the words,
+// metadata, and translations of every copy
+fn remove_copies(conn: &mut Connection) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQL_FIND_COPIES)?;
+    let copies: i64 = tx.query_row(SQL_COUNT_COPIES, params![], |row| row.get(0))?;
+    tx.execute_batch(SQL_REMOVE_COPIES)?;
+    tx.execute_batch(SQL_SETUP_UNIQUE)?;
+    tx.commit()?;
+    Ok(copies as usize)
+}
+
+/// Remove the sentences a query picks out, along with everything stored with them.
+///
+/// The query inserts the ids of the sentences to remove into temp.Removed.
+fn remove_sentences(
+    conn: &mut Connection,
+    query: &str,
+    params: &[&dyn ToSql],
+) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQL_SETUP_REMOVED)?;
+    tx.execute(query, params)?;
+    let removed = count_sentences(&tx, SQL_COUNT_REMOVED)?;
+    tx.execute_batch(SQL_REMOVE_SENTENCES)?;
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// Remove the words no sentence contains anymore, along with their readings, returning how many there were.
+fn prune_words(conn: &mut Connection) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQL_FIND_ORPHAN_WORDS)?;
+    let pruned = count_sentences(&tx, SQL_COUNT_PRUNED)?;
+    tx.execute_batch(SQL_PRUNE_WORDS)?;
+    tx.commit()?;
+    Ok(pruned)
+}
+
+fn sentence_pieces(conn: &Connection) -> rusqlite::Result<Vec<(u32, Vec<similar::Piece>)>> {
+    let mut stmt = conn.prepare(SQL_SENTENCE_PIECES)?;
+    let rows = stmt.query_map(params![], |row| {
+        let piece = similar::Piece {
+            surface: row.get(1)?,
+            word: row.get(2)?,
+        };
+        Ok((row.get(0)?, piece))
+    })?;
+    let mut sentences: Vec<(u32, Vec<similar::Piece>)> = Vec::new();
+    for row in rows {
+        let (id, piece) = row?;
+        match sentences.last_mut() {
+            Some((last, pieces)) if *last == id => pieces.push(piece),
+            _ => sentences.push((id, vec![piece])),
         }
     }
+    Ok(sentences)
+}
+
+// Only sentences whose tokens were stored can be compared
+fn print_near_copies(conn: &Connection) -> rusqlite::Result<()> {
+    let sentences = sentence_pieces(conn)?;
+    let pieces: Vec<Vec<similar::Piece>> = sentences.iter().map(|s| s.1.clone()).collect();
+    let copies = similar::near_copies(&pieces);
+    for &(copy, earlier) in &copies {
+        let text = |i: usize| -> rusqlite::Result<String> {
+            Ok(sentence(conn, sentences[i].0)?.unwrap_or_default())
+        };
+        let (copy, earlier) = (text(copy)?, text(earlier)?);
+        println!("{} is nearly the same as {}", copy, earlier);
+    }
+    println!(
+        "Found {} sentences nearly the same as another",
+        copies.len()
+    );
     Ok(())
 }
 
-fn consume_trimmed(conn: &Connection, trimmed: &str) -> rusqlite::Result<()> {
-    let sentence_id = add_sentence(conn, trimmed)?;
-    let mut tagger = Tagger::new("");
-    tagger.parse_nbest_init(trimmed);
-    let mecab_out = tagger.next().unwrap();
-    for l in mecab_out.lines() {
-        if l == "EOS" {
-            break;
+// The full text index is rebuilt along with the others, if there is one
+fn compact(conn: &Connection, reindex: bool) -> rusqlite::Result<()> {
+    if reindex {
+        conn.execute_batch(SQL_REINDEX)?;
+        if has_phrase_index(conn)? {
+            conn.execute_batch(SQL_INDEX_PHRASES)?;
         }
-        let tab_index = l.find('\t').unwrap();
-        let (_, rest) = l.split_at(tab_index);
-        // Remove the leading tab
-        let rest = &rest[1..];
-        let root = rest.split(',').skip(6).next().unwrap();
-        add_word(conn, root, sentence_id)?;
     }
-    Ok(())
+    conn.execute_batch(SQL_COMPACT)
 }
 
-fn consume_sentences<R: io::BufRead>(conn: &Connection, reader: R) -> rusqlite::Result<()> {
-    let mut i = 0;
-    for sentence in sentences(reader) {
-        i += 1;
-        if sentence.is_err() {
-            println!("Err on #{}: {:?}", i, sentence);
-            continue;
-        };
-        let sentence = sentence.unwrap();
-        println!("#{}: {}", i, sentence);
-        consume_trimmed(conn, &sentence)?;
+fn rotated_path(dest: &Path, n: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", dest.display(), n))
+}
+
+// The oldest copy is dropped, and the others move back one place, so the last one is at dest.1
+fn rotate_backups(dest: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 || !dest.exists() {
+        return Ok(());
+    }
+    let oldest = rotated_path(dest, keep);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for n in (1..keep).rev() {
+        let from = rotated_path(dest, n);
+        if from.exists() {
+            fs::rename(from, rotated_path(dest, n + 1))?;
+        }
+    }
+    fs::rename(dest, rotated_path(dest, 1))
+}
+
+/// Copy a database to a new file, while other processes might still be writing to it.
+///
+/// The copy is written next to the destination first, so the backups already there
+/// are only moved back once it's complete. The copy of an encrypted database is
+/// encrypted with the same key.
+fn backup_to(
+    conn: &Connection,
+    dest: &Path,
+    keep: usize,
+    key: Option<&str>,
+) -> rusqlite::Result<()> {
+    let partial = PathBuf::from(format!("{}.partial", dest.display()));
+    if partial.exists() {
+        let _ = fs::remove_file(&partial);
+    }
+    {
+        let mut copy = Connection::open(&partial)?;
+        unlock(&copy, key)?;
+        let backup = Backup::new(conn, &mut copy)?;
+        // SQLite starts over by itself when a write happens in between two steps
+        backup.run_to_completion(100, Duration::from_millis(10), None)?;
+    }
+    let moved = rotate_backups(dest, keep).and_then(|_| fs::rename(&partial, dest));
+    if let Err(e) = moved {
+        println!(
+            "Couldn't move the backup to {}, leaving it at {}:\n {}",
+            dest.display(),
+            partial.display(),
+            e
+        );
     }
     Ok(())
 }
 
-#[derive(Debug, StructOpt)]
-#[structopt(name = "ginkou", about = "Japanese sentence bank")]
-enum Ginkou {
-    /// Add new sentences to the database.
-    #[structopt(name = "add")]
-    Add {
-        /// The file to read sentences from.
-        ///
-        /// If no file is given, sentences will be read from stdin.
-        #[structopt(long, short = "f", parse(from_os_str))]
-        file: Option<PathBuf>,
-        /// The database to use.
-        #[structopt(long = "database", short = "d", parse(from_os_str))]
-        db: Option<PathBuf>,
-    },
-    /// Search for all sentences containing a given word.
-    #[structopt(name = "get")]
-    Get {
-        /// The word to search for in the database.
-        word: String,
-        /// Show all results instead of shortest 200
-        #[structopt(long = "allwords", short = "a")]
-        all: bool,
-        /// The database to use.
-        #[structopt(long = "database", short = "d", parse(from_os_str))]
-        db: Option<PathBuf>,
-    },
+/// Find anything wrong with a database, like rows pointing at sentences that aren't there.
+///
+/// Each problem is described in a line, so a database with none is consistent.
+fn check(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let mut stmt = conn.prepare(SQL_INTEGRITY_CHECK)?;
+    for row in stmt.query_map(params![], |row| row.get::<_, String>(0))? {
+        let row = row?;
+        if row != "ok" {
+            problems.push(row);
+        }
+    }
+    let mut stmt = conn.prepare(SQL_ORPHANS)?;
+    let orphans = stmt.query_map(params![], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    for orphan in orphans {
+        let (table, parent, count) = orphan?;
+        problems.push(format!(
+            "{} rows of {} point at rows of {} that aren't there",
+            count, table, parent
+        ));
+    }
+    if has_phrase_index(conn)? {
+        if let Err(e) = conn.execute_batch(SQL_CHECK_PHRASE_INDEX) {
+            problems.push(format!(
+                "The index for searching phrases is out of date: {}",
+                e
+            ));
+        }
+    }
+    Ok(problems)
 }
 
-fn default_db_path() -> PathBuf {
-    if let Some(mut pb) = dirs::home_dir() {
-        pb.push(".ginkoudb");
-        pb
-    } else {
-        PathBuf::from(".ginkoudb")
+fn sentence_record(
+    conn: &Connection,
+    id: u32,
+    sentence: String,
+    created_at: Option<String>,
+) -> rusqlite::Result<export::Record> {
+    let mut stmt = conn.prepare_cached(SQL_SENTENCE_WORDS)?;
+    let words: Vec<(i64, String)> = stmt
+        .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut readings = conn.prepare_cached(SQL_WORD_ID_READINGS)?;
+    let mut with_readings = Vec::with_capacity(words.len());
+    for (word_id, word) in words {
+        let read = readings
+            .query_map(params![word_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        with_readings.push((word, read));
     }
+    let mut tags = conn.prepare_cached(SQL_SENTENCE_TAGS)?;
+    let tags = tags
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(export::Record {
+        id,
+        sentence,
+        source: sentence_metadata(conn, id, "source")?,
+        tags,
+        translations: sentence_translations(conn, id)?,
+        words: with_readings,
+        created_at,
+    })
 }
 
-fn main() -> rusqlite::Result<()> {
-    let opt = Ginkou::from_args();
-    match opt {
-        Ginkou::Get { word, all, db } => {
-            let db_path = db.unwrap_or(default_db_path());
-            let mut conn = conn_from_disk(&db_path)?;
-            print_matching_words(&mut conn, &word, all)?;
-        }
-        Ginkou::Add { file, db } => {
-            let db_path = db.unwrap_or(default_db_path());
-            let mut conn = conn_from_disk(&db_path)?;
-            let tx = conn.transaction()?;
-            match file {
-                None => {
-                    consume_sentences(&tx, io::BufReader::new(io::stdin()))?;
-                }
-                Some(path) => {
-                    let file_res = File::open(&path);
-                    if let Err(e) = file_res {
-                        println!("Couldn't open {}:\n {}", path.as_path().display(), e);
-                        return Ok(());
-                    }
-                    let file = file_res.unwrap();
-                    consume_sentences(&tx, io::BufReader::new(file))?;
-                }
-            };
-            tx.commit()?;
+/// Write every sentence out, returning how many were.
+///
+/// Writing stops at the first error, which is printed unless it's a pipe being closed.
+fn export_sentences(
+    conn: &Connection,
+    format: export::Format,
+    out: &mut dyn Write,
+) -> rusqlite::Result<usize> {
+    let report = |e: io::Error| {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            println!("Couldn't write the export:\n {}", e);
         }
     };
-    Ok(())
+    if let Some(header) = export::header(format) {
+        if let Err(e) = writeln!(out, "{}", header) {
+            report(e);
+            return Ok(0);
+        }
+    }
+    let mut stmt = conn.prepare(SQL_EXPORT_SENTENCES)?;
+    let rows = stmt.query_map(params![], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    let mut count = 0;
+    for row in rows {
+        let (id, sentence, created_at) = row?;
+        let record = sentence_record(conn, id, sentence, created_at)?;
+        if let Err(e) = writeln!(out, "{}", export::line(format, &record)) {
+            report(e);
+            break;
+        }
+        count += 1;
+    }
+    Ok(count)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn sentence_id(conn: &Connection, sentence: &str) -> rusqlite::Result<Option<u32>> {
+    conn.prepare_cached(SQL_SENTENCE_ID)?
+        .query_row(params![sentence], |row| row.get(0))
+        .optional()
+}
 
-    #[test]
-    fn sentences_works_correctly() {
-        let string = "A。\n  B。\n\n XXC。";
-        let mut iter = sentences(std::io::BufReader::new(string.as_bytes()));
-        let a = iter.next();
-        assert_eq!(String::from("A。"), a.unwrap().unwrap());
-        let b = iter.next();
-        assert_eq!(String::from("B。"), b.unwrap().unwrap());
-        let c = iter.next();
-        assert_eq!(String::from("XXC。"), c.unwrap().unwrap());
+fn count_sentences(conn: &Connection, query: &str) -> rusqlite::Result<usize> {
+    let count: i64 = conn.query_row(query, params![], |row| row.get(0))?;
+    Ok(count as usize)
+}
+
+/// Merge another database into this one, returning how many of its sentences were new.
+///
+/// Sentences already here keep what they had, and gain the words, tags, and the like of their copy.
+/// An encrypted database can only import from one with the same key.
+fn import_database(
+    conn: &mut Connection,
+    path: &Path,
+    key: Option<&str>,
+) -> rusqlite::Result<Summary> {
+    // The other database is upgraded first, so that the tables of both match
+    drop(conn_with_key(path, key)?);
+    let before = count_sentences(conn, SQL_COUNT_SENTENCES)?;
+    conn.execute(SQL_ATTACH, params![path.to_string_lossy()])?;
+    let tx = conn.transaction()?;
+    let theirs = count_sentences(&tx, SQL_COUNT_OTHER_SENTENCES)?;
+    tx.execute_batch(SQL_IMPORT_DATABASE)?;
+    tx.commit()?;
+    conn.execute_batch("DETACH DATABASE other;")?;
+    let added = count_sentences(conn, SQL_COUNT_SENTENCES)? - before;
+    Ok(Summary {
+        sentences: added,
+        errors: 0,
+        duplicates: theirs - added,
+    })
+}
+
+// Sentences already here keep what they had, and gain the words and tags of their copy
+fn import_record(conn: &Connection, record: &export::Record) -> rusqlite::Result<bool> {
+    let (id, new) = match sentence_id(conn, &record.sentence)? {
+        Some(id) => (id, false),
+        None => (add_sentence(conn, &record.sentence)?, true),
+    };
+    if new {
+        if let Some(ref created_at) = record.created_at {
+            conn.prepare_cached(SQL_SET_CREATED_AT)?
+                .execute(params![id, created_at])?;
+        }
+        if let Some(ref source) = record.source {
+            add_metadata(conn, id, "source", source)?;
+        }
+        for translation in &record.translations {
+            add_translation(conn, id, translation)?;
+        }
+    }
+    for (word, readings) in &record.words {
+        add_word(conn, word, id)?;
+        for reading in readings {
+            add_reading(conn, word, reading)?;
+        }
+    }
+    for tag in &record.tags {
+        add_tag(conn, id, tag)?;
     }
+    Ok(new)
+}
 
-    #[test]
-    fn bank_lookup_works_correctly() -> rusqlite::Result<()> {
-        let conn = conn_from_memory()?;
-        let sentence1 = String::from("A B");
-        let sentence2 = String::from("A B C");
-        let s1 = add_sentence(&conn, &sentence1)?;
-        add_word(&conn, "A", s1)?;
-        add_word(&conn, "B", s1)?;
-        let s2 = add_sentence(&conn, &sentence2)?;
-        add_word(&conn, "A", s2)?;
-        add_word(&conn, "B", s2)?;
-        add_word(&conn, "C", s2)?;
-        let a_sentences = vec![sentence1.clone(), sentence2.clone()];
-        assert_eq!(Ok(a_sentences), matching_word(&conn, "A"));
-        let c_sentences = vec![sentence2.clone()];
-        assert_eq!(Ok(c_sentences), matching_word(&conn, "C"));
-        Ok(())
+/// Import the sentences of a JSON export, with the words it lists instead of splitting them again.
+fn import_export<R: io::BufRead>(conn: &Connection, input: R) -> rusqlite::Result<Summary> {
+    let mut summary = Summary::default();
+    for (i, line) in input.lines().enumerate() {
+        let record = match line.map_err(|e| e.to_string()) {
+            Ok(ref line) if line.trim().is_empty() => continue,
+            Ok(line) => export::parse_line(&line),
+            Err(e) => Err(e),
+        };
+        match record {
+            Err(e) => {
+                println!("Err on line {}: {}", i + 1, e);
+                summary.errors += 1;
+            }
+            Ok(record) => {
+                if import_record(conn, &record)? {
+                    summary.sentences += 1;
+                } else {
+                    summary.duplicates += 1;
+                }
+            }
+        }
     }
+    Ok(summary)
+}
 
-    #[test]
-    fn sentences_can_be_consumed() -> rusqlite::Result<()> {
-        let conn = conn_from_memory()?;
-        let sentence1 = "猫を見た";
-        let sentence2 = "犬を見る";
-        consume_trimmed(&conn, sentence1)?;
-        consume_trimmed(&conn, sentence2)?;
-        let a_sentences = vec![sentence1.into(), sentence2.into()];
-        assert_eq!(Ok(a_sentences), matching_word(&conn, "見る"));
-        let b_sentences = vec![sentence2.into()];
-        assert_eq!(Ok(b_sentences), matching_word(&conn, "犬"));
-        let c_sentences = vec![sentence1.into()];
-        assert_eq!(Ok(c_sentences), matching_word(&conn, "猫"));
-        Ok(())
+/// Describe a number of bytes, like 1.5 MB.
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+fn database_size(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row(SQL_DATABASE_SIZE, params![], |row| row.get(0))
+}
+
+fn add_metadata(
+    conn: &Connection,
+    sentence_id: u32,
+    key: &str,
+    value: &str,
+) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_METADATA)?
+        .execute(params![sentence_id, key, value])?;
+    Ok(())
+}
+
+fn sentence_metadata(
+    conn: &Connection,
+    sentence_id: u32,
+    key: &str,
+) -> rusqlite::Result<Option<String>> {
+    conn.prepare_cached(SQL_SENTENCE_METADATA)?
+        .query_row(params![sentence_id, key], |row| row.get(0))
+        .optional()
+}
+
+/// Where some sentences came from, like the file they were added from.
+#[derive(Debug, PartialEq)]
+struct Source {
+    name: String,
+    sentences: usize,
+    /// How many different words these sentences contain.
+    words: usize,
+    /// The day the first of these sentences was added, if that's known.
+    added: Option<String>,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} sentences, {} words",
+            self.name, self.sentences, self.words
+        )?;
+        match self.added {
+            None => Ok(()),
+            Some(ref added) => write!(f, ", added {}", added),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// List every source sentences were added from, starting with the oldest.
+fn sources(conn: &Connection) -> rusqlite::Result<Vec<Source>> {
+    let mut stmt = conn.prepare(SQL_SOURCES)?;
+    let rows = stmt.query_map(params![], |row| {
+        Ok(Source {
+            name: row.get(0)?,
+            sentences: row.get::<_, i64>(1)? as usize,
+            words: row.get::<_, i64>(2)? as usize,
+            added: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Give the sentences from one source another, returning how many there were.
+///
+/// If there already are sentences from the new source, the two are merged.
+fn rename_source(conn: &Connection, old: &str, new: &str) -> rusqlite::Result<usize> {
+    conn.execute(SQL_RENAME_SOURCE, params![old, new])
+}
+
+// Check whether any sentence has some metadata, like the page it came from
+fn has_metadata(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<bool> {
+    conn.query_row(SQL_HAS_METADATA, params![key, value], |row| row.get(0))
+}
+
+fn add_translation(conn: &Connection, sentence_id: u32, translation: &str) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_TRANSLATION)?
+        .execute(params![sentence_id, translation])?;
+    Ok(())
+}
+
+fn add_word(conn: &Connection, word: &str, sentence_id: u32) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_WORD)?.execute(params![word])?;
+    conn.prepare_cached(SQL_ADD_WORD_JUNCTION)?
+        .execute(params![word, sentence_id])?;
+    Ok(())
+}
+
+fn add_reading(conn: &Connection, word: &str, reading: &str) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_READING)?
+        .execute(params![word, reading])?;
+    Ok(())
+}
+
+fn add_token(
+    conn: &Connection,
+    sentence_id: u32,
+    position: usize,
+    token: &Token,
+) -> rusqlite::Result<()> {
+    let start = token.start.map(|s| s as i64);
+    let params = params![
+        sentence_id,
+        position as i64,
+        start,
+        token.surface,
+        token.lemma,
+        token.pos
+    ];
+    conn.prepare_cached(SQL_ADD_TOKEN)?.execute(params)?;
+    Ok(())
+}
+
+// The words found in a sentence, in order
+#[cfg(test)]
+fn sentence_tokens(
+    conn: &Connection,
+    sentence_id: u32,
+) -> rusqlite::Result<Vec<(Option<i64>, String)>> {
+    let mut stmt = conn.prepare_cached(SQL_SENTENCE_TOKENS)?;
+    let results = stmt.query_map(params![sentence_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    results.collect()
+}
+
+fn add_part_of_speech(conn: &Connection, word: &str, pos: &str) -> rusqlite::Result<()> {
+    conn.prepare_cached(SQL_ADD_PART_OF_SPEECH)?
+        .execute(params![word, pos])?;
+    Ok(())
+}
+
+// The word an alias was given for, or the word itself if it isn't an alias
+fn canonical_word(conn: &Connection, word: &str) -> rusqlite::Result<String> {
+    let canonical = conn
+        .query_row(SQL_CANONICAL_WORD, params![word], |row| row.get(0))
+        .optional()?;
+    Ok(canonical.unwrap_or_else(|| word.to_string()))
+}
+
+/// Make looking up one word find the sentences of another, like 喰う for 食う.
+///
+/// Aliases always point straight at a word which isn't an alias itself,
+/// so that looking up any of them finds the sentences of all the others.
+/// Returns the word the alias now points at.
+fn add_alias(conn: &Connection, alias: &str, word: &str) -> rusqlite::Result<Option<String>> {
+    let canonical = canonical_word(conn, word)?;
+    if canonical == alias {
+        return Ok(None);
+    }
+    conn.execute(SQL_MOVE_ALIASES, params![alias, canonical])?;
+    conn.execute(SQL_ADD_ALIAS, params![alias, canonical])?;
+    Ok(Some(canonical))
+}
+
+// Words are looked up however their kana are written, so that りんご also finds リンゴ,
+// without changing how they were stored
+fn spellings(word: &str) -> [String; 3] {
+    [
+        word.to_string(),
+        normalize::to_hiragana(word),
+        normalize::to_katakana(word),
+    ]
+}
+
+// A word can have more than one reading, like 今日 being read as キョウ or コンニチ
+fn word_readings(conn: &Connection, word: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(SQL_WORD_READINGS)?;
+    let results = stmt.query_map(spellings(word), |row| row.get(0))?;
+    results.collect()
+}
+
+// Aliases count as words, since looking one up finds the sentences of the word it stands for
+fn has_word(conn: &Connection, word: &str) -> rusqlite::Result<bool> {
+    conn.prepare_cached(SQL_HAS_WORD)?
+        .query_row(spellings(word), |row| row.get(0))
+}
+
+fn words_matching(conn: &Connection, pattern: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(SQL_WORDS_MATCHING)?;
+    let results = stmt.query_map(params![glob(pattern)], |row| row.get(0))?;
+    results.collect()
+}
+
+// Only * is a wildcard, so the other characters GLOB treats specially are matched as they are
+fn glob(pattern: &str) -> String {
+    pattern
+        .chars()
+        .map(|c| match c {
+            '?' => "[?]".to_string(),
+            '[' => "[[]".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// Readings are stored in katakana, but can be looked up however they're written
+fn words_read_as(conn: &Connection, reading: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(SQL_WORDS_READ_AS)?;
+    let results = stmt.query_map(spellings(reading), |row| row.get(0))?;
+    results.collect()
+}
+
+#[cfg(test)]
+fn matching_word(conn: &Connection, word: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(SQL_ALL_WORD_SENTENCES)?;
+    let mut buffer = Vec::new();
+    let results = stmt.query_map(&word_params(word, None), |row| row.get(1))?;
+    for r in results {
+        let s: String = r?;
+        buffer.push(s);
+    }
+    Ok(buffer)
+}
+
+fn sentence_translations(conn: &Connection, sentence_id: u32) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(SQL_SENTENCE_TRANSLATIONS)?;
+    let results = stmt.query_map(params![sentence_id], |row| row.get(0))?;
+    results.collect()
+}
+
+// This will ignore broken pipes, to support unix piping into things like head
+fn print_line(line: &str) {
+    if let Err(e) = writeln!(io::stdout(), "{}", line) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            panic!("{}", e);
+        }
+    }
+}
+
+// Looking up a word looks up every way of writing it, only among the sentences with a tag if one is given
+fn word_params(word: &str, tag: Option<&str>) -> Vec<Box<dyn ToSql>> {
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    for spelling in spellings(word).iter().cloned() {
+        params.push(Box::new(spelling));
+    }
+    params.push(Box::new(tag.map(String::from)));
+    params
+}
+
+/// How each sentence found is shown.
+#[derive(Clone, Copy, Debug, Default)]
+struct Shown {
+    /// Whether to show the id of each sentence before it, as in 42: 猫を見た。
+    ids: bool,
+    /// Whether to show the translations stored with each sentence.
+    translations: bool,
+    /// Whether to show where each sentence came from.
+    sources: bool,
+}
+
+// Without a limit, every sentence is found, in the order they were added
+fn matching_words(
+    conn: &Connection,
+    word: &str,
+    limit: Option<usize>,
+    tag: Option<&str>,
+) -> rusqlite::Result<Vec<(u32, String)>> {
+    let mut params = word_params(word, tag);
+    let query = match limit {
+        None => SQL_ALL_WORD_SENTENCES,
+        Some(limit) => {
+            params.push(Box::new(limit as i64));
+            SQL_BEST_WORD_SENTENCES
+        }
+    };
+    find_sentences(conn, query, params)
+}
+
+/// What the sentences picked at random have to be like.
+#[derive(Clone, Debug, Default)]
+struct Picked<'a> {
+    /// A word the sentences contain.
+    containing: Option<&'a str>,
+    tag: Option<&'a str>,
+    /// The fewest characters the sentences can have.
+    min_length: Option<usize>,
+    /// The most characters the sentences can have.
+    max_length: Option<usize>,
+}
+
+fn random_sentences(
+    conn: &Connection,
+    count: usize,
+    picked: &Picked,
+) -> rusqlite::Result<Vec<(u32, String)>> {
+    let mut params = match picked.containing {
+        Some(word) => word_params(word, picked.tag),
+        None => vec![
+            Box::new(None::<String>) as Box<dyn ToSql>,
+            Box::new(None::<String>),
+            Box::new(None::<String>),
+            Box::new(picked.tag.map(String::from)),
+        ],
+    };
+    params.push(Box::new(picked.min_length.map(|l| l as i64)));
+    params.push(Box::new(picked.max_length.map(|l| l as i64)));
+    params.push(Box::new(count as i64));
+    find_sentences(conn, SQL_RANDOM_SENTENCES, params)
+}
+
+// Sentences added before their tokens were kept have no positions for any word
+fn word_positions(conn: &Connection, word: &str, sentence_id: u32) -> rusqlite::Result<Vec<usize>> {
+    let mut params = word_params(word, None);
+    params.pop();
+    params.push(Box::new(sentence_id));
+    let mut stmt = conn.prepare_cached(SQL_WORD_POSITIONS)?;
+    let results = stmt.query_map(&params, |row| row.get::<_, i64>(0).map(|p| p as usize))?;
+    results.collect()
+}
+
+// Sentences added before the parts of speech of tokens were kept only have those of the word,
+// which are every part of speech it's been used as anywhere
+fn word_parts_of_speech(
+    conn: &Connection,
+    word: &str,
+    sentence_id: u32,
+) -> rusqlite::Result<Vec<String>> {
+    let mut params = word_params(word, None);
+    params.pop();
+    params.push(Box::new(sentence_id));
+    let mut stmt = conn.prepare_cached(SQL_TOKEN_PARTS_OF_SPEECH)?;
+    let used: Vec<String> = stmt
+        .query_map(&params, |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    if !used.is_empty() {
+        return Ok(used);
+    }
+    let mut stmt = conn.prepare_cached(SQL_WORD_PARTS_OF_SPEECH)?;
+    let results = stmt.query_map(spellings(word), |row| row.get(0))?;
+    results.collect()
+}
+
+fn word_sentence_ids(conn: &Connection, word: &str) -> rusqlite::Result<HashSet<u32>> {
+    let mut stmt = conn.prepare_cached(SQL_WORD_SENTENCE_IDS)?;
+    let results = stmt.query_map(spellings(word), |row| row.get(0))?;
+    results.collect()
+}
+
+// Phrases are searched for in the text of sentences, which the full text index makes fast
+// for phrases of at least three characters. LIKE ignores the case of latin letters,
+// so each sentence it finds is checked for the exact phrase too
+fn matching_phrases(
+    conn: &Connection,
+    phrase: &str,
+    limit: Option<usize>,
+    tag: Option<&str>,
+) -> rusqlite::Result<Vec<(u32, String)>> {
+    let query = if has_phrase_index(conn)? {
+        SQL_PHRASE_SENTENCES
+    } else {
+        SQL_SCAN_PHRASE_SENTENCES
+    };
+    let limit: i64 = limit.map_or(-1, |limit| limit as i64);
+    let params: [&dyn ToSql; 4] = [&like_pattern(phrase), &limit, &tag, &phrase];
+    find_sentences(conn, query, params)
+}
+
+// Matches text containing a phrase, which may itself contain the characters LIKE treats specially
+fn like_pattern(phrase: &str) -> String {
+    let escaped = phrase
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn find_sentences<P>(
+    conn: &Connection,
+    query: &str,
+    params: P,
+) -> rusqlite::Result<Vec<(u32, String)>>
+where
+    P: IntoIterator,
+    P::Item: ToSql,
+{
+    let mut stmt = conn.prepare_cached(query)?;
+    let results = stmt.query_map(params, |row| Ok((row.get(0)?, row.get(1)?)))?;
+    results.collect()
+}
+
+/// The tables searching reads, which are named along with the database they're in when several are attached.
+const SEARCHED_TABLES: [&str; 9] = [
+    "Sentences",
+    "Words",
+    "WordSentence",
+    "WordAliases",
+    "Readings",
+    "Tags",
+    "SentenceTags",
+    "SentenceText",
+    "Metadata",
+];
+
+// A query for one database that reads the tables of another attached to the same connection.
+// Each table it names is prefixed with the other's schema, except inside strings or after a dot
+fn in_schema(sql: &str, schema: &str) -> String {
+    let mut qualified = String::with_capacity(sql.len());
+    let mut quoted = false;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '\'' {
+            quoted = !quoted;
+        }
+        if quoted || !(c.is_ascii_alphabetic() || c == '_') {
+            qualified.push(c);
+            continue;
+        }
+        let mut end = start + 1;
+        while let Some(&(i, c)) = chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            end = i + 1;
+            chars.next();
+        }
+        let name = &sql[start..end];
+        let table = SEARCHED_TABLES.iter().any(|t| t.eq_ignore_ascii_case(name));
+        if table && !qualified.ends_with('.') {
+            qualified.push_str(schema);
+            qualified.push('.');
+        }
+        qualified.push_str(name);
+    }
+    qualified
+}
+
+// Which of several databases searched at once a sentence is from, and its id there
+fn locate(id: u32, banks: usize) -> (usize, u32) {
+    let banks = banks as u32;
+    ((id % banks) as usize, id / banks)
+}
+
+/// Several SQLite databases searched as one, by attaching them all to the same connection.
+///
+/// Every search is one query across all of them, so their sentences are ranked together,
+/// and one stored in more than one database is only found in the first.
+/// The id of a sentence is its id in its database times how many there are, plus the index of that database.
+struct Attached {
+    conn: Connection,
+    /// What each database is attached as, starting with main for the first.
+    schemas: Vec<String>,
+    /// The connection to each database on its own, to look up what's stored about one of its sentences.
+    banks: Vec<Connection>,
+}
+
+impl Attached {
+    // Attached databases share the key of the first, like with import, and are read-only like it.
+    // Each is opened on its own first, so that an older one is refused before being attached
+    fn open(paths: &[PathBuf], key: Option<&str>) -> rusqlite::Result<Attached> {
+        let mut banks = Vec::new();
+        for path in paths {
+            banks.push(conn_read_only(path, key)?);
+        }
+        let conn = conn_read_only(&paths[0], key)?;
+        let mut schemas = vec!["main".to_string()];
+        for (i, path) in paths.iter().enumerate().skip(1) {
+            let schema = format!("bank{}", i);
+            conn.execute(SQL_ATTACH_BANK, params![path.to_string_lossy(), schema])?;
+            schemas.push(schema);
+        }
+        Ok(Attached {
+            conn,
+            schemas,
+            banks,
+        })
+    }
+
+    // One query putting together what each database's query finds, after the index of that database
+    fn union(&self, queries: &[&str]) -> String {
+        let each: Vec<String> = queries
+            .iter()
+            .zip(&self.schemas)
+            .enumerate()
+            .map(|(bank, (sql, schema))| {
+                let sql = in_schema(sql.trim_end().trim_end_matches(';'), schema);
+                format!("SELECT {} AS bank, * FROM ({})", bank, sql)
+            })
+            .collect();
+        each.join("\nUNION ALL ")
+    }
+
+    fn same_query(&self, sql: &'static str) -> Vec<&'static str> {
+        vec![sql; self.schemas.len()]
+    }
+
+    fn words<P>(&self, sql: &'static str, params: P) -> rusqlite::Result<Vec<String>>
+    where
+        P: IntoIterator,
+        P::Item: ToSql,
+    {
+        let query = format!(
+            "WITH Found(bank, word) AS ({})\nSELECT DISTINCT word FROM Found ORDER BY word",
+            self.union(&self.same_query(sql))
+        );
+        let mut stmt = self.conn.prepare_cached(&query)?;
+        let results = stmt.query_map(params, |row| row.get(0))?;
+        results.collect()
+    }
+
+    // The shortest sentences of every database are among the shortest of each, which each query finds,
+    // and of the copies of a sentence, the one in the first database is kept
+    fn sentences<P>(
+        &self,
+        queries: &[&str],
+        params: P,
+        limit: Option<usize>,
+    ) -> rusqlite::Result<Vec<(u32, String)>>
+    where
+        P: IntoIterator,
+        P::Item: ToSql,
+    {
+        let order = match limit {
+            None => "min(bank), id",
+            Some(_) => "length(sentence), min(bank), id",
+        };
+        let query = format!(
+            "WITH Found(bank, id, sentence) AS ({})\n\
+             SELECT id * {} + min(bank), sentence FROM Found\n\
+             GROUP BY sentence ORDER BY {} LIMIT {}",
+            self.union(queries),
+            self.schemas.len(),
+            order,
+            limit.map_or(-1, |limit| limit as i64)
+        );
+        find_sentences(&self.conn, &query, params)
+    }
+}
+
+impl SentenceStore for Connection {
+    fn readings(&self, word: &str) -> store::Result<Vec<String>> {
+        Ok(word_readings(self, word)?)
+    }
+
+    fn has_word(&self, word: &str) -> store::Result<bool> {
+        Ok(has_word(self, word)?)
+    }
+
+    fn words_read_as(&self, reading: &str) -> store::Result<Vec<String>> {
+        Ok(words_read_as(self, reading)?)
+    }
+
+    fn words_matching(&self, pattern: &str) -> store::Result<Vec<String>> {
+        Ok(words_matching(self, pattern)?)
+    }
+
+    fn sentences_with_word(
+        &self,
+        word: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> store::Result<Vec<(u32, String)>> {
+        Ok(matching_words(self, word, limit, tag)?)
+    }
+
+    fn sentences_with_phrase(
+        &self,
+        phrase: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> store::Result<Vec<(u32, String)>> {
+        Ok(matching_phrases(self, phrase, limit, tag)?)
+    }
+
+    fn ids_with_word(&self, word: &str) -> store::Result<HashSet<u32>> {
+        Ok(word_sentence_ids(self, word)?)
+    }
+
+    fn positions(&self, word: &str, id: u32) -> store::Result<Vec<usize>> {
+        Ok(word_positions(self, word, id)?)
+    }
+
+    fn parts_of_speech(&self, word: &str, id: u32) -> store::Result<Vec<String>> {
+        Ok(word_parts_of_speech(self, word, id)?)
+    }
+
+    fn source(&self, id: u32) -> store::Result<Option<String>> {
+        Ok(sentence_metadata(self, id, "source")?)
+    }
+
+    fn translations(&self, id: u32) -> store::Result<Vec<String>> {
+        Ok(sentence_translations(self, id)?)
+    }
+}
+
+impl SentenceStore for Attached {
+    fn readings(&self, word: &str) -> store::Result<Vec<String>> {
+        Ok(self.words(SQL_WORD_READINGS, spellings(word))?)
+    }
+
+    fn has_word(&self, word: &str) -> store::Result<bool> {
+        let query = format!(
+            "WITH Found(bank, found) AS ({})\nSELECT max(found) FROM Found",
+            self.union(&self.same_query(SQL_HAS_WORD))
+        );
+        let mut stmt = self.conn.prepare_cached(&query)?;
+        Ok(stmt.query_row(spellings(word), |row| row.get(0))?)
+    }
+
+    fn words_read_as(&self, reading: &str) -> store::Result<Vec<String>> {
+        Ok(self.words(SQL_WORDS_READ_AS, spellings(reading))?)
+    }
+
+    fn words_matching(&self, pattern: &str) -> store::Result<Vec<String>> {
+        Ok(self.words(SQL_WORDS_MATCHING, params![glob(pattern)])?)
+    }
+
+    fn sentences_with_word(
+        &self,
+        word: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> store::Result<Vec<(u32, String)>> {
+        let mut params = word_params(word, tag);
+        let query = match limit {
+            None => SQL_ALL_WORD_SENTENCES,
+            Some(limit) => {
+                params.push(Box::new(limit as i64));
+                SQL_BEST_WORD_SENTENCES
+            }
+        };
+        Ok(self.sentences(&self.same_query(query), params, limit)?)
+    }
+
+    fn sentences_with_phrase(
+        &self,
+        phrase: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> store::Result<Vec<(u32, String)>> {
+        let mut queries = Vec::new();
+        for bank in &self.banks {
+            queries.push(if has_phrase_index(bank)? {
+                SQL_PHRASE_SENTENCES
+            } else {
+                SQL_SCAN_PHRASE_SENTENCES
+            });
+        }
+        let most: i64 = limit.map_or(-1, |limit| limit as i64);
+        let params: [&dyn ToSql; 4] = [&like_pattern(phrase), &most, &tag, &phrase];
+        Ok(self.sentences(&queries, params, limit)?)
+    }
+
+    fn ids_with_word(&self, word: &str) -> store::Result<HashSet<u32>> {
+        let query = format!(
+            "WITH Found(bank, id) AS ({})\nSELECT id * {} + bank FROM Found",
+            self.union(&self.same_query(SQL_WORD_SENTENCE_IDS)),
+            self.schemas.len()
+        );
+        let mut stmt = self.conn.prepare_cached(&query)?;
+        let results = stmt.query_map(spellings(word), |row| row.get(0))?;
+        Ok(results.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn positions(&self, word: &str, id: u32) -> store::Result<Vec<usize>> {
+        let (bank, id) = locate(id, self.banks.len());
+        self.banks[bank].positions(word, id)
+    }
+
+    fn parts_of_speech(&self, word: &str, id: u32) -> store::Result<Vec<String>> {
+        let (bank, id) = locate(id, self.banks.len());
+        self.banks[bank].parts_of_speech(word, id)
+    }
+
+    fn source(&self, id: u32) -> store::Result<Option<String>> {
+        let (bank, id) = locate(id, self.banks.len());
+        self.banks[bank].source(id)
+    }
+
+    fn translations(&self, id: u32) -> store::Result<Vec<String>> {
+        let (bank, id) = locate(id, self.banks.len());
+        self.banks[bank].translations(id)
+    }
+}
+
+/// Find the sentences containing every one of some words, or phrases.
+///
+/// Each word after the first is looked up in full, so the limit only applies once
+/// the sentences have been narrowed down to those containing all of them.
+fn sentences_with_all(
+    store: &dyn SentenceStore,
+    words: &[String],
+    phrase: bool,
+    limit: Option<usize>,
+    tag: Option<&str>,
+) -> store::Result<Vec<(u32, String)>> {
+    let find = |word: &str, limit| {
+        if phrase {
+            store.sentences_with_phrase(word, limit, tag)
+        } else {
+            store.sentences_with_word(word, limit, tag)
+        }
+    };
+    let (first, rest) = match words.split_first() {
+        None => return Ok(Vec::new()),
+        Some((first, [])) => return find(first, limit),
+        Some(split) => split,
+    };
+    let mut found = find(first, None)?;
+    for word in rest {
+        if phrase {
+            found.retain(|(_, sentence)| sentence.contains(word.as_str()));
+        } else {
+            let ids = store.ids_with_word(word)?;
+            found.retain(|(id, _)| ids.contains(id));
+        }
+    }
+    if let Some(limit) = limit {
+        found.sort_by_key(|(_, sentence)| sentence.chars().count());
+        found.truncate(limit);
+    }
+    Ok(found)
+}
+
+/// Find the sentences containing any of some words, or phrases, like the spellings of one word.
+///
+/// A sentence containing several of them is only found once.
+fn sentences_with_any(
+    store: &dyn SentenceStore,
+    words: &[String],
+    phrase: bool,
+    limit: Option<usize>,
+    tag: Option<&str>,
+) -> store::Result<Vec<(u32, String)>> {
+    let mut found: Vec<(u32, String)> = Vec::new();
+    let mut seen = HashSet::new();
+    // The shortest sentences of all of them are among the shortest of each
+    for word in words {
+        let sentences = if phrase {
+            store.sentences_with_phrase(word, limit, tag)?
+        } else {
+            store.sentences_with_word(word, limit, tag)?
+        };
+        found.extend(sentences.into_iter().filter(|(id, _)| seen.insert(*id)));
+    }
+    match limit {
+        None => found.sort_by_key(|(id, _)| *id),
+        Some(limit) => {
+            found.sort_by_key(|(_, sentence)| sentence.chars().count());
+            found.truncate(limit);
+        }
+    }
+    Ok(found)
+}
+
+/// Leave out the sentences containing any of some words, or phrases.
+fn exclude(
+    store: &dyn SentenceStore,
+    found: &mut Vec<(u32, String)>,
+    excluded: &[String],
+    phrase: bool,
+) -> store::Result<()> {
+    for word in excluded {
+        if phrase {
+            found.retain(|(_, sentence)| !sentence.contains(word.as_str()));
+        } else {
+            let ids = store.ids_with_word(word)?;
+            found.retain(|(id, _)| !ids.contains(id));
+        }
+    }
+    Ok(())
+}
+
+// Kanji are looked for one at a time, so 憂鬱 finds the sentences with both 憂 and 鬱 in them
+fn kanji_in(words: &[String]) -> Result<Vec<String>, char> {
+    let mut kanji = Vec::new();
+    for c in words.iter().flat_map(|word| word.chars()) {
+        if !normalize::is_kanji(c) {
+            return Err(c);
+        }
+        kanji.push(c.to_string());
+    }
+    Ok(kanji)
+}
+
+/// Keep only the sentences where every other word is within some number of tokens of the first.
+fn keep_near(
+    store: &dyn SentenceStore,
+    found: &mut Vec<(u32, String)>,
+    words: &[String],
+    distance: usize,
+) -> store::Result<()> {
+    let (first, rest) = match words.split_first() {
+        None => return Ok(()),
+        Some(split) => split,
+    };
+    let mut near = Vec::new();
+    for (id, sentence) in found.drain(..) {
+        let starts = store.positions(first, id)?;
+        let mut others = Vec::new();
+        for word in rest {
+            others.push(store.positions(word, id)?);
+        }
+        let close = |p: usize, q: &usize| p.max(*q) - p.min(*q) <= distance;
+        let is_near = starts.iter().any(|&p| {
+            others
+                .iter()
+                .all(|positions| positions.iter().any(|q| close(p, q)))
+        });
+        if is_near {
+            near.push((id, sentence));
+        }
+    }
+    *found = near;
+    Ok(())
+}
+
+/// Keep only the sentences where a word is used as some part of speech, going by any of its names.
+fn keep_part_of_speech(
+    store: &dyn SentenceStore,
+    found: &mut Vec<(u32, String)>,
+    word: &str,
+    names: &[&str],
+) -> store::Result<()> {
+    let mut kept = Vec::new();
+    for (id, sentence) in found.drain(..) {
+        let used = store.parts_of_speech(word, id)?;
+        if used.iter().any(|pos| names.contains(&pos.as_str())) {
+            kept.push((id, sentence));
+        }
+    }
+    *found = kept;
+    Ok(())
+}
+
+/// What get looks for in each database.
+struct Query<'a> {
+    words: Vec<String>,
+    /// Whether the words are phrases, found in the text of sentences.
+    phrase: bool,
+    /// Whether sentences only need to contain one of the words.
+    any: bool,
+    /// The words that sentences mustn't contain.
+    not: &'a [String],
+    /// How many tokens apart the words can be, at most.
+    within: Option<usize>,
+    /// The names of the part of speech the first word has to be used as.
+    pos: Option<&'static [&'static str]>,
+    limit: Option<usize>,
+    tag: Option<&'a str>,
+}
+
+impl<'a> Query<'a> {
+    fn find(&self, store: &dyn SentenceStore) -> store::Result<Vec<(u32, String)>> {
+        let search = if self.any {
+            sentences_with_any
+        } else {
+            sentences_with_all
+        };
+        // Sentences are left out before the limit applies, so that it's still reached
+        let limit = if self.not.is_empty() && self.within.is_none() && self.pos.is_none() {
+            self.limit
+        } else {
+            None
+        };
+        let mut found = search(store, &self.words, self.phrase, limit, self.tag)?;
+        exclude(store, &mut found, self.not, self.phrase)?;
+        if let Some(distance) = self.within {
+            keep_near(store, &mut found, &self.words, distance)?;
+        }
+        if let Some(names) = self.pos {
+            keep_part_of_speech(store, &mut found, &self.words[0], names)?;
+        }
+        if let (Some(most), None) = (self.limit, limit) {
+            found.sort_by_key(|(_, sentence)| sentence.chars().count());
+            found.truncate(most);
+        }
+        Ok(found)
+    }
+}
+
+/// The words indexed which are closest to one that isn't, like 雰囲気 for 雰意気.
+///
+/// Only the words sharing a kanji with it are compared, or a kana for words written without any.
+fn closest_words(store: &dyn SentenceStore, word: &str) -> store::Result<Vec<String>> {
+    let mut candidates: Vec<String> = Vec::new();
+    for c in suggest::shared(word) {
+        for candidate in store.words_matching(&format!("*{}*", c))? {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    Ok(suggest::suggestions(word, &candidates))
+}
+
+/// The dictionary form of a conjugated word, like 食べる for 食べました, if one is indexed.
+///
+/// Words that are indexed as they are, like a noun, are never changed.
+fn dictionary_form(store: &dyn SentenceStore, word: &str) -> store::Result<Option<String>> {
+    if store.has_word(word)? {
+        return Ok(None);
+    }
+    for candidate in deinflect::candidates(word) {
+        if store.has_word(&candidate)? {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+/// The words something typed in romaji might be, once it's turned into kana.
+///
+/// Those read like it are found, or else those read like its dictionary form, as in tabeta for 食べる.
+fn words_sounding_like(store: &dyn SentenceStore, kana: &str) -> store::Result<Vec<String>> {
+    let mut forms = vec![kana.to_string()];
+    forms.extend(deinflect::candidates(kana));
+    for form in forms {
+        let mut words = store.words_read_as(&form)?;
+        if store.has_word(&form)? && !words.contains(&form) {
+            words.push(form);
+        }
+        if !words.is_empty() {
+            return Ok(words);
+        }
+    }
+    Ok(Vec::new())
+}
+
+fn print_found(
+    store: &dyn SentenceStore,
+    names: &[String],
+    query: &Query,
+    shown: Shown,
+) -> store::Result<()> {
+    for (id, sentence) in query.find(store)? {
+        let bank = match names {
+            [_] => None,
+            _ => {
+                let (bank, id) = locate(id, names.len());
+                Some((names[bank].as_str(), id))
+            }
+        };
+        print_sentence(store, id, &sentence, shown, bank)?;
+    }
+    Ok(())
+}
+
+// Searching goes through SentenceStore, so another kind of database only needs a backend of its own.
+// It never changes a database, so older ones aren't upgraded, and have to be by another command
+fn open_store(locations: &[PathBuf], key: Option<&str>) -> store::Result<Box<dyn SentenceStore>> {
+    let mut paths = Vec::new();
+    for location in locations {
+        match Location::parse(location) {
+            Location::Sqlite(path) => paths.push(path),
+            Location::Postgres(url) => return Err(store::unsupported(&url)),
+        }
+    }
+    match paths.as_slice() {
+        [path] => Ok(Box::new(conn_read_only(path, key)?)),
+        paths => Ok(Box::new(Attached::open(paths, key)?)),
+    }
+}
+
+// The database a sentence is from is only named when searching several, along with its id there,
+// since ids are only unique within one
+fn print_sentence(
+    store: &dyn SentenceStore,
+    id: u32,
+    sentence: &str,
+    shown: Shown,
+    bank: Option<(&str, u32)>,
+) -> store::Result<()> {
+    match bank {
+        Some((bank, id)) if shown.ids => print_line(&format!("{} in {}: {}", id, bank, sentence)),
+        _ if shown.ids => print_line(&format!("{}: {}", id, sentence)),
+        _ => print_line(sentence),
+    }
+    if shown.sources {
+        if let Some(source) = store.source(id)? {
+            print_line(&format!("    from {}", source));
+        }
+    }
+    if shown.translations {
+        for translation in store.translations(id)? {
+            print_line(&format!("    {}", translation));
+        }
+    }
+    Ok(())
+}
+
+// Words are found in the normalized form of a sentence, so that ﾋﾞｰﾙ is indexed as ビール,
+// and without readings like the ばら in 薔薇（ばら）
+fn find_words(tokenizer: &mut dyn Tokenizer, sentence: &str) -> io::Result<Vec<Token>> {
+    let text = normalize::strip_readings(&normalize(sentence));
+    let mut tokens = tokenizer.tokens(&text)?;
+    tokenizer::locate(&text, &mut tokens);
+    Ok(tokens
+        .into_iter()
+        .map(|t| Token {
+            lemma: normalize(&t.lemma),
+            reading: t.reading.map(|r| normalize(&r)),
+            ..t
+        })
+        .collect())
+}
+
+// The sentence is stored as it was written, along with every word found in it, in order.
+// Particles, auxiliary verbs, and punctuation are in almost every sentence, so they're
+// only indexed as words if asked for.
+fn consume_trimmed(
+    conn: &Connection,
+    trimmed: &str,
+    words: &[Token],
+    particles: bool,
+) -> rusqlite::Result<u32> {
+    let sentence_id = add_sentence(conn, trimmed)?;
+    for (position, word) in words.iter().enumerate() {
+        add_token(conn, sentence_id, position, word)?;
+    }
+    for word in words.iter().filter(|w| particles || !w.is_function_word()) {
+        add_word(conn, &word.lemma, sentence_id)?;
+        if let Some(ref reading) = word.reading {
+            add_reading(conn, &word.lemma, reading)?;
+        }
+        if let Some(ref pos) = word.pos {
+            add_part_of_speech(conn, &word.lemma, pos)?;
+        }
+    }
+    Ok(sentence_id)
+}
+
+/// How many sentences were added from some input, and how many couldn't be.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Summary {
+    sentences: usize,
+    errors: usize,
+    /// How many sentences were skipped, having been added already.
+    duplicates: usize,
+}
+
+impl Summary {
+    /// The summary of input that couldn't be read at all.
+    fn failed() -> Self {
+        Summary {
+            errors: 1,
+            ..Summary::default()
+        }
+    }
+}
+
+impl AddAssign for Summary {
+    fn add_assign(&mut self, other: Summary) {
+        self.sentences += other.sentences;
+        self.errors += other.errors;
+        self.duplicates += other.duplicates;
+    }
+}
+
+/// One time sentences were added, as kept in the history.
+#[derive(Debug, PartialEq)]
+struct Run {
+    /// When the sentences were added, like 2020-01-01 12:00:00.
+    added_at: String,
+    /// What the sentences were added from, like a file or a source given with --source.
+    source: String,
+    summary: Summary,
+    seconds: f64,
+}
+
+impl fmt::Display for Run {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: added {} sentences, skipped {} already added, with {} errors, in {:.1}s",
+            self.added_at,
+            self.source,
+            self.summary.sentences,
+            self.summary.duplicates,
+            self.summary.errors,
+            self.seconds
+        )
+    }
+}
+
+fn add_history(
+    conn: &Connection,
+    source: &str,
+    summary: Summary,
+    seconds: f64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        SQL_ADD_HISTORY,
+        params![
+            source,
+            summary.sentences as i64,
+            summary.duplicates as i64,
+            summary.errors as i64,
+            seconds
+        ],
+    )?;
+    Ok(())
+}
+
+/// List every time sentences were added, starting with the oldest.
+fn history(conn: &Connection) -> rusqlite::Result<Vec<Run>> {
+    let mut stmt = conn.prepare(SQL_HISTORY)?;
+    let rows = stmt.query_map(params![], |row| {
+        let count = |i| row.get::<_, i64>(i).map(|n| n as usize);
+        Ok(Run {
+            added_at: row.get(0)?,
+            source: row.get(1)?,
+            summary: Summary {
+                sentences: count(2)?,
+                duplicates: count(3)?,
+                errors: count(4)?,
+            },
+            seconds: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Run just before each commit, to save how far the input has been read.
+type SaveProgress<'a> = Box<dyn Fn(&Connection) -> rusqlite::Result<()> + 'a>;
+
+/// How often to commit along the way while adding sentences.
+struct Batches<'a> {
+    size: usize,
+    save: SaveProgress<'a>,
+}
+
+impl<'a> Batches<'a> {
+    fn new(size: usize) -> Self {
+        Batches {
+            size,
+            save: Box::new(|_| Ok(())),
+        }
+    }
+}
+
+/// What finds the words in each sentence, and which of them to index.
+struct Indexer {
+    tokenizer: Box<dyn Tokenizer>,
+    /// Whether to index particles, auxiliary verbs, and punctuation as words.
+    particles: bool,
+    /// How many times each word the tokenizer didn't know was found.
+    unknown: HashMap<String, usize>,
+    /// How many sentences had any words the tokenizer didn't know.
+    unknown_sentences: usize,
+    /// How many sentences were added, across every input.
+    added: usize,
+}
+
+impl Indexer {
+    fn new(tokenizer: Box<dyn Tokenizer>, particles: bool) -> Self {
+        Indexer {
+            tokenizer,
+            particles,
+            unknown: HashMap::new(),
+            unknown_sentences: 0,
+            added: 0,
+        }
+    }
+
+    fn count_unknown(&mut self, words: &[Token]) {
+        let mut any = false;
+        for word in words.iter().filter(|w| w.unknown) {
+            *self.unknown.entry(word.surface.clone()).or_insert(0) += 1;
+            any = true;
+        }
+        if any {
+            self.unknown_sentences += 1;
+        }
+    }
+
+    // Lots of unknown words means a source needs another dictionary, or a user dictionary
+    fn print_unknown(&self) {
+        if self.unknown.is_empty() {
+            return;
+        }
+        let mut words: Vec<(&String, &usize)> = self.unknown.iter().collect();
+        words.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let top: Vec<String> = words
+            .iter()
+            .take(UNKNOWN_SHOWN)
+            .map(|(word, count)| format!("{} ({})", word, count))
+            .collect();
+        println!(
+            "{} unknown words across {} sentences, most often: {}",
+            self.unknown.values().sum::<usize>(),
+            self.unknown_sentences,
+            top.join(", ")
+        );
+    }
+}
+
+/// Add every sentence, committing the transaction after each batch if batches are given.
+///
+/// Committing along the way is done by ending the current transaction
+/// and starting a new one, so this has to be run inside of a transaction.
+fn consume_sentences<I>(
+    conn: &Connection,
+    indexer: &mut Indexer,
+    sentences: I,
+    batches: Option<&Batches>,
+) -> rusqlite::Result<Summary>
+where
+    I: IntoIterator<Item = Result<Sentence, SentenceError>>,
+{
+    let mut summary = Summary::default();
+    let mut i = 0;
+    for sentence in sentences {
+        i += 1;
+        let sentence = match sentence {
+            Err(e) => {
+                println!("Err on #{}: {}", i, e);
+                summary.errors += 1;
+                continue;
+            }
+            Ok(s) => s,
+        };
+        println!("#{}: {}", i, sentence.text);
+        // Looking the sentence up first saves splitting it into words for nothing
+        if has_sentence(conn, &sentence.text)? {
+            summary.duplicates += 1;
+            continue;
+        }
+        let words = match find_words(indexer.tokenizer.as_mut(), &sentence.text) {
+            Err(e) => {
+                println!("Err on #{}: {}", i, e);
+                summary.errors += 1;
+                continue;
+            }
+            Ok(w) => w,
+        };
+        indexer.count_unknown(&words);
+        let particles = indexer.particles;
+        let sentence_id = consume_trimmed(conn, &sentence.text, &words, particles)?;
+        for word in indexer.tokenizer.alternatives() {
+            if particles || !word.is_function_word() {
+                add_word(conn, &normalize(&word.lemma), sentence_id)?;
+            }
+        }
+        for (key, value) in &sentence.metadata {
+            add_metadata(conn, sentence_id, key, value)?;
+        }
+        for translation in &sentence.translations {
+            add_translation(conn, sentence_id, translation)?;
+        }
+        for word in &sentence.words {
+            add_word(conn, &normalize(word), sentence_id)?;
+        }
+        for tag in &sentence.tags {
+            add_tag(conn, sentence_id, tag)?;
+        }
+        summary.sentences += 1;
+        indexer.added += 1;
+        if let Some(batches) = batches {
+            if summary.sentences % batches.size == 0 {
+                (batches.save)(conn)?;
+                conn.execute_batch("COMMIT; BEGIN;")?;
+                println!("Saved {} sentences so far", indexer.added);
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Everything deciding how the inputs to add are read.
+struct AddSettings {
+    /// The format of every input, instead of guessing it from file names.
+    format: Option<Format>,
+    options: formats::Options,
+    translations: Option<tatoeba::Translations>,
+    /// Whether to pick up reading files from where an earlier run stopped.
+    resume: bool,
+    /// How many characters a sentence can have before it's skipped, or 0 for no limit.
+    max_length: usize,
+    /// How many letters, ignoring punctuation, a sentence needs to not be skipped.
+    min_length: usize,
+    /// Whether to skip sentences without any kana or kanji.
+    japanese_only: bool,
+    /// What finds the words in each sentence.
+    indexer: RefCell<Indexer>,
+    /// How many sentences to add between commits, or 0 to only commit at the end.
+    batch_size: usize,
+    /// Where the sentences came from, instead of the name of each file.
+    source: Option<String>,
+    /// The tags to give every sentence.
+    tags: Vec<String>,
+}
+
+impl AddSettings {
+    fn batches(&self) -> Option<Batches<'static>> {
+        Some(self.batch_size).filter(|&n| n > 0).map(Batches::new)
+    }
+
+    // Sentences from files come from the file, unless another source was given
+    fn source(&self, path: Option<&Path>) -> Vec<(String, String)> {
+        let name = path
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        self.source
+            .clone()
+            .or(name)
+            .map(|source| ("source".to_string(), source))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl Default for AddSettings {
+    fn default() -> Self {
+        AddSettings {
+            format: None,
+            options: formats::Options::default(),
+            translations: None,
+            resume: false,
+            max_length: MAX_LENGTH,
+            min_length: MIN_LENGTH,
+            japanese_only: true,
+            indexer: RefCell::new(Indexer::new(Box::new(tokenizer::Mecab::default()), false)),
+            batch_size: BATCH_SIZE,
+            source: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+fn add_sentences(
+    conn: &Connection,
+    settings: &AddSettings,
+    batches: Option<Batches>,
+    sentences: io::Result<formats::SentenceIter>,
+) -> rusqlite::Result<Summary> {
+    let mut sentences = match sentences {
+        Err(e) => {
+            println!("Couldn't read input:\n {}", e);
+            return Ok(Summary::failed());
+        }
+        Ok(s) => s,
+    };
+    if let Some(ref translations) = settings.translations {
+        sentences = Box::new(sentences.map(move |s| {
+            s.map(|mut s| {
+                translations.attach(&mut s);
+                s
+            })
+        }));
+    }
+    if !settings.tags.is_empty() {
+        let tags = &settings.tags;
+        sentences = Box::new(sentences.map(move |s| {
+            s.map(|mut s| {
+                s.tags.extend(tags.iter().cloned());
+                s
+            })
+        }));
+    }
+    // Input missing its punctuation can otherwise run together into huge sentences,
+    // and subtitles are full of fragments like "……。", which aren't useful either.
+    // Text in other languages only gets mangled into bogus words.
+    let (long, short, foreign) = (Cell::new(0), Cell::new(0), Cell::new(0));
+    let (max_length, min_length) = (settings.max_length, settings.min_length);
+    let sentences = sentences.filter(|s| match s {
+        Ok(s) if settings.japanese_only && !has_japanese(&s.text) => {
+            foreign.set(foreign.get() + 1);
+            false
+        }
+        Ok(s) if max_length > 0 && s.text.chars().count() > max_length => {
+            long.set(long.get() + 1);
+            false
+        }
+        Ok(s) if s.text.chars().filter(|c| c.is_alphanumeric()).count() < min_length => {
+            short.set(short.get() + 1);
+            false
+        }
+        _ => true,
+    });
+    let mut indexer = settings.indexer.borrow_mut();
+    let malformed = indexer.tokenizer.malformed();
+    let summary = consume_sentences(conn, &mut indexer, sentences, batches.as_ref())?;
+    let malformed = indexer.tokenizer.malformed() - malformed;
+    if summary.duplicates > 0 {
+        println!(
+            "Skipped {} sentences already in the database",
+            summary.duplicates
+        );
+    }
+    if malformed > 0 {
+        println!(
+            "Skipped {} words the tokenizer's output couldn't be read for",
+            malformed
+        );
+    }
+    if long.get() > 0 {
+        println!(
+            "Skipped {} sentences longer than {} characters",
+            long.get(),
+            max_length
+        );
+    }
+    if short.get() > 0 {
+        println!(
+            "Skipped {} sentences with fewer than {} letters",
+            short.get(),
+            min_length
+        );
+    }
+    if foreign.get() > 0 {
+        println!("Skipped {} sentences without any Japanese", foreign.get());
+    }
+    Ok(summary)
+}
+
+fn add_input<R: io::Read>(
+    conn: &Connection,
+    settings: &AddSettings,
+    format: Format,
+    input: R,
+) -> rusqlite::Result<Summary> {
+    let sentences = formats::read_sentences(format, &settings.options, input)
+        .map(|iter| formats::with_metadata(iter, settings.source(None)));
+    add_sentences(conn, settings, settings.batches(), sentences)
+}
+
+// Pages are read as articles, unless a format is given
+fn add_url(conn: &Connection, settings: &AddSettings, url: &str) -> rusqlite::Result<Summary> {
+    let page = match web::download(url) {
+        Err(e) => {
+            println!("Couldn't download {}:\n {}", url, e);
+            return Ok(Summary::failed());
+        }
+        Ok(p) => p,
+    };
+    let sentences = match settings.format {
+        None => formats::read_article(page, &settings.options),
+        Some(format) => formats::read_sentences(format, &settings.options, io::Cursor::new(page)),
+    };
+    let mut metadata = vec![("url".into(), url.to_string())];
+    metadata.extend(settings.source(None));
+    let sentences = sentences.map(|iter| formats::with_metadata(iter, metadata));
+    add_sentences(conn, settings, settings.batches(), sentences)
+}
+
+fn add_file(conn: &Connection, settings: &AddSettings, path: &Path) -> rusqlite::Result<Summary> {
+    let format = settings.format.unwrap_or_else(|| Format::from_path(path));
+    match compression::open(path) {
+        Err(e) => {
+            println!("Couldn't open {}:\n {}", path.display(), e);
+            Ok(Summary::failed())
+        }
+        Ok(f) if format == Format::Text => add_text_file(conn, settings, path, f),
+        Ok(f) => {
+            let options = formats::Options {
+                path: Some(path.to_path_buf()),
+                ..settings.options.clone()
+            };
+            let sentences = formats::read_sentences(format, &options, f)
+                .map(|iter| formats::with_metadata(iter, settings.source(Some(path))));
+            add_sentences(conn, settings, settings.batches(), sentences)
+        }
+    }
+}
+
+fn checkpoint(conn: &Connection, path: &str) -> rusqlite::Result<Option<u64>> {
+    let offset: Option<i64> = conn
+        .query_row(SQL_CHECKPOINT, params![path], |row| row.get(0))
+        .optional()?;
+    Ok(offset.map(|o| o as u64))
+}
+
+fn save_checkpoint(conn: &Connection, path: &str, offset: u64) -> rusqlite::Result<()> {
+    conn.execute(SQL_SAVE_CHECKPOINT, params![path, offset as i64])?;
+    Ok(())
+}
+
+// How far into text files has been read is saved with each batch,
+// so that an interrupted run can be resumed without adding anything twice
+fn add_text_file(
+    conn: &Connection,
+    settings: &AddSettings,
+    path: &Path,
+    file: Box<dyn io::Read>,
+) -> rusqlite::Result<Summary> {
+    let key = fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned();
+    let start = match checkpoint(conn, &key)? {
+        Some(offset) if settings.resume => offset,
+        _ => 0,
+    };
+    if start > 0 {
+        println!("Resuming {} from byte {}", path.display(), start);
+    }
+    let (sentences, position) = match formats::read_text_from(&settings.options, file, start) {
+        Err(e) => return add_sentences(conn, settings, None, Err(e)),
+        Ok(read) => read,
+    };
+    let batches = Batches {
+        size: Some(settings.batch_size)
+            .filter(|&n| n > 0)
+            .unwrap_or(usize::MAX),
+        save: {
+            let (key, position) = (key.clone(), position.clone());
+            Box::new(move |conn| save_checkpoint(conn, &key, position.get()))
+        },
+    };
+    let sentences = formats::with_metadata(sentences, settings.source(Some(path)));
+    let summary = add_sentences(conn, settings, Some(batches), Ok(sentences))?;
+    save_checkpoint(conn, &key, position.get())?;
+    Ok(summary)
+}
+
+// Every file in a directory is added, as long as its format is known
+fn add_directory(
+    conn: &Connection,
+    settings: &AddSettings,
+    dir: &Path,
+) -> rusqlite::Result<Summary> {
+    let files = match inputs::walk(dir) {
+        Err(e) => {
+            println!("Couldn't read {}:\n {}", dir.display(), e);
+            return Ok(Summary::failed());
+        }
+        Ok(f) => f,
+    };
+    let files = files
+        .into_iter()
+        .filter(|file| settings.format.is_some() || Format::detect(file).is_some())
+        .collect();
+    add_files(conn, settings, files)
+}
+
+fn add_pattern(
+    conn: &Connection,
+    settings: &AddSettings,
+    pattern: &str,
+) -> rusqlite::Result<Summary> {
+    match inputs::glob(pattern) {
+        Err(e) => {
+            println!("Couldn't search for {}:\n {}", pattern, e);
+            Ok(Summary::failed())
+        }
+        Ok(files) => add_files(conn, settings, files),
+    }
+}
+
+// Files that fail are reported, and the rest are still added
+fn add_files(
+    conn: &Connection,
+    settings: &AddSettings,
+    files: Vec<PathBuf>,
+) -> rusqlite::Result<Summary> {
+    let mut total = Summary::default();
+    let count = files.len();
+    for file in files {
+        println!("Adding {}", file.display());
+        let summary = add_file(conn, settings, &file)?;
+        println!(
+            "{}: {} sentences, {} errors",
+            file.display(),
+            summary.sentences,
+            summary.errors
+        );
+        total += summary;
+    }
+    println!(
+        "Added {} sentences from {} files, with {} errors",
+        total.sentences, count, total.errors
+    );
+    Ok(total)
+}
+
+/// The sites articles can be fetched from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Site {
+    /// NHK News Web Easy, with news in simpler Japanese.
+    NhkEasy,
+}
+
+impl std::str::FromStr for Site {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nhk-easy" => Ok(Site::NhkEasy),
+            _ => Err(format!("unknown site: {}", s)),
+        }
+    }
+}
+
+/// The changes that can be made to sources.
+#[derive(Debug, StructOpt)]
+enum SourcesAction {
+    /// Give a source a better name, like one that defaulted to a file name.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The name the source has now.
+        old: String,
+        /// The name to give it.
+        new: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ginkou", about = "Japanese sentence bank")]
+struct Opt {
+    /// The profile from the config file to use, like one for a bank of fiction.
+    #[structopt(long = "profile", short = "p")]
+    profile: Option<String>,
+    /// Only read from the database, never changing it, or creating it if it isn't there.
+    #[structopt(long = "read-only")]
+    read_only: bool,
+    /// Ask for the passphrase of an encrypted database, which needs ginkou built with SQLCipher.
+    #[structopt(long = "passphrase")]
+    passphrase: bool,
+    /// Read the passphrase of an encrypted database from the first line of this file instead.
+    #[structopt(
+        long = "key-file",
+        parse(from_os_str),
+        raw(conflicts_with = r#""passphrase""#)
+    )]
+    key_file: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: Ginkou,
+}
+
+// Only one of these is ever made, so the size of adding's many options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+enum Ginkou {
+    /// Add new sentences to the database.
+    #[structopt(name = "add")]
+    Add {
+        /// The file to read sentences from.
+        ///
+        /// If no file is given, sentences will be read from stdin. If a directory is given,
+        /// every file of a known format inside of it is read. Glob patterns like
+        /// `novels/**/*.txt` read every matching file.
+        #[structopt(long, short = "f", parse(from_os_str))]
+        file: Option<PathBuf>,
+        /// A web page to download, adding the sentences of its article.
+        #[structopt(long = "url", raw(conflicts_with = r#""file""#))]
+        url: Option<String>,
+        /// Where the sentences came from, like a book or a show, instead of the name of each file.
+        #[structopt(long = "source")]
+        source: Option<String>,
+        /// A tag to give every sentence, like anime or formal, which can be given more than once.
+        #[structopt(long = "tag", raw(number_of_values = "1"))]
+        tags: Vec<String>,
+        /// The format of the input: text, epub, html, pdf, srt, ass, vtt, aozora, tatoeba, anki,
+        /// kindle, jsonl, csv, tsv, markdown, mokuro, wikipedia, or twitter.
+        ///
+        /// If no format is given, it is guessed from the file's extension.
+        #[structopt(long = "format")]
+        format: Option<Format>,
+        /// The encoding of text: utf-8, shift-jis, or euc-jp.
+        ///
+        /// If no encoding is given, it is detected from the text.
+        #[structopt(long = "encoding")]
+        encoding: Option<Encoding>,
+        /// Open the database with write-ahead logging and less syncing, for faster imports.
+        ///
+        /// Searching also keeps working while sentences are being added.
+        #[structopt(long = "fast")]
+        fast: bool,
+        /// How many sentences to add between commits, or 0 to only commit once everything is added.
+        #[structopt(long = "batch-size", default_value = "5000")]
+        batch_size: usize,
+        /// Continue adding files from where an interrupted run stopped.
+        #[structopt(long = "resume")]
+        resume: bool,
+        /// Read each line as a whole sentence, instead of splitting sentences on punctuation
+        #[structopt(long = "lines")]
+        lines: bool,
+        /// Keep the spacing inside of sentences, only removing new lines and surrounding spaces.
+        #[structopt(long = "preserve-whitespace")]
+        preserve_whitespace: bool,
+        /// The characters ending a sentence, instead of 。！？ and the like, where \n is a new line.
+        #[structopt(long = "delimiters")]
+        delimiters: Option<String>,
+        /// Skip sentences longer than this many characters, 500 by default, or 0 to keep them all.
+        #[structopt(long = "max-length")]
+        max_length: Option<usize>,
+        /// Skip sentences with fewer letters than this, not counting punctuation, 2 by default.
+        #[structopt(long = "min-length")]
+        min_length: Option<usize>,
+        /// Keep sentences without any kana or kanji, which are skipped otherwise.
+        #[structopt(long = "keep-non-japanese")]
+        keep_non_japanese: bool,
+        /// What to find the words in sentences with: mecab, the default, or sudachi.
+        #[structopt(long = "tokenizer")]
+        tokenizer: Option<tokenizer::Backend>,
+        /// How finely Sudachi splits words, from A for the shortest to C for whole compounds.
+        #[structopt(long = "split-mode", default_value = "C")]
+        split_mode: tokenizer::SplitMode,
+        /// The dictionary for MeCab to use, like UniDic, instead of its system dictionary.
+        #[structopt(long = "dictionary", parse(from_os_str), raw(alias = r#""mecab-dic""#))]
+        dictionary: Option<PathBuf>,
+        /// How many of MeCab's best ways of splitting each sentence to index the words of.
+        ///
+        /// Sentences which can be read more than one way are then found under either.
+        #[structopt(long = "nbest", default_value = "1")]
+        nbest: usize,
+        /// Also index particles, auxiliary verbs, and punctuation as words.
+        #[structopt(long = "index-particles")]
+        index_particles: bool,
+        /// A dictionary of extra words, like names, for the tokenizer to know.
+        ///
+        /// MeCab takes a compiled dictionary, or a CSV of words to compile, and Sudachi a
+        /// compiled dictionary.
+        #[structopt(long = "user-dictionary", parse(from_os_str))]
+        user_dictionary: Option<PathBuf>,
+        /// The field of each Anki note, or column of a spreadsheet, holding the sentence.
+        ///
+        /// Fields and columns are counted from 1.
+        #[structopt(
+            long = "field",
+            default_value = "1",
+            raw(alias = r#""sentence-column""#)
+        )]
+        field: usize,
+        /// The Tatoeba links.csv file, used to find translations of each sentence.
+        #[structopt(
+            long = "links",
+            parse(from_os_str),
+            raw(requires = r#""translations""#)
+        )]
+        links: Option<PathBuf>,
+        /// A Tatoeba sentence dump containing the translations to find with --links.
+        #[structopt(
+            long = "translations",
+            parse(from_os_str),
+            raw(requires = r#""links""#)
+        )]
+        translations: Option<PathBuf>,
+        /// Search for a word once everything is added, like with get.
+        #[structopt(long = "get")]
+        get: Option<String>,
+        /// Search for a phrase once everything is added, like with get --phrase.
+        #[structopt(long = "get-phrase", raw(conflicts_with = r#""get""#))]
+        get_phrase: Option<String>,
+        /// The database to use, or :memory: for one only kept until adding is done.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Download and add sentences from a news site.
+    #[structopt(name = "fetch")]
+    Fetch {
+        /// The site to fetch from: nhk-easy.
+        site: Site,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Keep adding the Japanese text copied to the clipboard.
+    #[structopt(name = "watch")]
+    Watch {
+        /// Watch the system clipboard.
+        #[structopt(long = "clipboard")]
+        clipboard: bool,
+        /// How often to check for new text, in milliseconds.
+        #[structopt(long = "interval", default_value = "500")]
+        interval: u64,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Look up one word as another, like a variant spelling as the usual one.
+    #[structopt(name = "alias")]
+    Alias {
+        /// The word to look up differently, like 喰う.
+        alias: String,
+        /// The word to find the sentences of instead, like 食う.
+        word: String,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Tag a sentence, like as anime or formal, or remove a tag from it.
+    #[structopt(name = "tag")]
+    Tag {
+        /// The id of the sentence, as shown by get --ids.
+        sentence: u32,
+        /// The tag to give the sentence.
+        tag: String,
+        /// Remove the tag from the sentence instead.
+        #[structopt(long = "remove")]
+        remove: bool,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Write every sentence out, with its words, readings, source, and tags.
+    #[structopt(name = "export")]
+    Export {
+        /// The format to write: json, for JSON Lines, the default, or csv.
+        #[structopt(long = "format")]
+        format: Option<export::Format>,
+        /// The file to write to, instead of stdout.
+        #[structopt(long = "output", short = "o", parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Merge another database, or a JSON export, into this one.
+    #[structopt(name = "import")]
+    Import {
+        /// The database, or .json or .jsonl export, to import.
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Check the database for problems, like an import being interrupted.
+    #[structopt(name = "check")]
+    Check {
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Save a copy of the database, even while sentences are being added to it.
+    #[structopt(name = "backup")]
+    Backup {
+        /// Where to save the copy.
+        #[structopt(parse(from_os_str))]
+        dest: PathBuf,
+        /// How many earlier copies to keep, as dest.1, dest.2, and so on.
+        #[structopt(long = "keep", default_value = "0")]
+        keep: usize,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Shrink the database after removing sentences, and update what queries are planned with.
+    #[structopt(name = "compact")]
+    Compact {
+        /// Rebuild every index too.
+        #[structopt(long = "reindex")]
+        reindex: bool,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Remove the copies of sentences stored more than once.
+    #[structopt(name = "dedupe")]
+    Dedupe {
+        /// List the sentences differing only in punctuation or a particle, without removing anything.
+        #[structopt(long = "fuzzy")]
+        fuzzy: bool,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Remove a sentence, every sentence containing some text, or every sentence from a source.
+    #[structopt(name = "rm")]
+    Rm {
+        /// The id of the sentence to remove, as shown by get --ids.
+        id: Option<u32>,
+        /// Remove every sentence containing this text instead.
+        #[structopt(long = "matching")]
+        matching: Option<String>,
+        /// Remove every sentence added from this source instead, as listed by sources.
+        #[structopt(long = "source")]
+        source: Option<String>,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Remove the words no sentence contains anymore, like after removing sentences.
+    #[structopt(name = "prune")]
+    Prune {
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// List where the sentences came from, with how many sentences and words each has.
+    #[structopt(name = "sources")]
+    Sources {
+        #[structopt(subcommand)]
+        action: Option<SourcesAction>,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// List every time sentences were added, with how many were and how long it took.
+    #[structopt(name = "history")]
+    History {
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+    /// Move the database from ~/.ginkoudb to where the platform keeps data.
+    #[structopt(name = "relocate")]
+    Relocate,
+    /// Search for all sentences containing a given word.
+    #[structopt(name = "get")]
+    Get {
+        /// The words to search for in the database, finding the sentences containing all of them.
+        #[structopt(raw(required = "true"))]
+        words: Vec<String>,
+        /// Show all results instead of only the shortest
+        #[structopt(long = "allwords", short = "a")]
+        all: bool,
+        /// How many of the shortest sentences to show, 200 by default, or 0 to show them all
+        #[structopt(long = "limit", short = "n", raw(conflicts_with = r#""all""#))]
+        limit: Option<usize>,
+        /// Show the translations stored with each sentence
+        #[structopt(long = "translate", short = "t")]
+        translate: bool,
+        /// Search for sentences containing the text given, instead of a word
+        #[structopt(long = "phrase")]
+        phrase: bool,
+        /// Read the words as romaji, like taberu, instead of Japanese
+        #[structopt(long = "romaji")]
+        romaji: bool,
+        /// Search for sentences containing each kanji given, whichever word it's part of
+        #[structopt(long = "kanji")]
+        kanji: bool,
+        /// Find the sentences containing any of the words, instead of all of them
+        #[structopt(long = "any")]
+        any: bool,
+        /// Leave out the sentences containing this word, which can be given more than once
+        #[structopt(long = "not", raw(number_of_values = "1"))]
+        not: Vec<String>,
+        /// Look the first word up by how it's read, finding the sentences of every word read that way
+        #[structopt(long = "by-reading")]
+        by_reading: bool,
+        /// Search for the words as they're given, instead of the dictionary form of conjugated ones
+        #[structopt(long = "exact")]
+        exact: bool,
+        /// Search for the closest word to each one that isn't indexed, instead of only suggesting it
+        #[structopt(long = "fuzzy")]
+        fuzzy: bool,
+        /// Only find the sentences where the words are at most this many tokens apart
+        #[structopt(
+            long = "within",
+            raw(conflicts_with_all = r#"&["phrase", "kanji", "any"]"#)
+        )]
+        within: Option<usize>,
+        /// Only find the sentences using the first word as this part of speech, like verb or 動詞
+        #[structopt(
+            long = "pos",
+            raw(conflicts_with_all = r#"&["phrase", "kanji", "any"]"#)
+        )]
+        pos: Option<String>,
+        /// Show where each sentence came from
+        #[structopt(long = "sources", short = "s")]
+        sources: bool,
+        /// Show the id of each sentence, to tag it with
+        #[structopt(long = "ids", short = "i")]
+        ids: bool,
+        /// Only search the sentences with this tag
+        #[structopt(long = "tag")]
+        tag: Option<String>,
+        /// Show how the word is read, before the sentences
+        #[structopt(long = "readings", short = "r")]
+        readings: bool,
+        /// The database to use, which can be given more than once to search several.
+        #[structopt(
+            long = "database",
+            short = "d",
+            parse(from_os_str),
+            raw(number_of_values = "1")
+        )]
+        db: Vec<PathBuf>,
+    },
+    /// Show sentences picked at random, like for reading practice.
+    #[structopt(name = "random")]
+    Random {
+        /// How many sentences to show.
+        #[structopt(long = "count", short = "n", default_value = "1")]
+        count: usize,
+        /// Only pick the sentences containing this word.
+        #[structopt(long = "containing")]
+        containing: Option<String>,
+        /// Only pick the sentences with this tag.
+        #[structopt(long = "tag")]
+        tag: Option<String>,
+        /// Only pick the sentences with at least this many characters.
+        #[structopt(long = "min-length")]
+        min_length: Option<usize>,
+        /// Only pick the sentences with at most this many characters.
+        #[structopt(long = "max-length")]
+        max_length: Option<usize>,
+        /// Show the translations stored with each sentence
+        #[structopt(long = "translate", short = "t")]
+        translate: bool,
+        /// Show the id of each sentence, to tag it with
+        #[structopt(long = "ids", short = "i")]
+        ids: bool,
+        /// The database to use.
+        #[structopt(long = "database", short = "d", parse(from_os_str))]
+        db: Option<PathBuf>,
+    },
+}
+
+impl Ginkou {
+    /// Whether the command can change the database, so that it can't be run with --read-only.
+    fn writes(&self) -> bool {
+        match self {
+            Ginkou::Get { .. }
+            | Ginkou::Random { .. }
+            | Ginkou::Export { .. }
+            | Ginkou::Check { .. }
+            | Ginkou::Backup { .. }
+            | Ginkou::History { .. } => false,
+            Ginkou::Sources { action, .. } => action.is_some(),
+            Ginkou::Dedupe { fuzzy, .. } => !fuzzy,
+            _ => true,
+        }
+    }
+}
+
+// Articles already added are skipped, so fetching again only adds new ones
+fn fetch_nhk_easy(conn: &mut Connection) -> rusqlite::Result<()> {
+    let settings = AddSettings {
+        format: Some(Format::Html),
+        source: Some("NHK News Web Easy".into()),
+        tags: vec!["news".into()],
+        ..AddSettings::default()
+    };
+    let articles = match web::download(nhk::LIST_URL)
+        .and_then(|list| nhk::articles(&String::from_utf8_lossy(&list)))
+    {
+        Err(e) => {
+            println!("Couldn't read the list of articles:\n {}", e);
+            return Ok(());
+        }
+        Ok(a) => a,
+    };
+    let mut total = Summary::default();
+    let mut count = 0;
+    for article in articles {
+        let url = article.url();
+        if has_metadata(conn, "url", &url)? {
+            continue;
+        }
+        let body = match web::download(&url) {
+            Err(e) => {
+                println!("Couldn't download {}:\n {}", url, e);
+                total.errors += 1;
+                continue;
+            }
+            Ok(page) => nhk::body(&String::from_utf8_lossy(&page)),
+        };
+        let body = match body {
+            None => {
+                println!("Couldn't find the article in {}", url);
+                total.errors += 1;
+                continue;
+            }
+            Some(b) => b,
+        };
+        println!("Adding {} ({})", article.title, article.date);
+        let sentences = formats::cue_sentences(vec![article.title, body], &settings.options);
+        let metadata = vec![("date".into(), article.date), ("url".into(), url)];
+        let tx = conn.transaction()?;
+        total += add_sentences(
+            &tx,
+            &settings,
+            None,
+            Ok(formats::with_metadata(sentences, metadata)),
+        )?;
+        tx.commit()?;
+        count += 1;
+    }
+    println!(
+        "Added {} sentences from {} new articles, with {} errors",
+        total.sentences, count, total.errors
+    );
+    Ok(())
+}
+
+// Whatever is on the clipboard when starting has already been seen
+fn watch_clipboard(conn: &mut Connection, interval: Duration) -> rusqlite::Result<()> {
+    let settings = AddSettings {
+        format: Some(Format::Text),
+        ..AddSettings::default()
+    };
+    let mut last = clipboard::read().ok();
+    println!("Watching the clipboard, press Ctrl-C to stop");
+    loop {
+        thread::sleep(interval);
+        let text = match clipboard::read() {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                println!("Couldn't read the clipboard:\n {}", e);
+                return Ok(());
+            }
+            // Some programs fail when the clipboard is empty, or holds an image
+            Err(_) => continue,
+            Ok(t) => t,
+        };
+        if last.as_ref() == Some(&text) {
+            continue;
+        }
+        // The text is tried again next time if another process is still adding sentences
+        if has_japanese(&text) {
+            let added = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .and_then(|tx| {
+                    add_input(&tx, &settings, Format::Text, io::Cursor::new(text.clone()))?;
+                    tx.commit()
+                });
+            match added {
+                Err(ref e) if is_busy(e) => {
+                    println!("The database is busy, trying again");
+                    continue;
+                }
+                added => added?,
+            }
+        }
+        last = Some(text);
+    }
+}
+
+// Where the database was kept before, which is still used if it's the only one there is
+fn legacy_db_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ginkoudb"))
+}
+
+// Like ~/.local/share/ginkou/bank.db, or wherever else the platform keeps data
+fn data_db_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|data| data.join("ginkou").join("bank.db"))
+}
+
+fn pick_db_path(data: Option<PathBuf>, legacy: Option<PathBuf>) -> PathBuf {
+    match (data, legacy) {
+        (Some(ref data), Some(legacy)) if !data.exists() && legacy.exists() => legacy,
+        (Some(data), _) => data,
+        (None, Some(legacy)) => legacy,
+        (None, None) => PathBuf::from(".ginkoudb"),
+    }
+}
+
+// GINKOU_DB is set for a whole shell, so that scripts can switch databases without passing -d
+// to each command. A profile is chosen for one command, so its database wins over that one
+fn chosen_db_path(config: &config::Settings, env_db: Option<OsString>) -> Option<PathBuf> {
+    let env_db = env_db.filter(|path| !path.is_empty()).map(PathBuf::from);
+    match config.profile {
+        Some(_) => config.database.clone().or(env_db),
+        None => env_db.or_else(|| config.database.clone()),
+    }
+}
+
+fn default_db_path(config: &config::Settings) -> PathBuf {
+    if let Some(path) = chosen_db_path(config, env::var_os("GINKOU_DB")) {
+        return path;
+    }
+    let data = data_db_path();
+    let path = pick_db_path(data.clone(), legacy_db_path());
+    match data {
+        Some(ref data) if *data == path => {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+        }
+        // Output like exports can go to stdout, so this can't
+        Some(ref data) => eprintln!(
+            "Using the database at {}, which ginkou relocate moves to {}",
+            path.display(),
+            data.display()
+        ),
+        None => {}
+    }
+    path
+}
+
+/// Move a database somewhere else, along with its write-ahead log.
+fn relocate(from: &Path, to: &Path) -> rusqlite::Result<bool> {
+    if let Some(dir) = to.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    backup_to(&conn_from_disk(from)?, to, 0, None)?;
+    if !to.exists() {
+        return Ok(false);
+    }
+    for suffix in &["", "-wal", "-shm"] {
+        let file = PathBuf::from(format!("{}{}", from.display(), suffix));
+        if file.exists() {
+            let _ = fs::remove_file(file);
+        }
+    }
+    Ok(true)
+}
+
+fn main() -> store::Result<()> {
+    match run(Opt::from_args()) {
+        Err(store::Error::Sqlite(ref e)) if is_busy(e) => {
+            println!(
+                "The database is busy, another ginkou is probably adding sentences to it:\n {}",
+                e
+            );
+            Ok(())
+        }
+        Err(store::Error::Sqlite(rusqlite::Error::SqliteFailure(ref e, Some(ref message))))
+            if e.code == ErrorCode::CannotOpen =>
+        {
+            println!("Couldn't open the database:\n {}", message);
+            Ok(())
+        }
+        Err(ref e @ store::Error::Unsupported { .. }) => {
+            println!("Couldn't open the database:\n {}", e);
+            Ok(())
+        }
+        Err(store::Error::Sqlite(rusqlite::Error::SqliteFailure(ref e, _)))
+            if e.code == ErrorCode::ReadOnly =>
+        {
+            println!("The database couldn't be changed, since it can only be read");
+            Ok(())
+        }
+        Err(store::Error::Sqlite(rusqlite::Error::SqliteFailure(ref e, _)))
+            if e.code == ErrorCode::NotADatabase =>
+        {
+            println!("The database couldn't be read, it might need another passphrase");
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn run(opt: Opt) -> store::Result<()> {
+    let loaded = match config::path() {
+        None => config::Config::default(),
+        Some(path) => match config::load(&path) {
+            Err(e) => {
+                println!("Couldn't read {}:\n {}", path.display(), e);
+                return Ok(());
+            }
+            Ok(config) => config,
+        },
+    };
+    let config = match opt.profile {
+        None => loaded.settings,
+        Some(ref name) => match loaded.profile(name) {
+            None => {
+                println!("There's no profile named {} in the config file", name);
+                return Ok(());
+            }
+            Some(profile) => profile.clone(),
+        },
+    };
+    let key_file = opt.key_file.or_else(|| config.key_file.clone());
+    if (opt.passphrase || key_file.is_some()) && !cfg!(feature = "sqlcipher") {
+        println!(
+            "Encrypted databases need ginkou built with SQLCipher, using --features sqlcipher"
+        );
+        return Ok(());
+    }
+    let key = if opt.passphrase {
+        Some(passphrase::prompt("Passphrase"))
+    } else {
+        key_file.map(|path| passphrase::read(&path))
+    };
+    let key = match key.transpose() {
+        Err(e) => {
+            println!("Couldn't get the passphrase:\n {}", e);
+            return Ok(());
+        }
+        Ok(key) => key,
+    };
+    let key = key.as_deref();
+    let read_only = opt.read_only;
+    if read_only && opt.command.writes() {
+        println!("This command changes the database, so it can't be given --read-only");
+        return Ok(());
+    }
+    let open = |location: &Path| match Location::parse(location) {
+        Location::Postgres(url) => Err(store::unsupported(&url)),
+        Location::Sqlite(ref path) if read_only => Ok(conn_read_only(path, key)?),
+        Location::Sqlite(path) => Ok(conn_with_key(path, key)?),
+    };
+    match opt.command {
+        Ginkou::Fetch { site, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            match site {
+                Site::NhkEasy => fetch_nhk_easy(&mut conn)?,
+            }
+        }
+        Ginkou::Watch {
+            clipboard,
+            interval,
+            db,
+        } => {
+            if !clipboard {
+                println!("Nothing to watch, try --clipboard");
+                return Ok(());
+            }
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            watch_clipboard(&mut conn, Duration::from_millis(interval))?;
+        }
+        Ginkou::Alias { alias, word, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let (alias, word) = (normalize(&alias), normalize(&word));
+            match add_alias(&conn, &alias, &word)? {
+                None => println!("{} can't be an alias of itself", alias),
+                Some(canonical) => println!("{} is now looked up as {}", alias, canonical),
+            }
+        }
+        Ginkou::Tag {
+            sentence: id,
+            tag,
+            remove,
+            db,
+        } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let text = match sentence(&conn, id)? {
+                None => {
+                    println!("There's no sentence with the id {}", id);
+                    return Ok(());
+                }
+                Some(text) => text,
+            };
+            if !remove {
+                add_tag(&conn, id, &tag)?;
+                println!("Tagged {} as {}", text, tag);
+            } else if remove_tag(&conn, id, &tag)? {
+                println!("Removed {} from {}", tag, text);
+            } else {
+                println!("{} wasn't tagged as {}", text, tag);
+            }
+        }
+        Ginkou::Export { format, output, db } => {
+            let format = format.or(config.format).unwrap_or(export::Format::Json);
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            match output {
+                None => {
+                    export_sentences(&conn, format, &mut io::stdout().lock())?;
+                }
+                Some(path) => match File::create(&path) {
+                    Err(e) => println!("Couldn't create {}:\n {}", path.display(), e),
+                    Ok(file) => {
+                        let mut out = io::BufWriter::new(file);
+                        let count = export_sentences(&conn, format, &mut out)?;
+                        if let Err(e) = out.flush() {
+                            println!("Couldn't write {}:\n {}", path.display(), e);
+                        } else {
+                            println!("Exported {} sentences to {}", count, path.display());
+                        }
+                    }
+                },
+            }
+        }
+        Ginkou::Import { file, db } => {
+            if !file.exists() {
+                println!("Couldn't find {}", file.display());
+                return Ok(());
+            }
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            let json = matches!(
+                file.extension().and_then(|e| e.to_str()),
+                Some("json") | Some("jsonl")
+            );
+            let summary = if json {
+                match File::open(&file) {
+                    Err(e) => {
+                        println!("Couldn't open {}:\n {}", file.display(), e);
+                        return Ok(());
+                    }
+                    Ok(f) => {
+                        let tx = conn.transaction()?;
+                        let summary = import_export(&tx, io::BufReader::new(f))?;
+                        tx.commit()?;
+                        summary
+                    }
+                }
+            } else {
+                import_database(&mut conn, &file, key)?
+            };
+            println!(
+                "Imported {} new sentences from {}, skipping {} already here, with {} errors",
+                summary.sentences,
+                file.display(),
+                summary.duplicates,
+                summary.errors
+            );
+        }
+        Ginkou::Check { db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let problems = check(&conn)?;
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if problems.is_empty() {
+                println!("Found no problems with {}", db_path.display());
+            } else {
+                println!(
+                    "Found {} problems with {}",
+                    problems.len(),
+                    db_path.display()
+                );
+            }
+        }
+        Ginkou::Backup { dest, keep, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            backup_to(&conn, &dest, keep, key)?;
+            println!(
+                "Saved a copy of {} at {}",
+                db_path.display(),
+                dest.display()
+            );
+        }
+        Ginkou::Compact { reindex, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let size = || fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+            let before = size();
+            compact(&conn, reindex)?;
+            println!(
+                "Compacted {} from {} to {}",
+                db_path.display(),
+                format_size(before),
+                format_size(size())
+            );
+        }
+        Ginkou::Rm {
+            id,
+            matching,
+            source,
+            db,
+        } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            let removed = match (id, matching, source) {
+                (Some(id), None, None) => remove_sentences(&mut conn, SQL_REMOVE_BY_ID, &[&id])?,
+                (None, Some(text), None) => {
+                    remove_sentences(&mut conn, SQL_REMOVE_MATCHING, &[&text])?
+                }
+                (None, None, Some(source)) => {
+                    remove_sentences(&mut conn, SQL_REMOVE_BY_SOURCE, &[&source])?
+                }
+                _ => {
+                    println!(
+                        "Give one of the id of a sentence, --matching, or --source, to remove"
+                    );
+                    return Ok(());
+                }
+            };
+            println!("Removed {} sentences", removed);
+        }
+        Ginkou::Sources { action, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            if let Some(SourcesAction::Rename { old, new }) = action {
+                match rename_source(&conn, &old, &new)? {
+                    0 => println!("No sentences came from {}", old),
+                    n => println!("Renamed {} to {}, for {} sentences", old, new, n),
+                }
+                return Ok(());
+            }
+            let sources = sources(&conn)?;
+            for source in &sources {
+                println!("{}", source);
+            }
+            if sources.is_empty() {
+                println!("No sentences have a source");
+            }
+        }
+        Ginkou::History { db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let runs = history(&conn)?;
+            for run in &runs {
+                println!("{}", run);
+            }
+            if runs.is_empty() {
+                println!("No sentences have been added yet");
+            }
+        }
+        Ginkou::Relocate => match (legacy_db_path(), data_db_path()) {
+            (Some(ref legacy), Some(ref data)) if legacy.exists() && !data.exists() => {
+                if relocate(legacy, data)? {
+                    println!("Moved {} to {}", legacy.display(), data.display());
+                }
+            }
+            (_, Some(ref data)) if data.exists() => {
+                println!("The database is already at {}", data.display());
+            }
+            _ => println!("There's no database at ~/.ginkoudb to move"),
+        },
+        Ginkou::Prune { db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            let pruned = prune_words(&mut conn)?;
+            println!("Removed {} words no sentence contains anymore", pruned);
+        }
+        Ginkou::Dedupe { fuzzy, db } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            if fuzzy {
+                return Ok(print_near_copies(&conn)?);
+            }
+            let before = database_size(&conn)?;
+            let copies = remove_copies(&mut conn)?;
+            if copies == 0 {
+                println!("No sentences are stored more than once");
+                return Ok(());
+            }
+            conn.execute_batch("VACUUM;")?;
+            let freed = before - database_size(&conn)?;
+            println!(
+                "Removed {} copies of sentences, freeing {}",
+                copies,
+                format_size(freed.max(0) as u64)
+            );
+        }
+        Ginkou::Get {
+            words,
+            all,
+            limit,
+            translate,
+            readings,
+            phrase,
+            romaji,
+            kanji,
+            any,
+            not,
+            by_reading,
+            exact,
+            fuzzy,
+            within,
+            pos,
+            sources,
+            ids,
+            tag,
+            db,
+        } => {
+            let paths = if db.is_empty() {
+                vec![default_db_path(&config)]
+            } else {
+                db
+            };
+            // Searching never changes a database, so a mistyped path doesn't make an empty one
+            for path in &paths {
+                if let Location::Sqlite(ref file) = Location::parse(path) {
+                    if !file.exists() {
+                        println!("There's no database at {}", file.display());
+                        return Ok(());
+                    }
+                }
+            }
+            let store = open_store(&paths, key)?;
+            let store = store.as_ref();
+            let pos = match pos.as_deref().map(|p| (p, tokenizer::part_of_speech(p))) {
+                Some((name, None)) => {
+                    println!("{} isn't a part of speech, like noun, verb, or 動詞", name);
+                    return Ok(());
+                }
+                Some((_, names)) => names,
+                None => None,
+            };
+            let limit = match limit.or(config.limit) {
+                _ if all => None,
+                Some(0) => None,
+                limit => Some(limit.unwrap_or(LIMIT)),
+            };
+            let shown = Shown {
+                ids,
+                translations: translate,
+                sources,
+            };
+            let tag = tag.as_deref();
+            let mut words = words;
+            if romaji {
+                for word in words.iter_mut() {
+                    let kana = match romaji::to_kana(word) {
+                        Err(rest) => {
+                            println!("Couldn't read {} as romaji, starting from {}", word, rest);
+                            return Ok(());
+                        }
+                        Ok(kana) => kana,
+                    };
+                    if phrase {
+                        *word = kana;
+                        continue;
+                    }
+                    // A word sounding like several others can't be told apart from them by its romaji
+                    match words_sounding_like(store, &kana)?.as_slice() {
+                        [] => *word = kana,
+                        [only] => *word = only.clone(),
+                        several => {
+                            print_line(&format!("{} could be {}", word, several.join(", ")));
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            let phrase = phrase || kanji;
+            let words = if !kanji {
+                words
+            } else {
+                match kanji_in(&words) {
+                    Err(c) => {
+                        println!("{} isn't a kanji, and --kanji only searches for kanji", c);
+                        return Ok(());
+                    }
+                    Ok(kanji) => kanji,
+                }
+            };
+            let (words, not): (Vec<String>, Vec<String>) = if phrase {
+                (words, not)
+            } else {
+                let normalized = |words: Vec<String>| words.iter().map(|w| normalize(w)).collect();
+                (normalized(words), normalized(not))
+            };
+            // Readings and wildcards aren't conjugated, so they're looked up as they are
+            let mut words = words;
+            for (i, word) in words.iter_mut().enumerate() {
+                if phrase || exact || (by_reading && i == 0) || word.contains('*') {
+                    continue;
+                }
+                if let Some(dictionary) = dictionary_form(store, word)? {
+                    eprintln!("Searching for {} instead of {}", dictionary, word);
+                    *word = dictionary;
+                }
+            }
+            // A word that isn't indexed anywhere is most likely a typo, like a kanji converted wrong
+            for (i, word) in words.iter_mut().enumerate() {
+                if phrase || (by_reading && i == 0) || word.contains('*') {
+                    continue;
+                }
+                if store.has_word(word)? {
+                    continue;
+                }
+                let closest = closest_words(store, word)?;
+                match closest.first() {
+                    None => (),
+                    Some(best) if fuzzy => {
+                        eprintln!("Searching for {} instead of {}", best, word);
+                        *word = best.clone();
+                    }
+                    Some(_) => {
+                        let meant = closest.join(", ");
+                        println!("No results for {}, did you mean {}?", word, meant);
+                        return Ok(());
+                    }
+                }
+            }
+            if readings && !phrase {
+                for word in words.iter().filter(|w| !w.contains('*')) {
+                    let readings = store.readings(word)?;
+                    print_line(&format!("{}: {}", word, readings.join(", ")));
+                }
+            }
+            let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            let mut query = Query {
+                words,
+                phrase,
+                any,
+                not: &not,
+                within,
+                pos,
+                limit,
+                tag,
+            };
+            let wildcard = query.words[0].contains('*');
+            if !(by_reading || wildcard) || phrase {
+                print_found(store, &names, &query, shown)?;
+                return Ok(());
+            }
+            // Every word read like the first, or matching it, is searched for in turn,
+            // along with the other words
+            let lemmas = if by_reading {
+                store.words_read_as(&query.words[0])?
+            } else {
+                store.words_matching(&query.words[0])?
+            };
+            for lemma in lemmas {
+                print_line(&format!("{}:", lemma));
+                query.words[0] = lemma;
+                print_found(store, &names, &query, shown)?;
+            }
+        }
+        Ginkou::Random {
+            count,
+            containing,
+            tag,
+            min_length,
+            max_length,
+            translate,
+            ids,
+            db,
+        } => {
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let conn = open(&db_path)?;
+            let containing = containing.map(|w| normalize(&w));
+            let picked = Picked {
+                containing: containing.as_deref(),
+                tag: tag.as_deref(),
+                min_length,
+                max_length,
+            };
+            let shown = Shown {
+                ids,
+                translations: translate,
+                sources: false,
+            };
+            let sentences = random_sentences(&conn, count, &picked)?;
+            for (id, sentence) in &sentences {
+                print_sentence(&conn, *id, sentence, shown, None)?;
+            }
+            if sentences.is_empty() {
+                println!("No sentences could be picked");
+            }
+        }
+        Ginkou::Add {
+            file,
+            url,
+            source,
+            tags,
+            format,
+            encoding,
+            lines,
+            preserve_whitespace,
+            delimiters,
+            max_length,
+            min_length,
+            keep_non_japanese,
+            tokenizer,
+            split_mode,
+            dictionary,
+            user_dictionary,
+            nbest,
+            index_particles,
+            batch_size,
+            fast,
+            resume,
+            field,
+            links,
+            translations,
+            get,
+            get_phrase,
+            db,
+        } => {
+            let translations = match (links, translations) {
+                (Some(links), Some(translations)) => {
+                    let loaded = File::open(&links).and_then(|l| {
+                        let t = File::open(&translations)?;
+                        tatoeba::Translations::load(io::BufReader::new(l), io::BufReader::new(t))
+                    });
+                    match loaded {
+                        Err(e) => {
+                            println!("Couldn't read translations:\n {}", e);
+                            return Ok(());
+                        }
+                        Ok(t) => Some(t),
+                    }
+                }
+                _ => None,
+            };
+            let tokenizer = match tokenizer::start(&tokenizer::Options {
+                backend: tokenizer.or(config.tokenizer).unwrap_or_default(),
+                split_mode,
+                dictionary: dictionary.or_else(|| config.dictionary.clone()),
+                user_dictionary: user_dictionary.or_else(|| config.user_dictionary.clone()),
+                nbest,
+            }) {
+                Err(e) => {
+                    println!("Couldn't start the tokenizer:\n {}", e);
+                    return Ok(());
+                }
+                Ok(t) => t,
+            };
+            let settings = AddSettings {
+                format,
+                options: formats::Options {
+                    field: field.saturating_sub(1),
+                    encoding,
+                    path: None,
+                    lines,
+                    delimiters: delimiters
+                        .or_else(|| config.delimiters.clone())
+                        .as_deref()
+                        .map(parse_delimiters),
+                    preserve_whitespace,
+                },
+                translations,
+                resume,
+                max_length: max_length.or(config.max_length).unwrap_or(MAX_LENGTH),
+                min_length: min_length.or(config.min_length).unwrap_or(MIN_LENGTH),
+                japanese_only: !keep_non_japanese,
+                indexer: RefCell::new(Indexer::new(tokenizer, index_particles)),
+                batch_size,
+                source,
+                tags,
+            };
+            let db_path = db.unwrap_or_else(|| default_db_path(&config));
+            let mut conn = open(&db_path)?;
+            // Searches can then keep reading the database while this writes to it
+            if fast || config.fast.unwrap_or(false) {
+                go_fast(&conn)?;
+            } else if db_path != Path::new(":memory:") {
+                write_ahead(&conn)?;
+            }
+            let start = Instant::now();
+            // Waiting for other writers is only possible before this has read anything
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let (input, summary) = match file {
+                None => match url {
+                    Some(url) => {
+                        let summary = add_url(&tx, &settings, &url)?;
+                        (url, summary)
+                    }
+                    None => {
+                        let format = settings.format.unwrap_or(Format::Text);
+                        let summary = add_input(&tx, &settings, format, io::stdin())?;
+                        ("stdin".to_string(), summary)
+                    }
+                },
+                Some(ref path) if path.is_dir() => (
+                    path.display().to_string(),
+                    add_directory(&tx, &settings, path)?,
+                ),
+                Some(ref path) if !path.exists() && inputs::is_pattern(&path.to_string_lossy()) => {
+                    let pattern = path.to_string_lossy();
+                    (pattern.to_string(), add_pattern(&tx, &settings, &pattern)?)
+                }
+                Some(ref path) => (path.display().to_string(), add_file(&tx, &settings, path)?),
+            };
+            let source = settings.source.clone().unwrap_or(input);
+            let seconds = start.elapsed().as_secs_f64();
+            add_history(&tx, &source, summary, seconds)?;
+            tx.commit()?;
+            settings.indexer.borrow().print_unknown();
+            // An in-memory database is gone once this returns, so this is the only chance to search it
+            let limit = Some(config.limit.unwrap_or(LIMIT));
+            let found = match (get, get_phrase) {
+                (Some(word), _) => matching_words(&conn, &normalize(&word), limit, None)?,
+                (None, Some(phrase)) => matching_phrases(&conn, &phrase, limit, None)?,
+                (None, None) => Vec::new(),
+            };
+            for (id, sentence) in found {
+                print_sentence(&conn, id, &sentence, Shown::default(), None)?;
+            }
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use splitter::{Punctuation, SentenceSplitter};
+    use std::process;
+
+    #[test]
+    fn delimiters_can_be_chosen() {
+        let delimiters = parse_delimiters("．\\n");
+        assert_eq!(vec!["．", "\n"], delimiters);
+        let string = "一．二。三\n\n四";
+        let splitter = Punctuation {
+            terminators: delimiters,
+            preserve_whitespace: false,
+        };
+        let found: Vec<String> = splitter
+            .split(Box::new(string.as_bytes()))
+            .map(|s| s.unwrap().text)
+            .collect();
+        assert_eq!(vec!["一．", "二。三", "四"], found);
+    }
+
+    #[test]
+    fn japanese_text_is_recognised() {
+        assert!(has_japanese("see 猫"));
+        assert!(has_japanese("ｶﾀｶﾅ"));
+        assert!(has_japanese("ひらがな"));
+        assert!(!has_japanese("https://example.com/"));
+    }
+
+    #[test]
+    fn bank_lookup_works_correctly() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let sentence1 = String::from("A B");
+        let sentence2 = String::from("A B C");
+        let s1 = add_sentence(&conn, &sentence1)?;
+        add_word(&conn, "A", s1)?;
+        add_word(&conn, "B", s1)?;
+        let s2 = add_sentence(&conn, &sentence2)?;
+        add_word(&conn, "A", s2)?;
+        add_word(&conn, "B", s2)?;
+        add_word(&conn, "C", s2)?;
+        let a_sentences = vec![sentence1.clone(), sentence2.clone()];
+        assert_eq!(Ok(a_sentences), matching_word(&conn, "A"));
+        let c_sentences = vec![sentence2.clone()];
+        assert_eq!(Ok(c_sentences), matching_word(&conn, "C"));
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_can_be_consumed() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let sentence1 = "猫を見た";
+        let sentence2 = "犬を見る";
+        let mut tokenizer = tokenizer::Mock;
+        for sentence in &[sentence1, sentence2] {
+            let words = find_words(&mut tokenizer, sentence).unwrap();
+            consume_trimmed(&conn, sentence, &words, false)?;
+        }
+        let a_sentences = vec![sentence1.into(), sentence2.into()];
+        assert_eq!(Ok(a_sentences), matching_word(&conn, "見る"));
+        let b_sentences = vec![sentence2.into()];
+        assert_eq!(Ok(b_sentences), matching_word(&conn, "犬"));
+        let c_sentences = vec![sentence1.into()];
+        assert_eq!(Ok(c_sentences), matching_word(&conn, "猫"));
+        Ok(())
+    }
+
+    #[test]
+    fn kana_are_found_however_they_are_written() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let sentence_id = add_sentence(&conn, "リンゴとりんごを食べた。")?;
+        add_word(&conn, "リンゴ", sentence_id)?;
+        add_word(&conn, "りんご", sentence_id)?;
+        let sentences = vec!["リンゴとりんごを食べた。".to_string()];
+        assert_eq!(Ok(sentences.clone()), matching_word(&conn, "りんご"));
+        assert_eq!(Ok(sentences), matching_word(&conn, "リンゴ"));
+        Ok(())
+    }
+
+    #[test]
+    fn every_token_is_stored_in_order() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let words = find_words(&mut tokenizer::Mock, "猫を見た").unwrap();
+        let sentence_id = consume_trimmed(&conn, "猫を見た", &words, false)?;
+        let tokens = vec![
+            (Some(0), "猫".to_string()),
+            (Some(1), "を".to_string()),
+            (Some(2), "見".to_string()),
+            (Some(3), "た".to_string()),
+        ];
+        assert_eq!(Ok(tokens), sentence_tokens(&conn, sentence_id));
+        assert_eq!(Ok(Vec::new()), matching_word(&conn, "を"));
+        consume_trimmed(&conn, "猫を見た。", &words, true)?;
+        assert_eq!(1, matching_word(&conn, "を")?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn aliases_find_the_same_sentences() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        for (sentence, word) in &[
+            ("飯を喰う。", "喰う"),
+            ("飯を食う。", "食う"),
+            ("飯を食らう。", "食らう"),
+        ] {
+            let sentence_id = add_sentence(&conn, sentence)?;
+            add_word(&conn, word, sentence_id)?;
+        }
+        assert_eq!(Some("食う".to_string()), add_alias(&conn, "喰う", "食う")?);
+        assert_eq!(
+            Some("食らう".to_string()),
+            add_alias(&conn, "食う", "食らう")?
+        );
+        assert_eq!(None, add_alias(&conn, "食らう", "喰う")?);
+        let mut found = matching_word(&conn, "喰う")?;
+        found.sort();
+        assert_eq!(vec!["飯を喰う。", "飯を食う。", "飯を食らう。"], found);
+        assert_eq!(3, matching_word(&conn, "食らう")?.len());
+        Ok(())
+    }
+
+    // A database with some sentences added, split by the mock tokenizer
+    fn bank_with(sentences: &[&str]) -> rusqlite::Result<Connection> {
+        let conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let sentences = sentences.iter().map(|&s| Ok(Sentence::from(s)));
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        Ok(conn)
+    }
+
+    #[test]
+    fn unknown_words_are_counted() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let sentences = vec![
+            Ok("猫を見た。".into()),
+            Ok("鳥を見た。".into()),
+            Ok("鳥と鳩。".into()),
+        ];
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        assert_eq!(2, indexer.unknown_sentences);
+        assert_eq!(Some(&2), indexer.unknown.get("鳥"));
+        assert_eq!(4, indexer.unknown.values().sum::<usize>());
+        Ok(())
+    }
+
+    #[test]
+    fn databases_can_go_fast() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-fast-{}.db", process::id()));
+        let conn = conn_from_disk(&path)?;
+        go_fast(&conn)?;
+        let mode: String = conn.query_row("PRAGMA journal_mode", params![], |row| row.get(0))?;
+        assert_eq!("wal", mode);
+        // In memory databases can't use WAL, but still work
+        go_fast(&conn_from_memory()?)?;
+        drop(conn);
+        for suffix in &["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn phrases_can_be_found() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        add_sentence(&conn, "猫を見た。")?;
+        add_sentence(&conn, "その猫を見たい。")?;
+        add_sentence(&conn, "100%だ。")?;
+        add_sentence(&conn, "OKだ。")?;
+        let phrases = |phrase| -> rusqlite::Result<Vec<String>> {
+            let mut stmt = conn.prepare(SQL_PHRASE_SENTENCES)?;
+            let params: [&dyn ToSql; 4] = [&like_pattern(phrase), &-1, &None::<String>, &phrase];
+            let results = stmt.query_map(&params, |row| row.get(1))?;
+            results.collect()
+        };
+        assert_eq!(vec!["猫を見た。", "その猫を見たい。"], phrases("を見た")?);
+        assert_eq!(vec!["猫を見た。", "その猫を見たい。"], phrases("猫")?);
+        assert_eq!(vec!["100%だ。"], phrases("%")?);
+        assert_eq!(vec!["OKだ。"], phrases("OKだ")?);
+        assert!(phrases("okだ")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn older_databases_have_their_phrases_indexed() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SQL_SETUP)?;
+        add_sentence(&conn, "猫を見た。")?;
+        create_tables(&conn)?;
+        let found: String = conn.query_row(
+            SQL_PHRASE_SENTENCES,
+            params![like_pattern("を見"), -1, None::<String>, "を見"],
+            |row| row.get(1),
+        )?;
+        assert_eq!("猫を見た。", found);
+        Ok(())
+    }
+
+    #[test]
+    fn readings_are_stored_with_words() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let token = |lemma: &str, reading: Option<&str>| Token {
+            surface: lemma.to_string(),
+            lemma: lemma.to_string(),
+            reading: reading.map(String::from),
+            pos: None,
+            start: None,
+            unknown: false,
+        };
+        let words = vec![token("今日", Some("キョウ")), token("ジョジョ", None)];
+        consume_trimmed(&conn, "今日ジョジョ", &words, false)?;
+        consume_trimmed(&conn, "今日", &[token("今日", Some("コンニチ"))], false)?;
+        consume_trimmed(&conn, "今日も", &[token("今日", Some("キョウ"))], false)?;
+        let readings = vec!["キョウ".to_string(), "コンニチ".to_string()];
+        assert_eq!(Ok(readings), word_readings(&conn, "今日"));
+        assert_eq!(Ok(Vec::new()), word_readings(&conn, "ジョジョ"));
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_can_be_committed_along_the_way() -> rusqlite::Result<()> {
+        let mut conn = conn_from_memory()?;
+        let tx = conn.transaction()?;
+        let sentences = vec![Ok("一。".into()), Ok("二。".into()), Ok("三。".into())];
+        let summary = consume_sentences(
+            &tx,
+            &mut Indexer::new(Box::new(tokenizer::Mock), false),
+            sentences,
+            Some(&Batches::new(2)),
+        )?;
+        tx.commit()?;
+        assert_eq!(3, summary.sentences);
+        let count: u32 = conn.query_row("SELECT COUNT(*) FROM Sentences", params![], |row| {
+            row.get(0)
+        })?;
+        assert_eq!(3, count);
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_are_only_added_once() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let sentences = vec![Ok("猫。".into()), Ok("犬。".into()), Ok("猫。".into())];
+        let summary = consume_sentences(&conn, &mut indexer, sentences, None)?;
+        assert_eq!(2, summary.sentences);
+        assert_eq!(1, summary.duplicates);
+        let summary = consume_sentences(&conn, &mut indexer, vec![Ok("犬。".into())], None)?;
+        assert_eq!(0, summary.sentences);
+        assert_eq!(1, summary.duplicates);
+        assert!(add_sentence(&conn, "猫。").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn databases_with_copies_can_still_be_opened() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SQL_SETUP)?;
+        add_sentence(&conn, "猫。")?;
+        add_sentence(&conn, "猫。")?;
+        create_tables(&conn)?;
+        assert!(has_sentence(&conn, "猫。")?);
+        assert!(!has_sentence(&conn, "犬。")?);
+        Ok(())
+    }
+
+    #[test]
+    fn copies_are_merged_into_one_sentence() -> rusqlite::Result<()> {
+        let mut conn = Connection::open_in_memory()?;
+        conn.execute_batch(SQL_SETUP)?;
+        let first = add_sentence(&conn, "猫を見た。")?;
+        add_word(&conn, "猫", first)?;
+        add_sentence(&conn, "犬。")?;
+        let copy = add_sentence(&conn, "猫を見た。")?;
+        add_word(&conn, "見る", copy)?;
+        add_translation(&conn, copy, "I saw a cat.")?;
+        create_tables(&conn)?;
+        assert_eq!(1, remove_copies(&mut conn)?);
+        assert_eq!(vec!["猫を見た。"], matching_word(&conn, "猫")?);
+        assert_eq!(vec!["猫を見た。"], matching_word(&conn, "見る")?);
+        assert_eq!(vec!["I saw a cat."], sentence_translations(&conn, first)?);
+        assert!(add_sentence(&conn, "猫を見た。").is_err());
+        assert_eq!(0, remove_copies(&mut conn)?);
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_are_read_back_as_pieces() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        for sentence in &["猫が見た。", "猫を見た！"] {
+            let words = find_words(&mut tokenizer::Mock, sentence).unwrap();
+            consume_trimmed(&conn, sentence, &words, false)?;
+        }
+        let sentences = sentence_pieces(&conn)?;
+        assert_eq!(2, sentences.len());
+        let words: Vec<bool> = sentences[0].1.iter().map(|p| p.word).collect();
+        assert_eq!(vec![true, false, true, false, false], words);
+        let pieces: Vec<Vec<similar::Piece>> = sentences.into_iter().map(|s| s.1).collect();
+        assert_eq!(vec![(1, 0)], similar::near_copies(&pieces));
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_is_kept_with_sentences() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut sentence = Sentence::from("猫 を 見た");
+        sentence.metadata.push(("tatoeba_id".into(), "42".into()));
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        consume_sentences(&conn, &mut indexer, vec![Ok(sentence)], None)?;
+        let (text, id): (String, String) = conn.query_row(
+            "SELECT sentence, value FROM Sentences JOIN Metadata ON sentence_id = id",
+            params![],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(("猫を見た".into(), "42".into()), (text, id));
+        Ok(())
+    }
+
+    #[test]
+    fn words_are_normalized_but_sentences_are_not() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut sentence = Sentence::from("ｶﾞｽを使うＡＢＣ");
+        sentence.words.push("ｶﾞｽ".into());
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        consume_sentences(&conn, &mut indexer, vec![Ok(sentence)], None)?;
+        let text: String = conn.query_row("SELECT sentence FROM Sentences", params![], |row| {
+            row.get(0)
+        })?;
+        assert_eq!("ｶﾞｽを使うＡＢＣ", text);
+        assert_eq!(Ok(vec![text]), matching_word(&conn, "A"));
+        let found: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM Words WHERE word = 'ガス')",
+            params![],
+            |row| row.get(0),
+        )?;
+        assert!(found);
+        Ok(())
+    }
+
+    #[test]
+    fn long_and_short_sentences_are_skipped() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let settings = AddSettings {
+            max_length: 3,
+            min_length: 2,
+            indexer: RefCell::new(Indexer::new(Box::new(tokenizer::Mock), false)),
+            batch_size: 0,
+            ..AddSettings::default()
+        };
+        let sentences = vec!["猫だ。", "長すぎる。", "……。", "え？", "OK."];
+        let sentences: formats::SentenceIter =
+            Box::new(sentences.into_iter().map(|s| Ok(s.into())));
+        let summary = add_sentences(&conn, &settings, None, Ok(sentences))?;
+        assert_eq!(1, summary.sentences);
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_are_kept_with_their_source() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut settings = AddSettings {
+            indexer: RefCell::new(Indexer::new(Box::new(tokenizer::Mock), false)),
+            batch_size: 0,
+            ..AddSettings::default()
+        };
+        let file = vec![("source".to_string(), "猫.txt".to_string())];
+        assert_eq!(file, settings.source(Some(Path::new("本/猫.txt"))));
+        assert!(settings.source(None).is_empty());
+        settings.source = Some("となりのトトロ".into());
+        add_input(
+            &conn,
+            &settings,
+            Format::Text,
+            io::Cursor::new("猫を見た。"),
+        )?;
+        assert_eq!(
+            Some("となりのトトロ".to_string()),
+            sentence_metadata(&conn, 1, "source")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn older_databases_get_timestamps() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE Sentences(id INTEGER PRIMARY KEY, sentence TEXT NOT NULL);
+             CREATE TABLE Words(id INTEGER PRIMARY KEY, word TEXT UNIQUE NOT NULL);
+             INSERT INTO Sentences(sentence) VALUES('犬。');",
+        )?;
+        create_tables(&conn)?;
+        let id = add_sentence(&conn, "猫。")?;
+        add_word(&conn, "猫", id)?;
+        let added = |query| -> rusqlite::Result<Option<String>> {
+            conn.query_row(query, params![], |row| row.get(0))
+        };
+        assert_eq!(
+            None,
+            added("SELECT created_at FROM Sentences WHERE id = 1")?
+        );
+        assert!(added("SELECT created_at FROM Sentences WHERE id = 2")?.is_some());
+        assert!(added("SELECT created_at FROM Words")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_can_be_found_by_tag() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut sentence = Sentence::from("猫を見た。");
+        sentence.tags.push("anime".into());
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let sentences = vec![Ok(sentence), Ok("猫が見る。".into())];
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        let tagged = |tag| -> rusqlite::Result<Vec<String>> {
+            let mut stmt = conn.prepare(SQL_ALL_WORD_SENTENCES)?;
+            let results = stmt.query_map(word_params("猫", tag), |row| row.get(1))?;
+            results.collect()
+        };
+        assert_eq!(vec!["猫を見た。"], tagged(Some("anime"))?);
+        assert_eq!(2, tagged(None)?.len());
+        add_tag(&conn, 2, "anime")?;
+        add_tag(&conn, 2, "formal")?;
+        assert_eq!(2, tagged(Some("anime"))?.len());
+        assert!(remove_tag(&conn, 1, "anime")?);
+        assert!(!remove_tag(&conn, 1, "anime")?);
+        assert_eq!(vec!["猫が見る。"], tagged(Some("anime"))?);
+        assert_eq!(vec!["猫が見る。"], tagged(Some("formal"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_with_every_word_are_found() -> store::Result<()> {
+        let conn = bank_with(&["犬が猫を見た。", "犬を見た。", "猫を見た。"])?;
+        let all = |words: &[&str], phrase, limit| -> store::Result<Vec<String>> {
+            let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+            let found = sentences_with_all(&conn, &words, phrase, limit, None)?;
+            Ok(found.into_iter().map(|(_, sentence)| sentence).collect())
+        };
+        assert_eq!(3, all(&["見る"], false, None)?.len());
+        assert_eq!(
+            vec!["犬を見た。", "犬が猫を見た。"],
+            all(&["犬", "見る"], false, Some(LIMIT))?
+        );
+        assert_eq!(vec!["犬を見た。"], all(&["見る", "犬"], false, Some(1))?);
+        assert_eq!(
+            vec!["猫を見た。", "犬が猫を見た。"],
+            all(&["猫を", "見た"], true, None)?
+        );
+        assert!(all(&["犬", "猫", "鳥"], false, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_with_any_word_are_found_once() -> store::Result<()> {
+        let conn = bank_with(&["犬が猫を見た。", "犬を見た。", "猫。"])?;
+        let any = |words: &[&str], limit| -> store::Result<Vec<String>> {
+            let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+            let found = sentences_with_any(&conn, &words, false, limit, None)?;
+            Ok(found.into_iter().map(|(_, sentence)| sentence).collect())
+        };
+        assert_eq!(
+            vec!["犬が猫を見た。", "犬を見た。", "猫。"],
+            any(&["犬", "猫"], None)?
+        );
+        assert_eq!(vec!["猫。", "犬を見た。"], any(&["猫", "犬"], Some(2))?);
+        assert_eq!(
+            vec!["猫。", "犬が猫を見た。"],
+            any(&["鳥", "猫"], Some(LIMIT))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_with_unwanted_words_are_left_out() -> store::Result<()> {
+        let conn = bank_with(&["犬が猫を見た。", "犬を見た。", "猫を見た。"])?;
+        let mut found = matching_words(&conn, "見る", None, None)?;
+        exclude(&conn, &mut found, &["猫".to_string()], false)?;
+        assert_eq!(vec![(2, "犬を見た。".to_string())], found);
+        let mut found = matching_phrases(&conn, "見た", None, None)?;
+        exclude(&conn, &mut found, &["犬を".into()], true)?;
+        let ids: Vec<u32> = found.iter().map(|(id, _)| *id).collect();
+        assert_eq!(vec![3, 1], ids);
+        Ok(())
+    }
+
+    #[test]
+    fn unwanted_words_are_left_out_before_the_limit() -> store::Result<()> {
+        let conn = bank_with(&[
+            "犬が猫を見た。",
+            "猫を見た。",
+            "犬を見ていた。",
+            "犬を見た。",
+        ])?;
+        let not = vec!["猫".to_string()];
+        let query = Query {
+            words: vec!["見る".into()],
+            phrase: false,
+            any: false,
+            not: &not,
+            within: None,
+            pos: None,
+            limit: Some(1),
+            tag: None,
+        };
+        assert_eq!(vec![(4, "犬を見た。".to_string())], query.find(&conn)?);
+        Ok(())
+    }
+
+    #[test]
+    fn words_can_be_found_by_reading() -> rusqlite::Result<()> {
+        let conn = bank_with(&["猫を見た。", "ねこが見る。"])?;
+        add_word(&conn, "ねこ", 2)?;
+        add_reading(&conn, "ねこ", "ネコ")?;
+        assert_eq!(vec!["ねこ", "猫"], words_read_as(&conn, "ねこ")?);
+        assert_eq!(vec!["見る"], words_read_as(&conn, "ミル")?);
+        assert!(words_read_as(&conn, "いぬ")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn conjugated_words_are_searched_for_in_their_dictionary_form() -> store::Result<()> {
+        let conn = bank_with(&["猫を見た。"])?;
+        assert_eq!(Some("見る".into()), dictionary_form(&conn, "見ました")?);
+        assert_eq!(Some("見る".into()), dictionary_form(&conn, "見なかった")?);
+        assert_eq!(None, dictionary_form(&conn, "見る")?);
+        assert_eq!(None, dictionary_form(&conn, "食べた")?);
+        Ok(())
+    }
+
+    #[test]
+    fn words_can_be_found_by_wildcard() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let id = add_sentence(&conn, "見せて見つけた。")?;
+        for word in &["見る", "見せる", "見つける", "花見", "見?"] {
+            add_word(&conn, word, id)?;
+        }
+        assert_eq!(
+            vec!["見?", "見せる", "見つける", "見る"],
+            words_matching(&conn, "見*")?
+        );
+        assert_eq!(vec!["花見"], words_matching(&conn, "*見")?);
+        assert_eq!(vec!["見?"], words_matching(&conn, "見?")?);
+        Ok(())
+    }
+
+    #[test]
+    fn words_can_be_found_near_each_other() -> store::Result<()> {
+        let conn = bank_with(&["猫を見た。", "猫が犬を見た。"])?;
+        assert_eq!(vec![0], word_positions(&conn, "猫", 1)?);
+        assert_eq!(vec![2], word_positions(&conn, "見る", 1)?);
+        let near = |distance| -> store::Result<Vec<u32>> {
+            let words = vec!["猫".to_string(), "見る".to_string()];
+            let mut found = sentences_with_all(&conn, &words, false, None, None)?;
+            keep_near(&conn, &mut found, &words, distance)?;
+            Ok(found.into_iter().map(|(id, _)| id).collect())
+        };
+        assert_eq!(vec![1, 2], near(4)?);
+        assert_eq!(vec![1], near(2)?);
+        assert!(near(1)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn words_can_be_found_by_part_of_speech() -> store::Result<()> {
+        let conn = bank_with(&["猫を見た。"])?;
+        let noun = Token {
+            surface: "見".into(),
+            lemma: "見る".into(),
+            reading: None,
+            pos: Some("名詞".into()),
+            start: None,
+            unknown: false,
+        };
+        let id = add_sentence(&conn, "見だ")?;
+        add_token(&conn, id, 0, &noun)?;
+        add_word(&conn, "見る", id)?;
+        add_part_of_speech(&conn, "見る", "名詞")?;
+        // Without the parts of speech of its tokens, a sentence could use the word as either
+        let old = add_sentence(&conn, "見るだ")?;
+        add_word(&conn, "見る", old)?;
+        let used_as = |pos| -> store::Result<Vec<u32>> {
+            let words = vec!["見る".to_string()];
+            let mut found = sentences_with_all(&conn, &words, false, None, None)?;
+            keep_part_of_speech(
+                &conn,
+                &mut found,
+                "見る",
+                tokenizer::part_of_speech(pos).unwrap(),
+            )?;
+            Ok(found.into_iter().map(|(id, _)| id).collect())
+        };
+        assert_eq!(vec![1, old], used_as("verb")?);
+        assert_eq!(vec![id, old], used_as("名詞")?);
+        assert!(used_as("adverb")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn words_close_to_typos_are_suggested() -> store::Result<()> {
+        let conn = bank_with(&["猫を見た。"])?;
+        add_word(&conn, "雰囲気", 1)?;
+        assert_eq!(vec!["雰囲気"], closest_words(&conn, "雰意気")?);
+        assert_eq!(vec!["見る"], closest_words(&conn, "見た")?);
+        assert!(closest_words(&conn, "勉強")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn random_sentences_are_only_picked_from_those_asked_for() -> rusqlite::Result<()> {
+        let conn = bank_with(&["猫を見た。", "犬が猫を見た。"])?;
+        add_tag(&conn, 1, "anime")?;
+        let picked = |count, picked: Picked| -> rusqlite::Result<Vec<u32>> {
+            let mut ids: Vec<u32> = random_sentences(&conn, count, &picked)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            Ok(ids)
+        };
+        assert_eq!(1, picked(1, Picked::default())?.len());
+        assert_eq!(vec![1, 2], picked(5, Picked::default())?);
+        let containing = Picked {
+            containing: Some("犬"),
+            ..Picked::default()
+        };
+        assert_eq!(vec![2], picked(5, containing)?);
+        let tagged = Picked {
+            tag: Some("anime"),
+            ..Picked::default()
+        };
+        assert_eq!(vec![1], picked(5, tagged)?);
+        let long = Picked {
+            min_length: Some(6),
+            max_length: Some(7),
+            ..Picked::default()
+        };
+        assert_eq!(vec![2], picked(5, long)?);
+        Ok(())
+    }
+
+    #[test]
+    fn kanji_are_searched_for_one_at_a_time() {
+        let words = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(Ok(words(&["憂", "鬱"])), kanji_in(&words(&["憂鬱"])));
+        assert_eq!(
+            Ok(words(&["人", "々", "猫"])),
+            kanji_in(&words(&["人々", "猫"]))
+        );
+        assert_eq!(Err('の'), kanji_in(&words(&["猫の"])));
+    }
+
+    #[test]
+    fn romaji_is_searched_for_by_reading() -> store::Result<()> {
+        let conn = bank_with(&["猫を見た。"])?;
+        add_word(&conn, "ねこ", 1)?;
+        assert_eq!(vec!["見る"], words_sounding_like(&conn, "みた")?);
+        assert_eq!(vec!["猫", "ねこ"], words_sounding_like(&conn, "ねこ")?);
+        assert!(words_sounding_like(&conn, "いぬ")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn databases_are_migrated_once() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        assert_eq!(MIGRATIONS.len(), schema_version(&conn)?);
+        create_tables(&conn)?;
+        let applied: i64 =
+            conn.query_row("SELECT COUNT(*) FROM SchemaVersion", params![], |row| {
+                row.get(0)
+            })?;
+        assert_eq!(MIGRATIONS.len() as i64, applied);
+        Ok(())
+    }
+
+    #[test]
+    fn unversioned_databases_are_brought_up_to_date() -> rusqlite::Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE Sentences(id INTEGER PRIMARY KEY, sentence TEXT NOT NULL);
+             CREATE TABLE Words(id INTEGER PRIMARY KEY, word TEXT UNIQUE NOT NULL);",
+        )?;
+        create_tables(&conn)?;
+        assert_eq!(MIGRATIONS.len(), schema_version(&conn)?);
+        assert!(has_column(&conn, "Sentences", "created_at")?);
+        assert!(has_table(&conn, "Tags")?);
+        Ok(())
+    }
+
+    #[test]
+    fn older_databases_are_upgraded_with_a_copy() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-upgrade-{}.db", process::id()));
+        let copy = backup_path(&path, 0);
+        let old = Connection::open(&path)?;
+        old.execute_batch(
+            "CREATE TABLE Sentences(id INTEGER PRIMARY KEY, sentence TEXT NOT NULL);
+             CREATE TABLE Words(id INTEGER PRIMARY KEY, word TEXT UNIQUE NOT NULL);
+             INSERT INTO Sentences(sentence) VALUES('猫。');",
+        )?;
+        drop(old);
+        let conn = conn_from_disk(&path)?;
+        assert_eq!(MIGRATIONS.len(), schema_version(&conn)?);
+        assert!(!has_column(
+            &Connection::open(&copy)?,
+            "Sentences",
+            "created_at"
+        )?);
+        drop(conn);
+        for file in &[&path, &copy] {
+            let _ = fs::remove_file(file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn databases_are_put_back_if_upgrading_fails() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-restore-{}.db", process::id()));
+        let old = Connection::open(&path)?;
+        // Without the table of words, its column can't be added
+        old.execute_batch(
+            "CREATE TABLE Sentences(id INTEGER PRIMARY KEY, sentence TEXT NOT NULL);
+             INSERT INTO Sentences(sentence) VALUES('猫。');",
+        )?;
+        drop(old);
+        assert!(conn_from_disk(&path).is_err());
+        let conn = Connection::open(&path)?;
+        assert!(!has_column(&conn, "Sentences", "created_at")?);
+        assert!(!has_table(&conn, "Tags")?);
+        drop(conn);
+        for file in &[path.clone(), backup_path(&path, 0)] {
+            let _ = fs::remove_file(file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sizes_are_described() {
+        assert_eq!("512 B", format_size(512));
+        assert_eq!("1.5 KB", format_size(1536));
+        assert_eq!("2.0 GB", format_size(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn databases_can_be_compacted() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        add_sentence(&conn, "猫を見た。")?;
+        compact(&conn, true)?;
+        let found: String = conn.query_row(
+            SQL_PHRASE_SENTENCES,
+            params![like_pattern("を見"), -1, None::<String>, "を見"],
+            |row| row.get(1),
+        )?;
+        assert_eq!("猫を見た。", found);
+        Ok(())
+    }
+
+    #[test]
+    fn orphaned_rows_are_found() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let id = add_sentence(&conn, "猫を見た。")?;
+        add_word(&conn, "猫", id)?;
+        assert_eq!(Vec::<String>::new(), check(&conn)?);
+        conn.execute("DELETE FROM Sentences WHERE id = ?1", params![id])?;
+        assert_eq!(
+            vec!["1 rows of WordSentence point at rows of Sentences that aren't there"],
+            check(&conn)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_can_be_exported() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let words = vec![Token {
+            surface: "猫".into(),
+            lemma: "猫".into(),
+            reading: Some("ネコ".into()),
+            pos: None,
+            start: None,
+            unknown: false,
+        }];
+        let id = consume_trimmed(&conn, "猫だ。", &words, false)?;
+        add_tag(&conn, id, "pets")?;
+        add_metadata(&conn, id, "source", "日記")?;
+        let mut out = Vec::new();
+        assert_eq!(1, export_sentences(&conn, export::Format::Csv, &mut out)?);
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[1].starts_with("1,猫だ。,日記,pets,猫,ネコ,,"));
+        Ok(())
+    }
+
+    #[test]
+    fn databases_can_be_merged() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-import-{}.db", process::id()));
+        let theirs = conn_from_disk(&path)?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let mut sentence = Sentence::from("犬を見た。");
+        sentence.tags.push("pets".into());
+        let sentences = vec![Ok("猫を見た。".into()), Ok(sentence)];
+        consume_sentences(&theirs, &mut indexer, sentences, None)?;
+        drop(theirs);
+        let mut conn = conn_from_memory()?;
+        consume_sentences(&conn, &mut indexer, vec![Ok("猫を見た。".into())], None)?;
+        let summary = import_database(&mut conn, &path, None)?;
+        assert_eq!(1, summary.sentences);
+        assert_eq!(1, summary.duplicates);
+        assert_eq!(
+            vec!["猫を見た。", "犬を見た。"],
+            matching_word(&conn, "見る")?
+        );
+        let mut stmt = conn.prepare(SQL_ALL_WORD_SENTENCES)?;
+        let tagged = stmt.query_map(word_params("犬", Some("pets")), |row| {
+            row.get::<_, String>(1)
+        })?;
+        assert_eq!(1, tagged.count());
+        let _ = fs::remove_file(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn exports_can_be_imported() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        add_sentence(&conn, "猫だ。")?;
+        let export = r#"{"sentence":"猫だ。","tags":["pets"],"words":[{"word":"猫","readings":["ネコ"]}]}
+
+{"sentence":"犬だ。","source":"日記","words":[{"word":"犬","readings":[]}],"created_at":"2020-01-01 00:00:00"}
+not json"#;
+        let summary = import_export(&conn, io::Cursor::new(export))?;
+        assert_eq!(1, summary.sentences);
+        assert_eq!(1, summary.duplicates);
+        assert_eq!(1, summary.errors);
+        assert_eq!(vec!["猫だ。"], matching_word(&conn, "猫")?);
+        assert_eq!(vec!["ネコ"], word_readings(&conn, "猫")?);
+        assert_eq!(Some("日記".into()), sentence_metadata(&conn, 2, "source")?);
+        Ok(())
+    }
+
+    #[test]
+    fn backups_are_rotated() -> rusqlite::Result<()> {
+        let dir = env::temp_dir().join(format!("ginkou-backup-{}", process::id()));
+        let _ = fs::create_dir(&dir);
+        let dest = dir.join("ginkou.db");
+        let conn = conn_from_memory()?;
+        for sentence in &["猫だ。", "犬だ。", "猫を見た。"] {
+            add_sentence(&conn, sentence)?;
+            backup_to(&conn, &dest, 1, None)?;
+        }
+        let count = |path: &Path| {
+            let copy = Connection::open(path)?;
+            count_sentences(&copy, SQL_COUNT_SENTENCES)
+        };
+        assert_eq!(3, count(&dest)?);
+        assert_eq!(2, count(&rotated_path(&dest, 1))?);
+        assert!(!rotated_path(&dest, 2).exists());
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_can_be_removed() -> rusqlite::Result<()> {
+        let mut conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let mut sentence = Sentence::from("犬を見た。");
+        sentence.tags.push("pets".into());
+        let sentences = vec![
+            Ok("猫を見た。".into()),
+            Ok(sentence),
+            Ok("猫が犬を見た。".into()),
+        ];
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        assert_eq!(1, remove_sentences(&mut conn, SQL_REMOVE_BY_ID, &[&1])?);
+        assert_eq!(0, remove_sentences(&mut conn, SQL_REMOVE_BY_ID, &[&1])?);
+        assert_eq!(
+            2,
+            remove_sentences(&mut conn, SQL_REMOVE_MATCHING, &[&"犬を"])?
+        );
+        assert!(matching_word(&conn, "見る")?.is_empty());
+        assert!(check(&conn)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn orphan_words_can_be_pruned() -> rusqlite::Result<()> {
+        let mut conn = bank_with(&["猫を見た。", "犬を見た。"])?;
+        assert_eq!(0, prune_words(&mut conn)?);
+        remove_sentences(&mut conn, SQL_REMOVE_MATCHING, &[&"犬"])?;
+        assert_eq!(1, prune_words(&mut conn)?);
+        assert!(word_readings(&conn, "犬")?.is_empty());
+        assert_eq!(vec!["猫を見た。"], matching_word(&conn, "見る")?);
+        assert!(check(&conn)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sources_can_be_removed() -> rusqlite::Result<()> {
+        let mut conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let from_dump = |text: &str| {
+            let mut sentence = Sentence::from(text);
+            let source = ("source".to_string(), "dump_final_v2.txt".to_string());
+            sentence.metadata.push(source);
+            Ok(sentence)
+        };
+        let sentences = vec![
+            from_dump("猫を見た。"),
+            Ok("猫が見た。".into()),
+            from_dump("犬を見た。"),
+        ];
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        assert_eq!(
+            0,
+            remove_sentences(&mut conn, SQL_REMOVE_BY_SOURCE, &[&"dump"])?
+        );
+        let source = "dump_final_v2.txt";
+        assert_eq!(
+            2,
+            remove_sentences(&mut conn, SQL_REMOVE_BY_SOURCE, &[&source])?
+        );
+        assert_eq!(vec!["猫が見た。"], matching_word(&conn, "見る")?);
+        Ok(())
+    }
+
+    #[test]
+    fn sources_are_listed() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        let from = |text: &str, source: &str| {
+            let mut sentence = Sentence::from(text);
+            sentence.metadata.push(("source".into(), source.into()));
+            Ok(sentence)
+        };
+        let sentences = vec![
+            from("猫を見た。", "日記"),
+            from("犬を見た。", "日記"),
+            from("猫が見た。", "NHK"),
+            Ok("犬が見た。".into()),
+        ];
+        consume_sentences(&conn, &mut indexer, sentences, None)?;
+        conn.execute_batch(
+            "UPDATE Sentences SET created_at = '2020-01-01 12:00:00' WHERE id = 3;",
+        )?;
+        let listed = sources(&conn)?;
+        assert_eq!(2, listed.len());
+        assert_eq!(
+            "NHK: 1 sentences, 2 words, added 2020-01-01",
+            listed[0].to_string()
+        );
+        assert_eq!("日記", listed[1].name);
+        assert_eq!((2, 3), (listed[1].sentences, listed[1].words));
+        Ok(())
+    }
+
+    #[test]
+    fn sources_can_be_renamed() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let id = add_sentence(&conn, "猫を見た。")?;
+        add_metadata(&conn, id, "source", "dump_final_v2 (1).txt")?;
+        assert_eq!(0, rename_source(&conn, "dump", "けいおん！")?);
+        assert_eq!(
+            1,
+            rename_source(&conn, "dump_final_v2 (1).txt", "けいおん！")?
+        );
+        assert_eq!(
+            Some("けいおん！".into()),
+            sentence_metadata(&conn, id, "source")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn adding_is_kept_in_the_history() -> rusqlite::Result<()> {
+        let conn = conn_from_memory()?;
+        let summary = Summary {
+            sentences: 1204,
+            errors: 2,
+            duplicates: 3,
+        };
+        add_history(&conn, "dump_final_v2.txt", summary, 12.34)?;
+        let runs = history(&conn)?;
+        assert_eq!(1, runs.len());
+        assert_eq!(summary, runs[0].summary);
+        let described = runs[0].to_string();
+        assert!(described.ends_with(
+            " dump_final_v2.txt: added 1204 sentences, skipped 3 already added, with 2 errors, in 12.3s"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_databases_are_used_until_moved() -> rusqlite::Result<()> {
+        let dir = env::temp_dir().join(format!("ginkou-relocate-{}", process::id()));
+        let _ = fs::create_dir(&dir);
+        let (legacy, data) = (dir.join(".ginkoudb"), dir.join("ginkou").join("bank.db"));
+        let pick = || pick_db_path(Some(data.clone()), Some(legacy.clone()));
+        assert_eq!(data, pick());
+        let conn = conn_from_disk(&legacy)?;
+        add_sentence(&conn, "猫だ。")?;
+        drop(conn);
+        assert_eq!(legacy, pick());
+        assert!(relocate(&legacy, &data)?);
+        assert!(!legacy.exists());
+        assert_eq!(data, pick());
+        assert_eq!(
+            1,
+            count_sentences(&conn_from_disk(&data)?, SQL_COUNT_SENTENCES)?
+        );
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[test]
+    fn the_database_can_be_chosen_by_environment() {
+        let env_db = |path: &str| Some(OsString::from(path));
+        assert_eq!(
+            None,
+            chosen_db_path(&config::Settings::default(), env_db(""))
+        );
+        let config = config::Settings {
+            database: Some(PathBuf::from("/tmp/technical.db")),
+            ..config::Settings::default()
+        };
+        let fiction = Some(PathBuf::from("/tmp/fiction.db"));
+        let technical = Some(PathBuf::from("/tmp/technical.db"));
+        assert_eq!(fiction, chosen_db_path(&config, env_db("/tmp/fiction.db")));
+        let profile = config::Settings {
+            profile: Some("technical".into()),
+            ..config.clone()
+        };
+        assert_eq!(
+            technical,
+            chosen_db_path(&profile, env_db("/tmp/fiction.db"))
+        );
+        assert_eq!(technical, chosen_db_path(&config, None));
+    }
+
+    // Each database is on disk, since those in memory can't be attached
+    fn attached_banks(name: &str, banks: &[&[&str]]) -> rusqlite::Result<(Attached, Vec<PathBuf>)> {
+        let mut paths = Vec::new();
+        for (i, sentences) in banks.iter().enumerate() {
+            let path = env::temp_dir().join(format!("ginkou-{}-{}-{}.db", name, process::id(), i));
+            let _ = fs::remove_file(&path);
+            let conn = conn_from_disk(&path)?;
+            let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+            let sentences = sentences.iter().map(|&s| Ok(Sentence::from(s)));
+            consume_sentences(&conn, &mut indexer, sentences, None)?;
+            paths.push(path);
+        }
+        Ok((Attached::open(&paths, None)?, paths))
+    }
+
+    fn found_in(found: Vec<(u32, String)>, banks: usize) -> Vec<(usize, u32)> {
+        found.into_iter().map(|(id, _)| locate(id, banks)).collect()
+    }
+
+    #[test]
+    fn tables_are_named_along_with_their_database() {
+        assert_eq!(
+            "SELECT id FROM bank1.Sentences JOIN bank1.Tags ON bank1.Tags.id = s.tag_id",
+            in_schema(
+                "SELECT id FROM Sentences JOIN Tags ON Tags.id = s.tag_id",
+                "bank1"
+            )
+        );
+        assert_eq!(
+            "SELECT sentence FROM bank2.sentences WHERE sentence LIKE 'Words' ESCAPE '\\'",
+            in_schema(
+                "SELECT sentence FROM sentences WHERE sentence LIKE 'Words' ESCAPE '\\'",
+                "bank2"
+            )
+        );
+    }
+
+    #[test]
+    fn sentences_from_several_databases_are_ranked_together() -> store::Result<()> {
+        let (attached, paths) =
+            attached_banks("ranked", &[&["猫が犬を見た。", "猫だ。"], &["猫を見た。"]])?;
+        let best = attached.sentences_with_word("猫", Some(2), None)?;
+        assert_eq!(vec![(0, 2), (1, 1)], found_in(best, 2));
+        let all = attached.sentences_with_word("猫", None, None)?;
+        assert_eq!(vec![(0, 1), (0, 2), (1, 1)], found_in(all, 2));
+        let phrases = attached.sentences_with_phrase("見た", Some(1), None)?;
+        assert_eq!(vec![(1, 1)], found_in(phrases, 2));
+        assert_eq!(vec!["猫"], attached.words_matching("猫*")?);
+        assert!(attached.has_word("犬")? && !attached.has_word("鳥")?);
+        for path in paths {
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_in_several_databases_are_only_shown_once() -> store::Result<()> {
+        let (attached, paths) = attached_banks(
+            "shown-once",
+            &[&["猫だ。", "猫が犬を見た。"], &["猫だ。", "猫を見た。"]],
+        )?;
+        let best = attached.sentences_with_word("猫", Some(2), None)?;
+        assert_eq!(vec![(0, 1), (1, 2)], found_in(best, 2));
+        assert_eq!(3, attached.sentences_with_word("猫", None, None)?.len());
+        for path in paths {
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sentences_found_in_several_databases_are_looked_up_in_their_own() -> store::Result<()> {
+        let (attached, paths) =
+            attached_banks("looked-up", &[&["猫だ。"], &["犬だ。", "猫が犬を見た。"]])?;
+        let query = Query {
+            words: vec!["猫".into(), "犬".into()],
+            phrase: false,
+            any: false,
+            not: &[],
+            within: Some(2),
+            pos: None,
+            limit: Some(LIMIT),
+            tag: None,
+        };
+        let found = query.find(&attached)?;
+        assert_eq!(vec![(1, 2)], found_in(found.clone(), 2));
+        assert_eq!(vec![0], attached.positions("猫", found[0].0)?);
+        assert_eq!(None, attached.source(found[0].0)?);
+        for path in paths {
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn databases_can_be_opened_read_only() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-read-only-{}.db", process::id()));
+        assert!(conn_read_only(&path, None).is_err());
+        assert!(!path.exists());
+        let conn = conn_from_disk(&path)?;
+        add_sentence(&conn, "猫だ。")?;
+        drop(conn);
+        let conn = conn_read_only(&path, None)?;
+        assert_eq!(1, count_sentences(&conn, SQL_COUNT_SENTENCES)?);
+        assert!(add_sentence(&conn, "犬だ。").is_err());
+        drop(conn);
+        let _ = fs::remove_file(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn older_databases_are_left_alone_when_only_read() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-old-read-only-{}.db", process::id()));
+        let old = Connection::open(&path)?;
+        old.execute_batch(
+            "CREATE TABLE Sentences(id INTEGER PRIMARY KEY, sentence TEXT NOT NULL);
+             CREATE TABLE Words(id INTEGER PRIMARY KEY, word TEXT UNIQUE NOT NULL);
+             INSERT INTO Sentences(sentence) VALUES('猫。');",
+        )?;
+        drop(old);
+        let before = fs::read(&path).unwrap();
+        assert!(conn_read_only(&path, None).is_err());
+        assert_eq!(before, fs::read(&path).unwrap());
+        assert!(!backup_path(&path, 0).exists());
+        drop(conn_from_disk(&path)?);
+        assert_ne!(before, fs::read(&path).unwrap());
+        assert!(conn_read_only(&path, None).is_ok());
+        for file in &[path.clone(), backup_path(&path, 0)] {
+            let _ = fs::remove_file(file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn only_commands_reading_the_database_can_be_read_only() {
+        let writes = |args: &[&str]| Opt::from_iter(args).command.writes();
+        assert!(!writes(&["ginkou", "get", "猫"]));
+        assert!(!writes(&["ginkou", "sources"]));
+        assert!(!writes(&["ginkou", "dedupe", "--fuzzy"]));
+        assert!(writes(&["ginkou", "dedupe"]));
+        assert!(writes(&["ginkou", "sources", "rename", "a.txt", "本"]));
+        assert!(writes(&["ginkou", "add", "-f", "a.txt"]));
+        assert!(writes(&["ginkou", "tag", "1", "anime"]));
+    }
+
+    #[test]
+    fn databases_can_be_kept_in_memory() -> rusqlite::Result<()> {
+        let conn = conn_from_disk(":memory:")?;
+        let mut indexer = Indexer::new(Box::new(tokenizer::Mock), false);
+        consume_sentences(&conn, &mut indexer, vec![Ok("猫を見た。".into())], None)?;
+        assert_eq!(1, matching_words(&conn, "猫", Some(LIMIT), None)?.len());
+        assert!(!Path::new(":memory:").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn waiting_on_a_busy_database_gives_up() -> rusqlite::Result<()> {
+        let path = env::temp_dir().join(format!("ginkou-busy-{}.db", process::id()));
+        let mut writer = conn_from_disk(&path)?;
+        let other = conn_from_disk(&path)?;
+        other.busy_timeout(Duration::from_millis(10))?;
+        let tx = writer.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        add_sentence(&tx, "猫だ。")?;
+        let error = add_sentence(&other, "犬だ。").unwrap_err();
+        assert!(is_busy(&error));
+        tx.commit()?;
+        assert!(add_sentence(&other, "犬だ。").is_ok());
+        drop((writer, other));
+        let _ = fs::remove_file(&path);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Fetching articles from NHK News Web Easy.
+//!
+//! The site lists its recent articles in a JSON file, grouped by the date they
+//! were published, and each article's page holds its body in a known element.
+use std::io;
+
+use crate::formats::markup::{element_by_id, strip_tags};
+use crate::json::{self, Value};
+
+pub const LIST_URL: &str = "https://www3.nhk.or.jp/news/easy/news-list.json";
+
+/// An article listed on the site.
+#[derive(Debug, PartialEq)]
+pub struct Article {
+    pub id: String,
+    pub title: String,
+    /// The day it was published, like `2019-06-01`.
+    pub date: String,
+}
+
+impl Article {
+    pub fn url(&self) -> String {
+        format!("https://www3.nhk.or.jp/news/easy/{0}/{0}.html", self.id)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read the articles from the list of recent ones, newest first.
+pub fn articles(list: &str) -> io::Result<Vec<Article>> {
+    let value =
+        json::parse(list.trim_start_matches('\u{feff}')).map_err(|e| invalid(e.to_string()))?;
+    // The dates are the members of an object, wrapped in an array
+    let days = match value {
+        Value::Array(mut values) if !values.is_empty() => values.swap_remove(0),
+        other => other,
+    };
+    let days = match days {
+        Value::Object(days) => days,
+        _ => return Err(invalid("unexpected article list".into())),
+    };
+    let mut articles = Vec::new();
+    for (date, listed) in days {
+        let listed = match listed {
+            Value::Array(listed) => listed,
+            _ => continue,
+        };
+        for article in listed {
+            let field = |name| article.get(name).and_then(Value::as_str).map(String::from);
+            if let Some(id) = field("news_id") {
+                articles.push(Article {
+                    id,
+                    title: field("title").unwrap_or_default(),
+                    date: date.clone(),
+                });
+            }
+        }
+    }
+    Ok(articles)
+}
+
+/// Extract the text of an article's body from its page, without readings.
+pub fn body(page: &str) -> Option<String> {
+    element_by_id(page, "js-article-body").map(|body| strip_tags(body).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn articles_are_listed_with_their_date() {
+        let list = "\u{feff}[{\"2019-06-02\": [{\"news_id\": \"k1\", \"title\": \"雨\"}],\
+                    \"2019-06-01\": [{\"news_id\": \"k0\", \"title\": \"晴れ\"}]}]";
+        let articles = articles(list).unwrap();
+        assert_eq!(2, articles.len());
+        assert_eq!("2019-06-01", articles[1].date);
+        assert_eq!(
+            "https://www3.nhk.or.jp/news/easy/k1/k1.html",
+            articles[0].url()
+        );
+    }
+
+    #[test]
+    fn body_is_extracted_without_readings() {
+        let page = r#"<html><body><h1>題</h1><div class="article-body" id="js-article-body">
+            <p><ruby>雨<rt>あめ</rt></ruby>が<span>降</span>りました。</p></div></body></html>"#;
+        assert_eq!(Some("雨が降りました。".into()), body(page));
+    }
+}
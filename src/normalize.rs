@@ -0,0 +1,220 @@
+//! Normalizing the different ways of writing the same characters.
+//!
+//! This follows NFKC for the characters found in Japanese text: full width
+//! ASCII, half width katakana, separate voicing marks, and compatibility
+//! characters like ① or ㍻. Other text is left as it is.
+
+// The full width forms of U+FF61 to U+FF9F, in order
+const HALF_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチ\
+                          ツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン\u{3099}\u{309a}";
+
+const VOICED_MARK: char = '\u{3099}';
+const SEMI_VOICED_MARK: char = '\u{309a}';
+
+// Combine a kana with a following voicing mark, like か and ゙ into が
+fn compose(base: char, mark: char) -> Option<char> {
+    if mark == VOICED_MARK {
+        let composed = match base {
+            'う' => Some('ゔ'),
+            'ウ' => Some('ヴ'),
+            'ワ' => Some('ヷ'),
+            'ヰ' => Some('ヸ'),
+            'ヱ' => Some('ヹ'),
+            'ヲ' => Some('ヺ'),
+            'ゝ' => Some('ゞ'),
+            'ヽ' => Some('ヾ'),
+            _ => None,
+        };
+        if composed.is_some() {
+            return composed;
+        }
+    }
+    let code = base as u32;
+    // Katakana are laid out like hiragana, 0x60 later
+    let hiragana = if (0x30a1..=0x30f6).contains(&code) {
+        code - 0x60
+    } else {
+        code
+    };
+    let hagyou = matches!(hiragana, 0x306f | 0x3072 | 0x3075 | 0x3078 | 0x307b);
+    let voiceable = ((0x304b..=0x3061).contains(&hiragana) && (hiragana - 0x304b) % 2 == 0)
+        || matches!(hiragana, 0x3064 | 0x3066 | 0x3068)
+        || hagyou;
+    let offset = match mark {
+        VOICED_MARK if voiceable => 1,
+        SEMI_VOICED_MARK if hagyou => 2,
+        _ => return None,
+    };
+    std::char::from_u32(code + offset)
+}
+
+// The characters a compatibility character stands for
+fn expand(c: char) -> Option<&'static str> {
+    let expanded = match c {
+        '㈱' => "(株)",
+        '㈲' => "(有)",
+        '㍻' => "平成",
+        '㍼' => "昭和",
+        '㍽' => "大正",
+        '㍾' => "明治",
+        '㋿' => "令和",
+        '㌔' => "キロ",
+        '㌘' => "グラム",
+        '㌢' => "センチ",
+        '㌧' => "トン",
+        '㌫' => "パーセント",
+        '㍉' => "ミリ",
+        '㍍' => "メートル",
+        '㍑' => "リットル",
+        '…' => "...",
+        '‥' => "..",
+        '™' => "TM",
+        '№' => "No",
+        _ => return None,
+    };
+    Some(expanded)
+}
+
+const ROMAN_NUMERALS: [&str; 12] = [
+    "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII",
+];
+
+/// Normalize some text, so that text written differently but meaning the same is equal.
+///
+/// Byte order marks are removed too, since they're sometimes left inside of text.
+pub fn normalize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let code = c as u32;
+        let c = match code {
+            0xfeff => continue,
+            0x3000 => ' ',
+            0xff01..=0xff5e => std::char::from_u32(code - 0xfee0).unwrap_or(c),
+            0xff61..=0xff9f => HALF_WIDTH
+                .chars()
+                .nth((code - 0xff61) as usize)
+                .unwrap_or(c),
+            0xffe0 => '¢',
+            0xffe1 => '£',
+            0xffe5 => '¥',
+            0x2460..=0x2473 => {
+                out.push_str(&(code - 0x245f).to_string());
+                continue;
+            }
+            0x2160..=0x216b => {
+                out.push_str(ROMAN_NUMERALS[(code - 0x2160) as usize]);
+                continue;
+            }
+            0x2170..=0x217b => {
+                out.push_str(&ROMAN_NUMERALS[(code - 0x2170) as usize].to_lowercase());
+                continue;
+            }
+            _ => match expand(c) {
+                Some(expanded) => {
+                    out.push_str(expanded);
+                    continue;
+                }
+                None => c,
+            },
+        };
+        if c == VOICED_MARK || c == SEMI_VOICED_MARK {
+            if let Some(composed) = out.chars().last().and_then(|base| compose(base, c)) {
+                out.pop();
+                out.push(composed);
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Write the hiragana in some text as katakana, like りんご as リンゴ.
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' => std::char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Write the katakana in some text as hiragana, like リンゴ as りんご.
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{30a1}'..='\u{30f6}' => std::char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Whether a character is a kanji, counting the repetition mark 々 as one.
+pub fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{3005}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}')
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309f}' | '\u{30a0}'..='\u{30ff}')
+}
+
+/// Remove readings written in brackets after kanji, like the ばら in 薔薇（ばら）.
+///
+/// Only brackets holding nothing but kana, right after a kanji, are taken to be readings.
+pub fn strip_readings(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let after_kanji = i > 0 && is_kanji(chars[i - 1]);
+        if after_kanji && (c == '(' || c == '（') {
+            let close = if c == '(' { ')' } else { '）' };
+            let length = chars[i + 1..].iter().take_while(|&&k| is_kana(k)).count();
+            if length > 0 && chars.get(i + 1 + length) == Some(&close) {
+                i += length + 2;
+                continue;
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_are_normalized() {
+        assert_eq!("ABC123!", normalize("ＡＢＣ１２３！"));
+        assert_eq!("ガッコウ。パン", normalize("ｶﾞｯｺｳ｡ﾊﾟﾝ"));
+        assert_eq!("ヴ", normalize("ｳﾞ"));
+    }
+
+    #[test]
+    fn marks_are_composed() {
+        assert_eq!("がぱヴ", normalize("か\u{3099}は\u{309a}ウ\u{3099}"));
+        assert_eq!("ぱ", normalize("ぱ"));
+    }
+
+    #[test]
+    fn compatibility_characters_are_expanded() {
+        assert_eq!("10番と令和IV年(株)", normalize("\u{feff}⑩番と㋿Ⅳ年㈱"));
+    }
+
+    #[test]
+    fn kana_can_be_swapped() {
+        assert_eq!("リンゴヲ食ベル", to_katakana("りんごを食べる"));
+        assert_eq!("りんごを食べる", to_hiragana("リンゴヲ食ベル"));
+        assert_eq!("ゔぁー", to_hiragana("ヴァー"));
+    }
+
+    #[test]
+    fn readings_are_stripped() {
+        assert_eq!("薔薇が咲く", strip_readings("薔薇（ばら）が咲く"));
+        assert_eq!("東京に行く", strip_readings("東京(とうきょう)に行く"));
+        assert_eq!("猫(3匹)と(あと)", strip_readings("猫(3匹)と(あと)"));
+    }
+}
@@ -0,0 +1,74 @@
+//! Getting the passphrase of an encrypted database, without it showing up on the command line.
+//!
+//! It's asked for on the terminal, even when sentences are piped in, or read from a key file.
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[cfg(unix)]
+const TERMINAL: &str = "/dev/tty";
+#[cfg(windows)]
+const TERMINAL: &str = "CONIN$";
+
+// stty isn't on every platform, and the passphrase is still read without it, just shown as typed
+fn echo(on: bool) -> bool {
+    let terminal = match File::open(TERMINAL) {
+        Err(_) => return false,
+        Ok(t) => t,
+    };
+    Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(terminal)
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+// A passphrase is always one line, which might end in a newline
+fn first_line(text: &str) -> io::Result<String> {
+    let line = text.lines().next().unwrap_or("");
+    if line.is_empty() {
+        let msg = "the passphrase is empty";
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    }
+    Ok(line.to_string())
+}
+
+/// Ask for a passphrase on the terminal, without showing what's typed.
+pub fn prompt(message: &str) -> io::Result<String> {
+    let mut terminal = BufReader::new(File::open(TERMINAL)?);
+    eprint!("{}: ", message);
+    io::stderr().flush()?;
+    let hidden = echo(false);
+    let mut line = String::new();
+    let read = terminal.read_line(&mut line);
+    if hidden {
+        echo(true);
+        eprintln!();
+    }
+    read?;
+    first_line(&line)
+}
+
+/// Read a passphrase from the first line of a file.
+pub fn read(path: &Path) -> io::Result<String> {
+    first_line(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn key_files_hold_one_line() {
+        let path = env::temp_dir().join(format!("ginkou-key-{}", process::id()));
+        fs::write(&path, "猫が好き\r\n").unwrap();
+        assert_eq!("猫が好き", read(&path).unwrap());
+        fs::write(&path, "\n").unwrap();
+        assert!(read(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,211 @@
+//! Turning romaji, like taberu, into hiragana, for typing words without a Japanese input method.
+//!
+//! Both Hepburn and Kunrei-shiki spellings are read, like shi and si for し, along with
+//! macrons for long vowels, like kōen for こうえん.
+
+/// The kana each syllable is written with, longest first so that kya is read before ki.
+const SYLLABLES: [(&str, &str); 130] = [
+    ("kya", "きゃ"),
+    ("kyu", "きゅ"),
+    ("kyo", "きょ"),
+    ("sha", "しゃ"),
+    ("shi", "し"),
+    ("shu", "しゅ"),
+    ("she", "しぇ"),
+    ("sho", "しょ"),
+    ("sya", "しゃ"),
+    ("syu", "しゅ"),
+    ("syo", "しょ"),
+    ("chi", "ち"),
+    ("cha", "ちゃ"),
+    ("chu", "ちゅ"),
+    ("che", "ちぇ"),
+    ("cho", "ちょ"),
+    ("tya", "ちゃ"),
+    ("tyu", "ちゅ"),
+    ("tyo", "ちょ"),
+    ("tsu", "つ"),
+    ("nya", "にゃ"),
+    ("nyu", "にゅ"),
+    ("nyo", "にょ"),
+    ("hya", "ひゃ"),
+    ("hyu", "ひゅ"),
+    ("hyo", "ひょ"),
+    ("mya", "みゃ"),
+    ("myu", "みゅ"),
+    ("myo", "みょ"),
+    ("rya", "りゃ"),
+    ("ryu", "りゅ"),
+    ("ryo", "りょ"),
+    ("gya", "ぎゃ"),
+    ("gyu", "ぎゅ"),
+    ("gyo", "ぎょ"),
+    ("jya", "じゃ"),
+    ("jyu", "じゅ"),
+    ("jyo", "じょ"),
+    ("zya", "じゃ"),
+    ("zyu", "じゅ"),
+    ("zyo", "じょ"),
+    ("bya", "びゃ"),
+    ("byu", "びゅ"),
+    ("byo", "びょ"),
+    ("pya", "ぴゃ"),
+    ("pyu", "ぴゅ"),
+    ("pyo", "ぴょ"),
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("sa", "さ"),
+    ("si", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("ta", "た"),
+    ("ti", "ち"),
+    ("tu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("fu", "ふ"),
+    ("hu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("fa", "ふぁ"),
+    ("fi", "ふぃ"),
+    ("fe", "ふぇ"),
+    ("fo", "ふぉ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("ya", "や"),
+    ("yu", "ゆ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("wa", "わ"),
+    ("wo", "を"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("za", "ざ"),
+    ("ji", "じ"),
+    ("zi", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("ja", "じゃ"),
+    ("ju", "じゅ"),
+    ("je", "じぇ"),
+    ("jo", "じょ"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("vu", "ゔ"),
+    ("n'", "ん"),
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+    ("-", "ー"),
+];
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+// Long vowels written with macrons are spelled out, as in kōen for kouen
+fn without_macrons(romaji: &str) -> String {
+    let mut text = String::new();
+    for c in romaji.to_lowercase().chars() {
+        match c {
+            'ā' => text.push_str("aa"),
+            'ī' => text.push_str("ii"),
+            'ū' => text.push_str("uu"),
+            'ē' => text.push_str("ee"),
+            'ō' => text.push_str("ou"),
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// Turn romaji into hiragana, giving back the part that isn't romaji if there is one.
+pub fn to_kana(romaji: &str) -> Result<String, String> {
+    let text = without_macrons(romaji);
+    let mut kana = String::new();
+    let mut rest = text.as_str();
+    while let Some(c) = rest.chars().next() {
+        let next = rest[c.len_utf8()..].chars().next();
+        // A doubled consonant is a small tsu, as in kitte, and so is the t of tch, as in matcha.
+        // nn is two syllables instead, as in konnichiwa
+        if (c != 'n' && !is_vowel(c) && next == Some(c)) || rest.starts_with("tch") {
+            kana.push('っ');
+            rest = &rest[1..];
+            continue;
+        }
+        // A lone n before another consonant, or at the end, is ん
+        let syllabic = matches!(next, Some(n) if is_vowel(n) || n == 'y' || n == '\'');
+        if c == 'n' && !syllabic {
+            kana.push('ん');
+            rest = &rest[1..];
+            continue;
+        }
+        let syllable = SYLLABLES.iter().find(|(r, _)| rest.starts_with(r));
+        match syllable {
+            None => return Err(rest.to_string()),
+            Some((r, k)) => {
+                kana.push_str(k);
+                rest = &rest[r.len()..];
+            }
+        }
+    }
+    Ok(kana)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romaji_is_read_as_hiragana() {
+        assert_eq!(Ok("たべる".into()), to_kana("taberu"));
+        assert_eq!(Ok("きって".into()), to_kana("kitte"));
+        assert_eq!(Ok("こんにちわ".into()), to_kana("konnichiwa"));
+        assert_eq!(Ok("しんぶん".into()), to_kana("shinbun"));
+        assert_eq!(Ok("きんえん".into()), to_kana("kin'en"));
+        assert_eq!(Ok("ほんや".into()), to_kana("hon'ya"));
+        assert_eq!(Ok("まっちゃ".into()), to_kana("matcha"));
+        assert_eq!(Ok("こうえん".into()), to_kana("Kōen"));
+        assert_eq!(Ok("しゃしん".into()), to_kana("syasin"));
+        assert_eq!(Ok("らーめん".into()), to_kana("ra-men"));
+        assert_eq!(Err("xyz".into()), to_kana("taxyz"));
+    }
+}
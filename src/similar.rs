@@ -0,0 +1,124 @@
+//! Finding sentences that are nearly the same, like one line from two different subtitle rips.
+use std::collections::HashMap;
+
+/// One token of a sentence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub surface: String,
+    /// Whether this is one of the words the sentence is found by, instead of a particle.
+    pub word: bool,
+}
+
+fn is_punctuation(surface: &str) -> bool {
+    surface.chars().all(|c| !c.is_alphanumeric())
+}
+
+// Whether one sequence can be made into the other by changing, adding, or removing one element
+fn within_one_edit(a: &[&str], b: &[&str]) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long).take_while(|(x, y)| x == y).count();
+    let suffix = short[prefix..]
+        .iter()
+        .rev()
+        .zip(long[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    long.len() - prefix - suffix <= 1
+}
+
+// The tokens of a sentence that aren't punctuation
+fn text(pieces: &[Piece]) -> Vec<&str> {
+    pieces
+        .iter()
+        .map(|p| p.surface.as_str())
+        .filter(|s| !is_punctuation(s))
+        .collect()
+}
+
+/// Find the sentences differing from an earlier one only in punctuation, or in a single particle.
+///
+/// Each is returned as its index, along with the index of the earlier sentence it's nearly the same as.
+/// Only sentences with the same words, in the same order, are compared with each other.
+pub fn near_copies(sentences: &[Vec<Piece>]) -> Vec<(usize, usize)> {
+    let mut groups: HashMap<Vec<&str>, Vec<usize>> = HashMap::new();
+    let mut copies = Vec::new();
+    for (i, pieces) in sentences.iter().enumerate() {
+        let words: Vec<&str> = pieces
+            .iter()
+            .filter(|p| p.word && !is_punctuation(&p.surface))
+            .map(|p| p.surface.as_str())
+            .collect();
+        if words.is_empty() {
+            continue;
+        }
+        let group = groups.entry(words).or_default();
+        let this = text(pieces);
+        if let Some(&earlier) = group
+            .iter()
+            .find(|&&j| within_one_edit(&text(&sentences[j]), &this))
+        {
+            copies.push((i, earlier));
+        } else {
+            group.push(i);
+        }
+    }
+    copies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pieces(tokens: &[(&str, bool)]) -> Vec<Piece> {
+        tokens
+            .iter()
+            .map(|&(surface, word)| Piece {
+                surface: surface.to_string(),
+                word,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn edits_are_counted() {
+        assert!(within_one_edit(
+            &["猫", "が", "見た"],
+            &["猫", "を", "見た"]
+        ));
+        assert!(within_one_edit(&["猫", "見た"], &["猫", "を", "見た"]));
+        assert!(within_one_edit(&["猫"], &["猫"]));
+        assert!(!within_one_edit(
+            &["猫", "が", "犬", "を"],
+            &["猫", "を", "犬", "が"]
+        ));
+        assert!(!within_one_edit(&["猫"], &["猫", "を", "見た"]));
+    }
+
+    #[test]
+    fn near_copies_are_found() {
+        let sentences = vec![
+            pieces(&[
+                ("猫", true),
+                ("が", false),
+                ("見", true),
+                ("た", false),
+                ("。", false),
+            ]),
+            pieces(&[
+                ("猫", true),
+                ("を", false),
+                ("見", true),
+                ("た", false),
+                ("！", false),
+            ]),
+            pieces(&[("猫", true), ("見", true), ("た", false), ("……", false)]),
+            pieces(&[("犬", true), ("を", false), ("見", true), ("た", false)]),
+            pieces(&[("猫", true), ("が", false), ("犬", true), ("を", false)]),
+            pieces(&[("猫", true), ("を", false), ("犬", true), ("が", false)]),
+        ];
+        assert_eq!(vec![(1, 0), (2, 0)], near_copies(&sentences));
+    }
+}
@@ -0,0 +1,343 @@
+//! Splitting text into sentences.
+//!
+//! Each way of splitting is a [`SentenceSplitter`], so that formats can pick the one
+//! fitting how their text is laid out.
+use std::io;
+use std::io::BufRead;
+
+use crate::formats::SentenceIter;
+use crate::{strip_whitespace, Sentence, SentenceError};
+
+/// The punctuation ending a sentence by default, which stays attached to it.
+const TERMINATORS: [&str; 8] = ["。", "｡", "！", "？", "!", "?", "‼", "⁉"];
+/// The brackets around quotes, inside of which punctuation doesn't end a sentence.
+const QUOTES: [(&str, &str); 3] = [("「", "」"), ("『", "』"), ("（", "）")];
+/// How many bytes a quote can go on for before it's assumed to never be closed.
+const QUOTE_LIMIT: usize = 1024;
+
+/// A way of splitting text into sentences.
+pub trait SentenceSplitter {
+    /// Split the text read from some input into sentences, reading only as much as needed.
+    fn split<'a>(&self, reader: Box<dyn BufRead + 'a>) -> SentenceIter<'a>;
+}
+
+/// Remove the whitespace in a sentence, or only its new lines and surrounding spaces,
+/// which keeps the spacing in things like lyrics and romaji.
+pub fn tidy_whitespace(text: &str, preserve: bool) -> String {
+    if preserve {
+        text.trim().replace(['\n', '\r'], "")
+    } else {
+        strip_whitespace(text)
+    }
+}
+
+struct Sentences<R> {
+    reader: R,
+    terminators: Vec<String>,
+    preserve_whitespace: bool,
+    done: bool,
+}
+
+impl<R: io::BufRead> Sentences<R> {
+    /// Keep the spacing inside of each sentence, instead of removing all of it.
+    fn preserving_whitespace(mut self, preserve: bool) -> Self {
+        self.preserve_whitespace = preserve;
+        self
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.reader.fill_buf()?.first().cloned();
+        if byte.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(byte)
+    }
+
+    // The terminator coming next in the input, without reading it
+    fn peek_terminator(&mut self) -> io::Result<Option<&str>> {
+        let buf = self.reader.fill_buf()?;
+        Ok(self
+            .terminators
+            .iter()
+            .find(|t| buf.starts_with(t.as_bytes()))
+            .map(String::as_str))
+    }
+
+    fn ends_sentence(&self, text: &[u8]) -> bool {
+        self.terminators
+            .iter()
+            .any(|t| text.ends_with(t.as_bytes()))
+    }
+
+    // Whether a quote ending here also ends its sentence, being followed by a new line or quote
+    fn quote_ends_sentence(&mut self) -> io::Result<bool> {
+        let buf = self.reader.fill_buf()?;
+        Ok(buf.is_empty()
+            || buf.starts_with(b"\n")
+            || buf.starts_with(b"\r")
+            || QUOTES
+                .iter()
+                .any(|(open, _)| buf.starts_with(open.as_bytes())))
+    }
+}
+
+impl<B: io::BufRead> Iterator for Sentences<B> {
+    type Item = Result<String, SentenceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut buf = Vec::new();
+        // The closing brackets of the quotes currently open, innermost last
+        let mut closers: Vec<&str> = Vec::new();
+        let mut quote_start = 0;
+        loop {
+            let byte = match self.next_byte() {
+                Err(e) => return Some(Err(e.into())),
+                Ok(None) => break,
+                Ok(Some(b)) => b,
+            };
+            buf.push(byte);
+            // A quote left open at the end of a line ends with it, so it can't swallow the rest
+            if byte == b'\n' && !closers.is_empty() {
+                break;
+            }
+            if buf.len() - quote_start > QUOTE_LIMIT {
+                closers.clear();
+            }
+            if let Some((_, close)) = QUOTES
+                .iter()
+                .find(|(open, _)| buf.ends_with(open.as_bytes()))
+            {
+                if closers.is_empty() {
+                    quote_start = buf.len();
+                }
+                closers.push(close);
+                continue;
+            }
+            if let Some(i) = closers.iter().rposition(|c| buf.ends_with(c.as_bytes())) {
+                let quoted = &buf[..buf.len() - closers[i].len()];
+                let ended = self.ends_sentence(quoted);
+                closers.truncate(i);
+                if closers.is_empty() && ended {
+                    match self.quote_ends_sentence() {
+                        Err(e) => return Some(Err(e.into())),
+                        Ok(true) => break,
+                        Ok(false) => {}
+                    }
+                }
+                continue;
+            }
+            if closers.is_empty() && self.ends_sentence(&buf) {
+                // Runs of punctuation like ！？ end a single sentence
+                loop {
+                    let t = match self.peek_terminator() {
+                        Err(e) => return Some(Err(e.into())),
+                        Ok(None) => break,
+                        Ok(Some(t)) => t.as_bytes().to_vec(),
+                    };
+                    self.reader.consume(t.len());
+                    buf.extend_from_slice(&t);
+                }
+                break;
+            }
+        }
+        if buf.is_empty() {
+            self.done = true;
+            return None;
+        }
+        let next = String::from_utf8(buf).map_err(SentenceError::from);
+        Some(next.map(|x| tidy_whitespace(&x, self.preserve_whitespace)))
+    }
+}
+
+/// Split text into sentences, each ending with one of some terminators.
+fn sentences<R: io::BufRead>(reader: R, terminators: &[String]) -> Sentences<R> {
+    Sentences {
+        reader,
+        terminators: terminators.to_vec(),
+        preserve_whitespace: false,
+        done: false,
+    }
+}
+
+/// The terminators sentences end with, unless others are chosen.
+pub fn default_terminators() -> Vec<String> {
+    TERMINATORS.iter().map(|t| t.to_string()).collect()
+}
+
+/// Ends sentences on punctuation like 。 or ！, except inside of quotes.
+#[derive(Clone, Debug)]
+pub struct Punctuation {
+    /// The strings ending a sentence.
+    pub terminators: Vec<String>,
+    /// Whether to keep the spacing inside of sentences.
+    pub preserve_whitespace: bool,
+}
+
+impl Default for Punctuation {
+    fn default() -> Self {
+        Punctuation {
+            terminators: default_terminators(),
+            preserve_whitespace: false,
+        }
+    }
+}
+
+impl SentenceSplitter for Punctuation {
+    fn split<'a>(&self, reader: Box<dyn BufRead + 'a>) -> SentenceIter<'a> {
+        let sentences =
+            sentences(reader, &self.terminators).preserving_whitespace(self.preserve_whitespace);
+        Box::new(sentences.map(|s| s.map(Sentence::from)))
+    }
+}
+
+/// Reads each line that isn't blank as a whole sentence.
+#[derive(Clone, Debug, Default)]
+pub struct Lines {
+    /// Whether to keep the spacing inside of sentences.
+    pub preserve_whitespace: bool,
+}
+
+impl SentenceSplitter for Lines {
+    fn split<'a>(&self, reader: Box<dyn BufRead + 'a>) -> SentenceIter<'a> {
+        let preserve_whitespace = self.preserve_whitespace;
+        let iter = reader
+            .lines()
+            .map(move |line| {
+                line.map(|l| Sentence::from(tidy_whitespace(&l, preserve_whitespace)))
+                    .map_err(SentenceError::from)
+            })
+            .filter(|s| s.as_ref().map_or(true, |s| !s.text.is_empty()));
+        Box::new(iter)
+    }
+}
+
+/// Ends a sentence at the end of each cue, like those of subtitles, as well as wherever
+/// another splitter would.
+///
+/// When splitting text, cues are separated by blank lines.
+#[derive(Clone, Debug, Default)]
+pub struct Cues<S> {
+    pub inner: S,
+}
+
+impl<S: SentenceSplitter + Clone + 'static> Cues<S> {
+    /// Split cues that have already been separated from each other.
+    pub fn split_cues<'a>(&self, cues: Vec<String>) -> SentenceIter<'a> {
+        let inner = self.inner.clone();
+        let iter = cues
+            .into_iter()
+            .flat_map(move |cue| inner.split(Box::new(io::Cursor::new(cue))));
+        Box::new(iter)
+    }
+}
+
+impl<S: SentenceSplitter + Clone + 'static> SentenceSplitter for Cues<S> {
+    fn split<'a>(&self, reader: Box<dyn BufRead + 'a>) -> SentenceIter<'a> {
+        let mut lines = reader.lines();
+        let cues = std::iter::from_fn(move || {
+            let mut cue = String::new();
+            for line in &mut lines {
+                match line {
+                    Err(e) => return Some(Err(e)),
+                    Ok(l) if l.trim().is_empty() && !cue.is_empty() => break,
+                    Ok(l) => {
+                        cue.push_str(&l);
+                        cue.push('\n');
+                    }
+                }
+            }
+            if cue.is_empty() {
+                None
+            } else {
+                Some(Ok(cue))
+            }
+        });
+        let inner = self.inner.clone();
+        Box::new(cues.flat_map(move |cue| -> SentenceIter<'a> {
+            match cue {
+                Err(e) => Box::new(std::iter::once(Err(e.into()))),
+                Ok(cue) => inner.split(Box::new(io::Cursor::new(cue))),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences_works_correctly() {
+        let string = "A。\n  B。\n\n XXC。";
+        let mut iter = sentences(
+            std::io::BufReader::new(string.as_bytes()),
+            &default_terminators(),
+        );
+        let a = iter.next();
+        assert_eq!(String::from("A。"), a.unwrap().unwrap());
+        let b = iter.next();
+        assert_eq!(String::from("B。"), b.unwrap().unwrap());
+        let c = iter.next();
+        assert_eq!(String::from("XXC。"), c.unwrap().unwrap());
+    }
+
+    #[test]
+    fn exclamations_and_questions_end_sentences() {
+        let string = "本当！？嘘だ!行く?‼ えっ⁉まだ｡";
+        let found: Vec<String> = sentences(string.as_bytes(), &default_terminators())
+            .map(Result::unwrap)
+            .collect();
+        let expected = vec!["本当！？", "嘘だ!", "行く?‼", "えっ⁉", "まだ｡"];
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn whitespace_can_be_preserved() {
+        let string = " 君の 名は\n kimi no na wa。 次";
+        let found: Vec<String> = sentences(string.as_bytes(), &default_terminators())
+            .preserving_whitespace(true)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec!["君の 名は kimi no na wa。", "次"], found);
+    }
+
+    #[test]
+    fn quotes_are_kept_in_one_sentence() {
+        let string = "「行く。『来た！』よ。」と言った。\n「え？」「うん。」\n（笑。ね。\n終わり。";
+        let found: Vec<String> = sentences(string.as_bytes(), &default_terminators())
+            .map(Result::unwrap)
+            .collect();
+        let expected = vec![
+            "「行く。『来た！』よ。」と言った。",
+            "「え？」",
+            "「うん。」",
+            "（笑。ね。",
+            "終わり。",
+        ];
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn lines_are_whole_sentences() {
+        let found: Vec<String> = Lines::default()
+            .split(Box::new("一。二\n\n 三\n".as_bytes()))
+            .map(|s| s.unwrap().text)
+            .collect();
+        assert_eq!(vec!["一。二", "三"], found);
+    }
+
+    #[test]
+    fn cues_end_sentences() {
+        let cues = Cues {
+            inner: Punctuation::default(),
+        };
+        let found: Vec<String> = cues
+            .split(Box::new("一。二\n三\n\n四\n".as_bytes()))
+            .map(|s| s.unwrap().text)
+            .collect();
+        assert_eq!(vec!["一。", "二三", "四"], found);
+    }
+}
@@ -0,0 +1 @@
+INSERT OR REPLACE INTO WordAliases(alias, canonical) VALUES(?1, ?2);
\ No newline at end of file
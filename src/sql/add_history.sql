@@ -0,0 +1,2 @@
+INSERT INTO History(added_at, source, sentences, duplicates, errors, seconds)
+VALUES(datetime('now'), ?1, ?2, ?3, ?4, ?5);
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT OR REPLACE INTO Metadata(sentence_id, key, value) VALUES(?1, ?2, ?3);
\ No newline at end of file
@@ -0,0 +1,2 @@
+INSERT OR IGNORE INTO PartsOfSpeech(word_id, pos)
+SELECT id, ?2 FROM Words WHERE word=?1;
\ No newline at end of file
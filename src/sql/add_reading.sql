@@ -0,0 +1,2 @@
+INSERT OR IGNORE INTO Readings(word_id, reading)
+SELECT id, ?2 FROM Words WHERE word=?1;
\ No newline at end of file
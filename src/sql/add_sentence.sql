@@ -1 +1 @@
-INSERT INTO Sentences(sentence) VALUES(?1);
\ No newline at end of file
+INSERT INTO Sentences(sentence, created_at) VALUES(?1, datetime('now'));
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT OR IGNORE INTO Tags(name) VALUES(?1);
\ No newline at end of file
@@ -0,0 +1,2 @@
+INSERT OR IGNORE INTO SentenceTags(sentence_id, tag_id)
+SELECT ?1, id FROM Tags WHERE name = ?2;
\ No newline at end of file
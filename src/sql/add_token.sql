@@ -0,0 +1 @@
+INSERT INTO Tokens(sentence_id, position, start, surface, lemma, pos) VALUES(?1, ?2, ?3, ?4, ?5, ?6);
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO Translations(sentence_id, translation) VALUES(?1, ?2);
\ No newline at end of file
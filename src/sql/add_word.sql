@@ -1,2 +1,2 @@
-INSERT INTO Words(word)
-SELECT ?1 WHERE NOT EXISTS(SELECT 1 FROM words WHERE word=?1);
\ No newline at end of file
+INSERT INTO Words(word, created_at)
+SELECT ?1, datetime('now') WHERE NOT EXISTS(SELECT 1 FROM words WHERE word=?1);
\ No newline at end of file
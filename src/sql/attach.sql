@@ -0,0 +1 @@
+ATTACH DATABASE ?1 AS other;
\ No newline at end of file
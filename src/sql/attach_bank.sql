@@ -0,0 +1 @@
+ATTACH DATABASE ?1 AS ?2;
\ No newline at end of file
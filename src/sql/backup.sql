@@ -0,0 +1 @@
+VACUUM INTO ?1;
\ No newline at end of file
@@ -1,6 +1,18 @@
-SELECT sentence FROM sentences
+WITH Spellings(word) AS (VALUES (?1), (?2), (?3)),
+Canonical(word) AS (
+    SELECT COALESCE((SELECT canonical FROM WordAliases WHERE alias = Spellings.word), word)
+    FROM Spellings
+),
+Lookup(word) AS (
+    SELECT word FROM Canonical
+    UNION SELECT alias FROM WordAliases WHERE canonical IN (SELECT word FROM Canonical)
+)
+SELECT DISTINCT sentences.id, sentence FROM sentences
 LEFT JOIN wordsentence ON wordsentence.sentence_id = sentences.id 
 LEFT JOIN words ON words.id = wordsentence.word_id
-WHERE word=?1
+WHERE word IN (SELECT word FROM Lookup)
+AND (?4 IS NULL OR sentences.id IN (
+    SELECT sentence_id FROM SentenceTags JOIN Tags ON Tags.id = tag_id WHERE name = ?4
+))
 ORDER BY length(sentence)
-LIMIT 200;
\ No newline at end of file
+LIMIT ?5;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT canonical FROM WordAliases WHERE alias=?1;
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO SentenceText(SentenceText) VALUES('integrity-check');
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT offset FROM Checkpoints WHERE path=?1;
\ No newline at end of file
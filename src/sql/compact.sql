@@ -0,0 +1,3 @@
+VACUUM;
+ANALYZE;
+PRAGMA wal_checkpoint(TRUNCATE);
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT COUNT(*) FROM temp.Copies;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT COUNT(*) FROM other.Sentences;
\ No newline at end of file
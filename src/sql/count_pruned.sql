@@ -0,0 +1 @@
+SELECT COUNT(*) FROM temp.Pruned;
\ No newline at end of file
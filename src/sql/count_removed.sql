@@ -0,0 +1 @@
+SELECT COUNT(*) FROM temp.Removed;
\ No newline at end of file
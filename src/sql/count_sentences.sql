@@ -0,0 +1 @@
+SELECT COUNT(*) FROM Sentences;
\ No newline at end of file
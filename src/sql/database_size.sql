@@ -0,0 +1 @@
+SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size();
\ No newline at end of file
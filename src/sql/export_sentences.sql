@@ -0,0 +1 @@
+SELECT id, sentence, created_at FROM Sentences ORDER BY id;
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TEMP TABLE Copies AS
+SELECT id AS copy, kept FROM (
+    SELECT id, MIN(id) OVER (PARTITION BY sentence) AS kept FROM Sentences
+)
+WHERE id != kept;
\ No newline at end of file
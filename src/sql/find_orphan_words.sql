@@ -0,0 +1,3 @@
+CREATE TEMP TABLE Pruned AS
+SELECT id FROM Words
+WHERE NOT EXISTS (SELECT 1 FROM WordSentence WHERE word_id = Words.id);
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2);
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM Metadata WHERE key=?1 AND value=?2);
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name='SentenceText');
\ No newline at end of file
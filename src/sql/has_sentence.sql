@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM Sentences WHERE sentence = ?1);
\ No newline at end of file
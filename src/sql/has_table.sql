@@ -0,0 +1 @@
+SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1);
\ No newline at end of file
@@ -0,0 +1,2 @@
+SELECT EXISTS(SELECT 1 FROM Words WHERE word IN (?1, ?2, ?3))
+OR EXISTS(SELECT 1 FROM WordAliases WHERE alias IN (?1, ?2, ?3));
\ No newline at end of file
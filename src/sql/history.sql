@@ -0,0 +1 @@
+SELECT added_at, source, sentences, duplicates, errors, seconds FROM History ORDER BY id;
\ No newline at end of file
@@ -0,0 +1,55 @@
+INSERT INTO Words(word, created_at)
+SELECT word, created_at FROM other.Words
+WHERE word NOT IN (SELECT word FROM main.Words);
+
+INSERT OR IGNORE INTO Sentences(sentence, created_at)
+SELECT sentence, created_at FROM other.Sentences o
+WHERE NOT EXISTS (SELECT 1 FROM main.Sentences WHERE sentence = o.sentence)
+ORDER BY id;
+
+CREATE TEMP TABLE SentenceMap AS
+SELECT o.id AS old, s.id AS new FROM other.Sentences o
+JOIN main.Sentences s ON s.sentence = o.sentence;
+
+CREATE TEMP TABLE WordMap AS
+SELECT o.id AS old, w.id AS new FROM other.Words o
+JOIN main.Words w ON w.word = o.word;
+
+INSERT OR IGNORE INTO WordSentence(word_id, sentence_id)
+SELECT w.new, s.new FROM other.WordSentence ws
+JOIN WordMap w ON w.old = ws.word_id
+JOIN SentenceMap s ON s.old = ws.sentence_id;
+
+INSERT OR IGNORE INTO Readings(word_id, reading)
+SELECT w.new, reading FROM other.Readings r JOIN WordMap w ON w.old = r.word_id;
+
+INSERT OR IGNORE INTO PartsOfSpeech(word_id, pos)
+SELECT w.new, pos FROM other.PartsOfSpeech p JOIN WordMap w ON w.old = p.word_id;
+
+INSERT OR IGNORE INTO Metadata(sentence_id, key, value)
+SELECT s.new, key, value FROM other.Metadata m JOIN SentenceMap s ON s.old = m.sentence_id;
+
+INSERT INTO Translations(sentence_id, translation)
+SELECT DISTINCT s.new, t.translation FROM other.Translations t
+JOIN SentenceMap s ON s.old = t.sentence_id
+WHERE NOT EXISTS (
+    SELECT 1 FROM main.Translations k WHERE k.sentence_id = s.new AND k.translation = t.translation
+);
+
+INSERT OR IGNORE INTO Tokens(sentence_id, position, start, surface, lemma, pos)
+SELECT s.new, position, start, surface, lemma, pos FROM other.Tokens t
+JOIN SentenceMap s ON s.old = t.sentence_id;
+
+INSERT OR IGNORE INTO Tags(name) SELECT name FROM other.Tags;
+
+INSERT OR IGNORE INTO SentenceTags(sentence_id, tag_id)
+SELECT s.new, mine.id FROM other.SentenceTags st
+JOIN SentenceMap s ON s.old = st.sentence_id
+JOIN other.Tags theirs ON theirs.id = st.tag_id
+JOIN main.Tags mine ON mine.name = theirs.name;
+
+INSERT OR IGNORE INTO WordAliases(alias, canonical)
+SELECT alias, canonical FROM other.WordAliases;
+
+DROP TABLE temp.SentenceMap;
+DROP TABLE temp.WordMap;
\ No newline at end of file
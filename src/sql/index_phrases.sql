@@ -0,0 +1 @@
+INSERT INTO SentenceText(SentenceText) VALUES('rebuild');
\ No newline at end of file
@@ -0,0 +1 @@
+UPDATE WordAliases SET canonical=?2 WHERE canonical=?1;
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT "table", parent, COUNT(*) FROM pragma_foreign_key_check
+GROUP BY "table", parent
+ORDER BY "table", parent;
\ No newline at end of file
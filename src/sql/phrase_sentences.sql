@@ -0,0 +1,8 @@
+SELECT rowid, sentence FROM SentenceText
+WHERE sentence LIKE ?1 ESCAPE '\'
+AND instr(sentence, ?4) > 0
+AND (?3 IS NULL OR rowid IN (
+    SELECT sentence_id FROM SentenceTags JOIN Tags ON Tags.id = tag_id WHERE name = ?3
+))
+ORDER BY length(sentence)
+LIMIT ?2;
\ No newline at end of file
@@ -0,0 +1,5 @@
+DELETE FROM Readings WHERE word_id IN (SELECT id FROM temp.Pruned);
+DELETE FROM PartsOfSpeech WHERE word_id IN (SELECT id FROM temp.Pruned);
+DELETE FROM Words WHERE id IN (SELECT id FROM temp.Pruned);
+
+DROP TABLE temp.Pruned;
\ No newline at end of file
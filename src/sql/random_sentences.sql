@@ -0,0 +1,21 @@
+WITH Spellings(word) AS (VALUES (?1), (?2), (?3)),
+Canonical(word) AS (
+    SELECT COALESCE((SELECT canonical FROM WordAliases WHERE alias = Spellings.word), word)
+    FROM Spellings
+),
+Lookup(word) AS (
+    SELECT word FROM Canonical
+    UNION SELECT alias FROM WordAliases WHERE canonical IN (SELECT word FROM Canonical)
+)
+SELECT id, sentence FROM Sentences
+WHERE (?1 IS NULL OR id IN (
+    SELECT sentence_id FROM WordSentence JOIN Words ON Words.id = word_id
+    WHERE word IN (SELECT word FROM Lookup)
+))
+AND (?4 IS NULL OR id IN (
+    SELECT sentence_id FROM SentenceTags JOIN Tags ON Tags.id = tag_id WHERE name = ?4
+))
+AND (?5 IS NULL OR length(sentence) >= ?5)
+AND (?6 IS NULL OR length(sentence) <= ?6)
+ORDER BY random()
+LIMIT ?7;
\ No newline at end of file
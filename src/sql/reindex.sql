@@ -0,0 +1 @@
+REINDEX;
\ No newline at end of file
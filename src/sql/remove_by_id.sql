@@ -0,0 +1,2 @@
+INSERT OR IGNORE INTO temp.Removed(id)
+SELECT id FROM Sentences WHERE id = ?1;
\ No newline at end of file
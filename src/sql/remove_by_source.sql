@@ -0,0 +1,2 @@
+INSERT OR IGNORE INTO temp.Removed(id)
+SELECT sentence_id FROM Metadata WHERE key = 'source' AND value = ?1;
\ No newline at end of file
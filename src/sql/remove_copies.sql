@@ -0,0 +1,24 @@
+INSERT OR IGNORE INTO WordSentence(word_id, sentence_id)
+SELECT word_id, kept FROM WordSentence JOIN Copies ON sentence_id = copy;
+
+INSERT OR IGNORE INTO Metadata(sentence_id, key, value)
+SELECT kept, key, value FROM Metadata JOIN Copies ON sentence_id = copy;
+
+INSERT INTO Translations(sentence_id, translation)
+SELECT DISTINCT kept, t.translation FROM Translations t JOIN Copies ON t.sentence_id = copy
+WHERE NOT EXISTS (
+    SELECT 1 FROM Translations k WHERE k.sentence_id = kept AND k.translation = t.translation
+);
+
+INSERT OR IGNORE INTO SentenceTags(sentence_id, tag_id)
+SELECT kept, tag_id FROM SentenceTags JOIN Copies ON sentence_id = copy;
+
+DELETE FROM WordSentence WHERE sentence_id IN (SELECT copy FROM Copies);
+DELETE FROM Metadata WHERE sentence_id IN (SELECT copy FROM Copies);
+DELETE FROM Translations WHERE sentence_id IN (SELECT copy FROM Copies);
+DELETE FROM SentenceTags WHERE sentence_id IN (SELECT copy FROM Copies);
+DELETE FROM Tokens WHERE sentence_id IN (SELECT copy FROM Copies);
+DELETE FROM Sentences WHERE id IN (SELECT copy FROM Copies);
+
+DROP TABLE temp.Copies;
+DROP INDEX IF EXISTS SentenceLookup;
\ No newline at end of file
@@ -0,0 +1,8 @@
+DELETE FROM WordSentence WHERE sentence_id IN (SELECT id FROM temp.Removed);
+DELETE FROM Metadata WHERE sentence_id IN (SELECT id FROM temp.Removed);
+DELETE FROM Translations WHERE sentence_id IN (SELECT id FROM temp.Removed);
+DELETE FROM SentenceTags WHERE sentence_id IN (SELECT id FROM temp.Removed);
+DELETE FROM Tokens WHERE sentence_id IN (SELECT id FROM temp.Removed);
+DELETE FROM Sentences WHERE id IN (SELECT id FROM temp.Removed);
+
+DROP TABLE temp.Removed;
\ No newline at end of file
@@ -0,0 +1,2 @@
+DELETE FROM SentenceTags
+WHERE sentence_id = ?1 AND tag_id = (SELECT id FROM Tags WHERE name = ?2);
\ No newline at end of file
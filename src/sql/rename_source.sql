@@ -0,0 +1 @@
+UPDATE Metadata SET value = ?2 WHERE key = 'source' AND value = ?1;
\ No newline at end of file
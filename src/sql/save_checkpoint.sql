@@ -0,0 +1 @@
+INSERT OR REPLACE INTO Checkpoints(path, offset) VALUES(?1, ?2);
\ No newline at end of file
@@ -0,0 +1 @@
+INSERT INTO SchemaVersion(version, applied_at) VALUES(?1, datetime('now'));
\ No newline at end of file
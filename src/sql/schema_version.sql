@@ -0,0 +1 @@
+SELECT COALESCE(MAX(version), 0) FROM SchemaVersion;
\ No newline at end of file
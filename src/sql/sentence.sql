@@ -0,0 +1 @@
+SELECT sentence FROM Sentences WHERE id = ?1;
\ No newline at end of file
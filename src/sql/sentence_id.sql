@@ -0,0 +1 @@
+SELECT id FROM Sentences WHERE sentence = ?1;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT value FROM Metadata WHERE sentence_id = ?1 AND key = ?2;
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT t.sentence_id, t.surface, EXISTS(
+    SELECT 1 FROM WordSentence ws JOIN Words w ON w.id = ws.word_id
+    WHERE ws.sentence_id = t.sentence_id AND w.word = t.lemma
+)
+FROM Tokens t
+ORDER BY t.sentence_id, t.position;
\ No newline at end of file
@@ -0,0 +1,4 @@
+SELECT name FROM Tags
+JOIN SentenceTags ON Tags.id = tag_id
+WHERE sentence_id = ?1
+ORDER BY name;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT start, surface FROM Tokens WHERE sentence_id=?1 ORDER BY position;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT translation FROM Translations WHERE sentence_id=?1;
\ No newline at end of file
@@ -0,0 +1,4 @@
+SELECT Words.id, word FROM Words
+JOIN WordSentence ON Words.id = word_id
+WHERE sentence_id = ?1
+ORDER BY Words.id;
\ No newline at end of file
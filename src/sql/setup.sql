@@ -1,17 +1,80 @@
-CREATE TABLE Words(
+CREATE TABLE IF NOT EXISTS Words(
     id INTEGER PRIMARY KEY,
-    word TEXT UNIQUE NOT NULL
+    word TEXT UNIQUE NOT NULL,
+    created_at TEXT
 );
 
-CREATE TABLE Sentences(
+CREATE TABLE IF NOT EXISTS Sentences(
     id INTEGER PRIMARY KEY,
-    sentence TEXT NOT NULL
+    sentence TEXT NOT NULL,
+    created_at TEXT
 );
 
-CREATE TABLE WordSentence(
+CREATE TABLE IF NOT EXISTS WordSentence(
     word_id INTEGER NOT NULL,
     sentence_id INTEGER NOT NULL,
     PRIMARY KEY(word_id, sentence_id),
     FOREIGN KEY(word_id) REFERENCES Words(id),
     FOREIGN KEY(sentence_id) REFERENCES Sentences(id)
+);
+
+CREATE TABLE IF NOT EXISTS Metadata(
+    sentence_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY(sentence_id, key),
+    FOREIGN KEY(sentence_id) REFERENCES Sentences(id)
+);
+
+CREATE TABLE IF NOT EXISTS Translations(
+    sentence_id INTEGER NOT NULL,
+    translation TEXT NOT NULL,
+    FOREIGN KEY(sentence_id) REFERENCES Sentences(id)
+);
+
+CREATE TABLE IF NOT EXISTS Checkpoints(
+    path TEXT PRIMARY KEY,
+    offset INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS Readings(
+    word_id INTEGER NOT NULL,
+    reading TEXT NOT NULL,
+    PRIMARY KEY(word_id, reading),
+    FOREIGN KEY(word_id) REFERENCES Words(id)
+);
+
+CREATE TABLE IF NOT EXISTS PartsOfSpeech(
+    word_id INTEGER NOT NULL,
+    pos TEXT NOT NULL,
+    PRIMARY KEY(word_id, pos),
+    FOREIGN KEY(word_id) REFERENCES Words(id)
+);
+
+CREATE TABLE IF NOT EXISTS Tokens(
+    sentence_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    start INTEGER,
+    surface TEXT NOT NULL,
+    lemma TEXT NOT NULL,
+    PRIMARY KEY(sentence_id, position),
+    FOREIGN KEY(sentence_id) REFERENCES Sentences(id)
+);
+
+CREATE TABLE IF NOT EXISTS WordAliases(
+    alias TEXT PRIMARY KEY,
+    canonical TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS Tags(
+    id INTEGER PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS SentenceTags(
+    sentence_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY(sentence_id, tag_id),
+    FOREIGN KEY(sentence_id) REFERENCES Sentences(id),
+    FOREIGN KEY(tag_id) REFERENCES Tags(id)
 );
\ No newline at end of file
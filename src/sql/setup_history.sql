@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS History(
+    id INTEGER PRIMARY KEY,
+    added_at TEXT NOT NULL,
+    source TEXT NOT NULL,
+    sentences INTEGER NOT NULL,
+    duplicates INTEGER NOT NULL,
+    errors INTEGER NOT NULL,
+    seconds REAL NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1,19 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS SentenceText USING fts5(
+    sentence,
+    content='Sentences',
+    content_rowid='id',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS SentenceTextInsert AFTER INSERT ON Sentences BEGIN
+    INSERT INTO SentenceText(rowid, sentence) VALUES(new.id, new.sentence);
+END;
+
+CREATE TRIGGER IF NOT EXISTS SentenceTextDelete AFTER DELETE ON Sentences BEGIN
+    INSERT INTO SentenceText(SentenceText, rowid, sentence) VALUES('delete', old.id, old.sentence);
+END;
+
+CREATE TRIGGER IF NOT EXISTS SentenceTextUpdate AFTER UPDATE ON Sentences BEGIN
+    INSERT INTO SentenceText(SentenceText, rowid, sentence) VALUES('delete', old.id, old.sentence);
+    INSERT INTO SentenceText(rowid, sentence) VALUES(new.id, new.sentence);
+END;
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE TEMP TABLE IF NOT EXISTS Removed(id INTEGER PRIMARY KEY);
\ No newline at end of file
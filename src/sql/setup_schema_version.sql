@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS SchemaVersion(
+    version INTEGER PRIMARY KEY,
+    applied_at TEXT NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS SentenceLookup ON Sentences(sentence);
\ No newline at end of file
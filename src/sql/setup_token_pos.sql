@@ -0,0 +1 @@
+ALTER TABLE Tokens ADD COLUMN pos TEXT;
\ No newline at end of file
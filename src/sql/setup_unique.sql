@@ -0,0 +1 @@
+CREATE UNIQUE INDEX IF NOT EXISTS UniqueSentences ON Sentences(sentence);
\ No newline at end of file
@@ -0,0 +1,7 @@
+SELECT m.value, COUNT(DISTINCT m.sentence_id), COUNT(DISTINCT ws.word_id), substr(MIN(s.created_at), 1, 10)
+FROM Metadata m
+JOIN Sentences s ON s.id = m.sentence_id
+LEFT JOIN WordSentence ws ON ws.sentence_id = m.sentence_id
+WHERE m.key = 'source'
+GROUP BY m.value
+ORDER BY MIN(s.created_at), m.value;
\ No newline at end of file
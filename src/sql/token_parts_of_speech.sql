@@ -0,0 +1,11 @@
+WITH Spellings(word) AS (VALUES (?1), (?2), (?3)),
+Canonical(word) AS (
+    SELECT COALESCE((SELECT canonical FROM WordAliases WHERE alias = Spellings.word), word)
+    FROM Spellings
+),
+Lookup(word) AS (
+    SELECT word FROM Canonical
+    UNION SELECT alias FROM WordAliases WHERE canonical IN (SELECT word FROM Canonical)
+)
+SELECT DISTINCT pos FROM Tokens
+WHERE sentence_id = ?4 AND lemma IN (SELECT word FROM Lookup) AND pos IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+SELECT reading FROM Readings WHERE word_id = ?1 ORDER BY reading;
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT DISTINCT p.pos FROM PartsOfSpeech p
+JOIN Words w ON w.id = p.word_id
+WHERE w.word IN (?1, ?2, ?3);
\ No newline at end of file
@@ -0,0 +1,12 @@
+WITH Spellings(word) AS (VALUES (?1), (?2), (?3)),
+Canonical(word) AS (
+    SELECT COALESCE((SELECT canonical FROM WordAliases WHERE alias = Spellings.word), word)
+    FROM Spellings
+),
+Lookup(word) AS (
+    SELECT word FROM Canonical
+    UNION SELECT alias FROM WordAliases WHERE canonical IN (SELECT word FROM Canonical)
+)
+SELECT position FROM Tokens
+WHERE sentence_id = ?4 AND lemma IN (SELECT word FROM Lookup)
+ORDER BY position;
\ No newline at end of file
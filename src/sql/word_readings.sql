@@ -0,0 +1,4 @@
+SELECT DISTINCT reading FROM Readings
+JOIN Words ON Words.id = Readings.word_id
+WHERE word IN (?1, ?2, ?3)
+ORDER BY reading;
\ No newline at end of file
@@ -0,0 +1,12 @@
+WITH Spellings(word) AS (VALUES (?1), (?2), (?3)),
+Canonical(word) AS (
+    SELECT COALESCE((SELECT canonical FROM WordAliases WHERE alias = Spellings.word), word)
+    FROM Spellings
+),
+Lookup(word) AS (
+    SELECT word FROM Canonical
+    UNION SELECT alias FROM WordAliases WHERE canonical IN (SELECT word FROM Canonical)
+)
+SELECT DISTINCT sentence_id FROM WordSentence
+JOIN Words ON Words.id = WordSentence.word_id
+WHERE word IN (SELECT word FROM Lookup);
\ No newline at end of file
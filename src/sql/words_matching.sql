@@ -0,0 +1,3 @@
+SELECT word FROM Words
+WHERE word GLOB ?1
+ORDER BY word;
\ No newline at end of file
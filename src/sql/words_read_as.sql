@@ -0,0 +1,4 @@
+SELECT DISTINCT word FROM Words
+JOIN Readings ON Readings.word_id = Words.id
+WHERE reading IN (?1, ?2, ?3)
+ORDER BY word;
\ No newline at end of file
@@ -0,0 +1,136 @@
+//! Where sentences are kept, so that searching doesn't depend on which database holds them.
+//!
+//! Databases are given as a path to a SQLite file, or as a URL saying which kind of database
+//! they are, like sqlite:///data/bank.db or postgres://school.example/bank. SQLite is the only
+//! backend built in so far; a PostgreSQL one, for a bank shared by a whole class, would
+//! implement `SentenceStore` too, turning its own errors into `Error`.
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Why a database couldn't be searched.
+#[derive(Debug)]
+pub enum Error {
+    /// Something went wrong in a SQLite database.
+    Sqlite(rusqlite::Error),
+    /// The database is of a kind ginkou has no backend for, like PostgreSQL.
+    Unsupported { backend: &'static str, url: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Sqlite(e) => write!(f, "{}", e),
+            Error::Unsupported { backend, url } => write!(
+                f,
+                "{} is a {} database, which ginkou can't use yet",
+                url, backend
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything searching needs from a database, whichever kind it is.
+pub trait SentenceStore {
+    /// The ways a word can be read, like キョウ and コンニチ for 今日.
+    fn readings(&self, word: &str) -> Result<Vec<String>>;
+    /// Whether a word is indexed, written some way, or as an alias.
+    fn has_word(&self, word: &str) -> Result<bool>;
+    /// The words read some way, like 橋, 箸, and 端 for はし.
+    fn words_read_as(&self, reading: &str) -> Result<Vec<String>>;
+    /// The words matching a pattern, in which * stands for any characters, as in 見* for 見る and 見せる.
+    fn words_matching(&self, pattern: &str) -> Result<Vec<String>>;
+    /// The sentences containing a word, only among those with a tag if one is given.
+    ///
+    /// With a limit, the shortest sentences are found, and otherwise every one, in the order they were added.
+    fn sentences_with_word(
+        &self,
+        word: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> Result<Vec<(u32, String)>>;
+    /// The sentences whose text contains a phrase.
+    fn sentences_with_phrase(
+        &self,
+        phrase: &str,
+        limit: Option<usize>,
+        tag: Option<&str>,
+    ) -> Result<Vec<(u32, String)>>;
+    /// The ids of every sentence containing a word.
+    fn ids_with_word(&self, word: &str) -> Result<HashSet<u32>>;
+    /// Where a word is among the tokens of a sentence, counting from 0.
+    fn positions(&self, word: &str, id: u32) -> Result<Vec<usize>>;
+    /// The parts of speech a word is used as in a sentence, as the tokenizer named them.
+    fn parts_of_speech(&self, word: &str, id: u32) -> Result<Vec<String>>;
+    /// Where a sentence came from, if that's known.
+    fn source(&self, id: u32) -> Result<Option<String>>;
+    fn translations(&self, id: u32) -> Result<Vec<String>>;
+}
+
+/// The kind of database a location names, along with where it is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Location {
+    Sqlite(PathBuf),
+    /// A PostgreSQL server, with the URL connecting to it.
+    Postgres(String),
+}
+
+impl Location {
+    /// Anything that isn't a URL is the path to a SQLite file, so paths keep working as they always have.
+    pub fn parse(location: &Path) -> Location {
+        let text = location.to_string_lossy();
+        if let Some(path) = text.strip_prefix("sqlite://") {
+            return Location::Sqlite(PathBuf::from(path));
+        }
+        if text.starts_with("postgres://") || text.starts_with("postgresql://") {
+            return Location::Postgres(text.into_owned());
+        }
+        Location::Sqlite(location.to_path_buf())
+    }
+}
+
+/// The error for a PostgreSQL database, which ginkou has no backend for.
+pub fn unsupported(url: &str) -> Error {
+    Error::Unsupported {
+        backend: "PostgreSQL",
+        url: url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_say_which_database_they_are() {
+        let sqlite = |path: &str| Location::Sqlite(PathBuf::from(path));
+        assert_eq!(
+            sqlite("/data/bank.db"),
+            Location::parse(Path::new("/data/bank.db"))
+        );
+        assert_eq!(
+            sqlite("/data/bank.db"),
+            Location::parse(Path::new("sqlite:///data/bank.db"))
+        );
+        assert_eq!(sqlite(":memory:"), Location::parse(Path::new(":memory:")));
+        let url = "postgresql://school.example/bank";
+        assert_eq!(
+            Location::Postgres(url.into()),
+            Location::parse(Path::new(url))
+        );
+        assert_eq!(
+            "postgresql://school.example/bank is a PostgreSQL database, which ginkou can't use yet",
+            unsupported(url).to_string()
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Suggesting the words meant by ones that aren't indexed, like 雰囲気 for the typo 雰意気.
+//!
+//! Most typos come from picking the wrong conversion of a kanji, so the word meant usually
+//! shares the rest of its kanji with the one typed, and is only a character or so away from it.
+use crate::normalize;
+
+/// How many words are suggested at most.
+const SUGGESTIONS: usize = 3;
+
+/// How many characters have to be changed, added, or removed to make one word into the other.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &y) in b.iter().enumerate() {
+            let changed = previous[j] + if x == y { 0 } else { 1 };
+            current.push(changed.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The characters the words meant by a word have to share with it, which are its kanji if it has any.
+pub fn shared(word: &str) -> Vec<char> {
+    let mut kanji: Vec<char> = word.chars().filter(|&c| normalize::is_kanji(c)).collect();
+    if kanji.is_empty() {
+        kanji = word.chars().collect();
+    }
+    kanji.dedup();
+    kanji
+}
+
+/// The words among some candidates which are closest to a word, the closest first.
+///
+/// Longer words can be further off, by about one character in three.
+pub fn suggestions(word: &str, candidates: &[String]) -> Vec<String> {
+    let furthest = word.chars().count().div_ceil(3).max(1);
+    let mut close: Vec<(usize, &String)> = candidates
+        .iter()
+        .filter(|c| c.as_str() != word)
+        .map(|c| (distance(word, c), c))
+        .filter(|&(d, _)| d <= furthest)
+        .collect();
+    close.sort();
+    close.dedup_by(|a, b| a.1 == b.1);
+    close
+        .into_iter()
+        .take(SUGGESTIONS)
+        .map(|(_, c)| c.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_closest_words_are_suggested() {
+        assert_eq!(1, distance("雰意気", "雰囲気"));
+        assert_eq!(2, distance("見る", "見せる見"));
+        assert_eq!(3, distance("", "猫舌だ"));
+        assert_eq!(vec!['猫', '舌'], shared("猫舌だ"));
+        assert_eq!(vec!['ね', 'こ'], shared("ねこ"));
+        let candidates: Vec<String> = ["天気", "雰囲気", "気分", "雰囲気"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(vec!["雰囲気"], suggestions("雰意気", &candidates));
+        assert_eq!(vec!["天気"], suggestions("元気", &candidates));
+        assert!(suggestions("勉強", &candidates).is_empty());
+    }
+}
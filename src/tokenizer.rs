@@ -0,0 +1,799 @@
+//! Finding the words in a sentence, with one of the tokenizers ginkou can use.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mecab::Tagger;
+
+use crate::external::{self, Pipe};
+use crate::normalize::to_katakana;
+
+/// The programs sentences can be split into words with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    /// MeCab, through its library, using the system dictionary.
+    #[default]
+    Mecab,
+    /// The `sudachipy` program, which can keep compound words together.
+    Sudachi,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mecab" => Ok(Backend::Mecab),
+            "sudachi" | "sudachipy" => Ok(Backend::Sudachi),
+            _ => Err(format!("unknown tokenizer: {}", s)),
+        }
+    }
+}
+
+/// How finely Sudachi splits sentences, from the shortest words in A to whole compounds in C.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SplitMode {
+    A,
+    B,
+    #[default]
+    C,
+}
+
+impl FromStr for SplitMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "a" | "A" => Ok(SplitMode::A),
+            "b" | "B" => Ok(SplitMode::B),
+            "c" | "C" => Ok(SplitMode::C),
+            _ => Err(format!("unknown split mode: {}", s)),
+        }
+    }
+}
+
+/// Settings for how sentences are split into words.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub backend: Backend,
+    /// The split mode, for tokenizers which have one.
+    pub split_mode: SplitMode,
+    /// The dictionary to use, for tokenizers which can be given one.
+    pub dictionary: Option<PathBuf>,
+    /// A dictionary of extra words, like names, added to the tokenizer's own.
+    pub user_dictionary: Option<PathBuf>,
+    /// How many of the best ways of splitting each sentence to find words in, for MeCab.
+    pub nbest: usize,
+}
+
+/// How many features a word has at most with IPADIC, beyond which it's from UniDic.
+const UNIDIC_FEATURES: usize = 9;
+
+/// A word found in a sentence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    /// The word as it was written in the sentence, like 見 for 見た.
+    pub surface: String,
+    /// The dictionary form of the word, like 見る for 見た.
+    pub lemma: String,
+    /// How the dictionary form is read, in katakana, if the tokenizer knows.
+    pub reading: Option<String>,
+    /// The part of speech, like 名詞 for nouns, if the tokenizer knows.
+    pub pos: Option<String>,
+    /// Where the word starts in the sentence, counted in characters, once it's been found.
+    pub start: Option<usize>,
+    /// Whether the word wasn't in the tokenizer's dictionary, so it had to guess at it.
+    pub unknown: bool,
+}
+
+/// The parts of speech of particles, auxiliary verbs, and punctuation, as IPADIC and UniDic name them.
+const FUNCTION_WORDS: [&str; 4] = ["助詞", "助動詞", "記号", "補助記号"];
+
+/// The parts of speech given in English, along with what IPADIC and UniDic name them.
+const PARTS_OF_SPEECH: [(&str, &[&str]); 14] = [
+    ("noun", &["名詞"]),
+    ("pronoun", &["代名詞"]),
+    ("verb", &["動詞"]),
+    ("adjective", &["形容詞"]),
+    ("na-adjective", &["形状詞", "形容動詞"]),
+    ("adverb", &["副詞"]),
+    ("adnominal", &["連体詞"]),
+    ("conjunction", &["接続詞"]),
+    ("interjection", &["感動詞"]),
+    ("particle", &["助詞"]),
+    ("auxiliary", &["助動詞"]),
+    ("prefix", &["接頭詞", "接頭辞"]),
+    ("suffix", &["接尾辞"]),
+    ("symbol", &["記号", "補助記号"]),
+];
+
+/// The names a tokenizer could give some part of speech, named in English or in Japanese.
+///
+/// Either way, every name the same part of speech has is found, so 接頭詞 also finds 接頭辞.
+pub fn part_of_speech(name: &str) -> Option<&'static [&'static str]> {
+    let english = name.to_lowercase();
+    PARTS_OF_SPEECH
+        .iter()
+        .find(|(e, names)| *e == english || names.contains(&name))
+        .map(|&(_, names)| names)
+}
+
+impl Token {
+    /// Check whether this is a particle, like は, an auxiliary verb, like ます, or punctuation.
+    ///
+    /// These are in almost every sentence, so they aren't useful to look up.
+    pub fn is_function_word(&self) -> bool {
+        self.pos
+            .as_ref()
+            .is_some_and(|p| FUNCTION_WORDS.contains(&p.as_str()))
+    }
+}
+
+/// Splits sentences into words.
+pub trait Tokenizer {
+    /// Find each word in a sentence.
+    fn tokens(&mut self, sentence: &str) -> io::Result<Vec<Token>>;
+
+    /// Take the words from other ways the last sentence could be split into words,
+    /// which weren't found in the best one.
+    fn alternatives(&mut self) -> Vec<Token> {
+        Vec::new()
+    }
+
+    /// How many words so far were skipped, since what the tokenizer printed couldn't be read.
+    fn malformed(&self) -> usize {
+        0
+    }
+}
+
+/// Find where each word starts in the sentence it was found in.
+///
+/// Tokenizers leave out spaces, so each word is looked for after the one before it.
+/// Words which can't be found, if a tokenizer changed them, are left without a start.
+pub fn locate(sentence: &str, tokens: &mut [Token]) {
+    let mut rest = sentence;
+    let mut chars = 0;
+    for token in tokens {
+        if let Some(i) = rest.find(token.surface.as_str()) {
+            chars += rest[..i].chars().count();
+            token.start = Some(chars);
+            chars += token.surface.chars().count();
+            rest = &rest[i + token.surface.len()..];
+        }
+    }
+}
+
+/// What a tokenizer printed for a sentence, read as words.
+#[derive(Debug, Default, PartialEq)]
+struct Output {
+    tokens: Vec<Token>,
+    /// The lines which couldn't be read as a word.
+    malformed: Vec<String>,
+}
+
+impl Output {
+    // Odd output for one word shouldn't stop the rest of the sentence from being added
+    fn skip_malformed(self, count: &mut usize) -> Vec<Token> {
+        for line in &self.malformed {
+            println!("Skipped a word the tokenizer printed as: {}", line);
+        }
+        *count += self.malformed.len();
+        self.tokens
+    }
+}
+
+/// A file made for a tokenizer to read, like a compiled user dictionary, removed once it's done with.
+struct TempFile(PathBuf);
+
+/// How many temporary files have been named, so that no two get the same name.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+impl TempFile {
+    /// Name a new file in the temporary directory, like ginkou-user-123-0.dic, without making it yet.
+    fn new(name: &str, extension: &str) -> Self {
+        let n = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+        let file = format!("ginkou-{}-{}-{}.{}", name, process::id(), n, extension);
+        TempFile(env::temp_dir().join(file))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// MeCab, through its library.
+///
+/// Starting MeCab loads its whole dictionary, so the same tagger is used for every sentence.
+pub struct Mecab {
+    tagger: Tagger,
+    /// How many of the best ways of splitting a sentence to find words in.
+    nbest: usize,
+    alternatives: Vec<Token>,
+    malformed: usize,
+    /// The files made for MeCab to read, kept until the tagger is dropped before them.
+    files: Vec<TempFile>,
+}
+
+impl Mecab {
+    /// Start MeCab with some arguments, like the dictionary to use.
+    pub fn new(args: &str) -> Self {
+        Mecab {
+            tagger: Tagger::new(args),
+            nbest: 1,
+            alternatives: Vec::new(),
+            malformed: 0,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Default for Mecab {
+    fn default() -> Self {
+        Mecab::new("")
+    }
+}
+
+// The words of the other ways of splitting a sentence which aren't in the best one
+fn new_words(best: &[Token], others: Vec<Vec<Token>>) -> Vec<Token> {
+    let mut words: Vec<Token> = Vec::new();
+    for token in others.into_iter().flatten() {
+        let known = |t: &Token| t.lemma == token.lemma;
+        if !best.iter().any(known) && !words.iter().any(known) {
+            words.push(token);
+        }
+    }
+    words
+}
+
+impl Tokenizer for Mecab {
+    fn tokens(&mut self, sentence: &str) -> io::Result<Vec<Token>> {
+        self.tagger.parse_nbest_init(sentence);
+        let output = self.tagger.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "MeCab couldn't split the sentence",
+            )
+        })?;
+        let best = mecab_tokens(&output).skip_malformed(&mut self.malformed);
+        let mut others = Vec::new();
+        for _ in 1..self.nbest {
+            match self.tagger.next() {
+                None => break,
+                Some(output) => others.push(mecab_tokens(&output).tokens),
+            }
+        }
+        self.alternatives = new_words(&best, others);
+        Ok(best)
+    }
+
+    fn alternatives(&mut self) -> Vec<Token> {
+        std::mem::take(&mut self.alternatives)
+    }
+
+    fn malformed(&self) -> usize {
+        self.malformed
+    }
+}
+
+/// A tokenizer run as an external program, which is kept running between sentences.
+pub struct Program {
+    pipe: Pipe,
+    /// Reads the words out of what the program prints for a sentence.
+    read: fn(&str) -> Output,
+    malformed: usize,
+    /// The files made for the program to read, like its config.
+    files: Vec<TempFile>,
+}
+
+impl Tokenizer for Program {
+    fn tokens(&mut self, sentence: &str) -> io::Result<Vec<Token>> {
+        let output = (self.read)(&self.pipe.ask(sentence)?);
+        Ok(output.skip_malformed(&mut self.malformed))
+    }
+
+    fn malformed(&self) -> usize {
+        self.malformed
+    }
+}
+
+fn program(name: &str, args: &[&str], read: fn(&str) -> Output) -> io::Result<Program> {
+    let pipe = external::pipe(name, args, "EOS")?;
+    Ok(Program {
+        pipe,
+        read,
+        malformed: 0,
+        files: Vec::new(),
+    })
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"))
+}
+
+fn mecab_config(flag: &str) -> io::Result<PathBuf> {
+    let out = external::filter("mecab-config", &[flag], Vec::new())?;
+    Ok(PathBuf::from(String::from_utf8_lossy(&out).trim()))
+}
+
+fn mecab_dictionary(dictionary: Option<&Path>) -> io::Result<PathBuf> {
+    match dictionary {
+        Some(d) => Ok(d.to_path_buf()),
+        None => Ok(mecab_config("--dicdir")?.join("ipadic")),
+    }
+}
+
+// MeCab can only use compiled user dictionaries, so the CSV of words is compiled with the
+// system dictionary, which its costs are estimated from, using mecab-dict-index
+fn compile_mecab_dictionary(csv: &Path, dictionary: Option<&Path>) -> io::Result<TempFile> {
+    let system = mecab_dictionary(dictionary)?;
+    let index = mecab_config("--libexecdir")?.join("mecab-dict-index");
+    let out = TempFile::new("user", "dic");
+    let args = [
+        "-d",
+        &system.to_string_lossy(),
+        "-u",
+        &out.0.to_string_lossy(),
+        "-f",
+        "utf-8",
+        "-t",
+        "utf-8",
+        &csv.to_string_lossy(),
+    ];
+    external::filter(&index.to_string_lossy(), &args, Vec::new())?;
+    Ok(out)
+}
+
+fn has_whitespace(path: &Path) -> bool {
+    path.to_string_lossy().chars().any(char::is_whitespace)
+}
+
+// MeCab splits its arguments on whitespace, without any way of quoting them, so paths with
+// spaces are given in a resource file instead, where each setting takes up the rest of its line,
+// and only the path of that file, in the temporary directory, is given with -r
+fn mecab_rc(dictionary: &Path, user: Option<&Path>) -> String {
+    let mut rc = format!("dicdir = {}\n", dictionary.display());
+    if let Some(user) = user {
+        rc.push_str(&format!("userdic = {}\n", user.display()));
+    }
+    rc
+}
+
+fn mecab_args(dictionary: Option<&Path>, user: Option<&Path>) -> String {
+    let mut args = Vec::new();
+    if let Some(d) = dictionary {
+        args.push(format!("-d {}", d.display()));
+    }
+    if let Some(user) = user {
+        args.push(format!("-u {}", user.display()));
+    }
+    args.join(" ")
+}
+
+// Sudachi's program only takes user dictionaries from a config file, the rest of which
+// is left as its default
+fn sudachi_config(user_dictionary: &Path) -> io::Result<TempFile> {
+    let config = TempFile::new("sudachi", "json");
+    let user_dictionary = fs::canonicalize(user_dictionary)?;
+    let quoted = user_dictionary
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    fs::write(&config.0, format!("{{\"userDict\": [\"{}\"]}}", quoted))?;
+    Ok(config)
+}
+
+/// A tokenizer knowing a handful of words, for testing without any tokenizer installed.
+///
+/// The longest known word is always taken, and anything else is split into single characters.
+#[cfg(test)]
+pub struct Mock;
+
+#[cfg(test)]
+impl Tokenizer for Mock {
+    fn tokens(&mut self, sentence: &str) -> io::Result<Vec<Token>> {
+        // The surface, part of speech, dictionary form, and its reading, of each word known
+        const WORDS: [(&str, &str, &str, &str); 8] = [
+            ("猫", "名詞", "猫", "ネコ"),
+            ("犬", "名詞", "犬", "イヌ"),
+            ("見る", "動詞", "見る", "ミル"),
+            ("見", "動詞", "見る", "ミル"),
+            ("を", "助詞", "を", "ヲ"),
+            ("が", "助詞", "が", "ガ"),
+            ("た", "助動詞", "た", "タ"),
+            ("。", "記号", "。", "。"),
+        ];
+        let mut tokens = Vec::new();
+        let mut rest = sentence;
+        while let Some(c) = rest.chars().next() {
+            let known = WORDS.iter().find(|w| rest.starts_with(w.0));
+            let token = match known {
+                Some(&(surface, pos, lemma, reading)) => Token {
+                    surface: surface.to_string(),
+                    lemma: lemma.to_string(),
+                    reading: Some(reading.to_string()),
+                    pos: Some(pos.to_string()),
+                    start: None,
+                    unknown: false,
+                },
+                None => Token {
+                    surface: c.to_string(),
+                    lemma: c.to_string(),
+                    reading: None,
+                    pos: None,
+                    start: None,
+                    unknown: true,
+                },
+            };
+            rest = &rest[token.surface.len()..];
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+}
+
+/// Start the tokenizer chosen, failing if the program it needs can't be run.
+pub fn start(options: &Options) -> io::Result<Box<dyn Tokenizer>> {
+    let user = options.user_dictionary.as_deref();
+    match options.backend {
+        Backend::Mecab => {
+            let system = options.dictionary.as_deref();
+            let compiled = match user {
+                Some(user) if is_csv(user) => Some(compile_mecab_dictionary(user, system)?),
+                _ => None,
+            };
+            let user = match compiled {
+                Some(ref c) => Some(c.0.clone()),
+                None => user.map(Path::to_path_buf),
+            };
+            let user = user.as_deref();
+            let spaced = system.into_iter().chain(user).any(has_whitespace);
+            let mut files: Vec<TempFile> = compiled.into_iter().collect();
+            let args = if spaced {
+                let rc = TempFile::new("mecab", "rc");
+                if has_whitespace(&rc.0) {
+                    let msg = format!("MeCab can't be given {}, which has spaces", rc.0.display());
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+                }
+                fs::write(&rc.0, mecab_rc(&mecab_dictionary(system)?, user))?;
+                let args = format!("-r {}", rc.0.display());
+                files.push(rc);
+                args
+            } else {
+                mecab_args(system, user)
+            };
+            let mut mecab = Mecab::new(&args);
+            mecab.nbest = options.nbest;
+            mecab.files = files;
+            Ok(Box::new(mecab))
+        }
+        Backend::Sudachi => {
+            let mode = match options.split_mode {
+                SplitMode::A => "A",
+                SplitMode::B => "B",
+                SplitMode::C => "C",
+            };
+            let config = user.map(sudachi_config).transpose()?;
+            let path = config.as_ref().map(|c| c.0.to_string_lossy());
+            let mut args = vec!["-m", mode, "-a"];
+            if let Some(ref path) = path {
+                args.extend(&["-r", path.as_ref()]);
+            }
+            let mut sudachi = program("sudachipy", &args, sudachi_tokens)?;
+            sudachi.files.extend(config);
+            Ok(Box::new(sudachi))
+        }
+    }
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30a1}'..='\u{30fa}' | 'ー')
+}
+
+// Tokenizers give the reading of a word as it was written, like ミ for the 見 of 見た,
+// which is turned into the reading of its dictionary form by swapping the kana it
+// was inflected with, when it only differs from it in kana.
+fn lemma_reading(surface: &str, lemma: &str, reading: &str) -> Option<String> {
+    if reading.is_empty() || !reading.chars().all(is_katakana) {
+        return None;
+    }
+    // The only verb with its kanji read differently once inflected
+    if lemma == "来る" {
+        return Some("クル".to_string());
+    }
+    let common = surface
+        .chars()
+        .zip(lemma.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let (written, original) = (
+        to_katakana(&surface[common..]),
+        to_katakana(&lemma[common..]),
+    );
+    let stem = reading.strip_suffix(written.as_str())?;
+    if !original.chars().all(is_katakana) {
+        return None;
+    }
+    Some(format!("{}{}", stem, original))
+}
+
+// Words missing from the dictionary are given without a dictionary form
+fn token(surface: &str, pos: Option<&str>, lemma: Option<&str>, reading: Option<&str>) -> Token {
+    let lemma = lemma.filter(|&l| !l.is_empty() && l != "*");
+    let unknown = lemma.is_none();
+    let lemma = lemma.unwrap_or(surface);
+    Token {
+        surface: surface.to_string(),
+        lemma: lemma.to_string(),
+        reading: reading.and_then(|r| lemma_reading(surface, lemma, r)),
+        pos: pos.map(String::from),
+        start: None,
+        unknown,
+    }
+}
+
+// MeCab prints a line for each word, with its features after a tab, starting with its
+// part of speech, unless it's an unknown word given without any. Its dictionary form
+// is the seventh with IPADIC or NEologd, followed by its reading, while UniDic, which has
+// many more, has the reading of the dictionary form just before it.
+// Unknown words might not have them.
+fn mecab_tokens(output: &str) -> Output {
+    let mut out = Output::default();
+    for line in output.lines().take_while(|&l| l != "EOS") {
+        let (surface, features) = match line.find('\t') {
+            Some(tab) if tab > 0 => (&line[..tab], &line[tab + 1..]),
+            _ => {
+                out.malformed.push(line.to_string());
+                continue;
+            }
+        };
+        let features: Vec<&str> = features.split(',').collect();
+        let pos = Some(features[0]).filter(|_| features.len() > 1);
+        let token = if features.len() > UNIDIC_FEATURES {
+            // UniDic spells out where loanwords come from, like ビール-beer
+            let lemma = features[7].split('-').next();
+            let mut token = token(surface, pos, lemma, None);
+            token.reading = Some(features[6].to_string()).filter(|r| r != "*");
+            token
+        } else {
+            let (lemma, reading) = (features.get(6).cloned(), features.get(7).cloned());
+            token(surface, pos, lemma, reading)
+        };
+        out.tokens.push(token);
+    }
+    out
+}
+
+// Sudachi prints the surface, parts of speech, normalized form, dictionary form,
+// reading, and dictionary id of each word, separated by tabs, where the id is -1
+// for words it doesn't know
+fn sudachi_tokens(output: &str) -> Output {
+    let mut out = Output::default();
+    for line in output.lines().filter(|l| !l.is_empty()) {
+        let columns: Vec<&str> = line.split('\t').collect();
+        if columns.len() < 4 || columns[0].is_empty() {
+            out.malformed.push(line.to_string());
+            continue;
+        }
+        let pos = columns[1].split(',').next();
+        let mut token = token(columns[0], pos, Some(columns[3]), columns.get(4).cloned());
+        token.unknown = columns.get(5) == Some(&"-1");
+        out.tokens.push(token);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lemmas(tokens: Vec<Token>) -> Vec<String> {
+        tokens.into_iter().map(|t| t.lemma).collect()
+    }
+
+    fn readings(tokens: Vec<Token>) -> Vec<Option<String>> {
+        tokens.into_iter().map(|t| t.reading).collect()
+    }
+
+    #[test]
+    fn lemmas_are_read_from_mecab_output() {
+        let output = "猫\t名詞,一般,*,*,*,*,猫,ネコ,ネコ\n\
+                      見\t動詞,自立,*,*,一段,連用形,見る,ミ,ミ\n\
+                      ジョジョ\tUNK\n\
+                      EOS\n";
+        assert_eq!(
+            vec!["猫", "見る", "ジョジョ"],
+            lemmas(mecab_tokens(output).tokens)
+        );
+        let expected = vec![Some("ネコ".to_string()), Some("ミル".to_string()), None];
+        assert_eq!(expected, readings(mecab_tokens(output).tokens));
+    }
+
+    #[test]
+    fn function_words_can_be_skipped() {
+        let output = "猫\t名詞,一般,*,*,*,*,猫,ネコ,ネコ\n\
+                      が\t助詞,格助詞,一般,*,*,*,が,ガ,ガ\n\
+                      見\t動詞,自立,*,*,一段,連用形,見る,ミ,ミ\n\
+                      た\t助動詞,*,*,*,特殊・タ,基本形,た,タ,タ\n\
+                      。\t記号,句点,*,*,*,*,。,。,。\n\
+                      EOS\n";
+        let tokens = mecab_tokens(output).tokens;
+        let pos: Vec<_> = tokens.iter().map(|t| t.pos.clone().unwrap()).collect();
+        assert_eq!(vec!["名詞", "助詞", "動詞", "助動詞", "記号"], pos);
+        let content: Vec<_> = tokens
+            .into_iter()
+            .filter(|t| !t.is_function_word())
+            .collect();
+        assert_eq!(vec!["猫", "見る"], lemmas(content));
+    }
+
+    #[test]
+    fn parts_of_speech_are_named_either_way() {
+        assert_eq!(Some(&["動詞"][..]), part_of_speech("verb"));
+        assert_eq!(Some(&["動詞"][..]), part_of_speech("Verb"));
+        assert_eq!(part_of_speech("prefix"), part_of_speech("接頭辞"));
+        assert_eq!(None, part_of_speech("verbs"));
+    }
+
+    #[test]
+    fn the_mock_takes_the_longest_words() {
+        let tokens = Mock.tokens("犬を見るX").unwrap();
+        let surfaces: Vec<_> = tokens.iter().map(|t| t.surface.as_str()).collect();
+        assert_eq!(vec!["犬", "を", "見る", "X"], surfaces);
+        assert_eq!(vec!["猫", "見る"], lemmas(Mock.tokens("猫見").unwrap()));
+    }
+
+    #[test]
+    fn malformed_words_are_skipped() {
+        let output = "猫\t名詞,一般,*,*,*,*,猫,ネコ,ネコ\n\
+                      \t名詞\n\
+                      壊れた\n\
+                      見\t動詞,自立,*,*,一段,連用形,見る,ミ,ミ\n\
+                      EOS\n";
+        let output = mecab_tokens(output);
+        assert_eq!(vec!["\t名詞", "壊れた"], output.malformed);
+        let mut count = 0;
+        assert_eq!(
+            vec!["猫", "見る"],
+            lemmas(output.skip_malformed(&mut count))
+        );
+        assert_eq!(2, count);
+        let output = sudachi_tokens("猫\t名詞\n猫\t名詞,普通名詞\t猫\t猫\tネコ\n");
+        assert_eq!((1, 1), (output.tokens.len(), output.malformed.len()));
+    }
+
+    #[test]
+    fn words_are_located() {
+        let mut tokens = Mock.tokens("猫を 見た X").unwrap();
+        tokens[3].surface = "?".to_string();
+        locate("猫を 見た X", &mut tokens);
+        let starts: Vec<_> = tokens.iter().map(|t| t.start).collect();
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), None, Some(4), Some(5), Some(6)],
+            starts
+        );
+    }
+
+    #[test]
+    fn alternatives_only_have_new_words() {
+        let best = Mock.tokens("猫を見た").unwrap();
+        let others = vec![
+            Mock.tokens("猫が見る").unwrap(),
+            Mock.tokens("犬を見た").unwrap(),
+        ];
+        assert_eq!(vec!["が", "犬"], lemmas(new_words(&best, others)));
+    }
+
+    #[test]
+    fn readings_of_dictionary_forms_are_found() {
+        let reading = |s, l, r| lemma_reading(s, l, r);
+        assert_eq!(Some("イク".to_string()), reading("行っ", "行く", "イッ"));
+        assert_eq!(
+            Some("タベル".to_string()),
+            reading("食べ", "食べる", "タベ")
+        );
+        assert_eq!(Some("スル".to_string()), reading("し", "する", "シ"));
+        assert_eq!(Some("クル".to_string()), reading("来", "来る", "キ"));
+        assert_eq!(None, reading("猫", "ネコ", "*"));
+    }
+
+    #[test]
+    fn lemmas_are_read_from_unidic_output() {
+        let output = "ビール\t名詞,普通名詞,一般,*,*,*,ビール,ビール-beer,ビール,ビール,ビール,ビール,外,*,*,*,*\n\
+                      見\t動詞,非自立可能,*,*,上一段-マ行,連用形-一般,ミル,見る,見,ミ,見る,ミル,和,*,*,*,*\n\
+                      EOS\n";
+        assert_eq!(vec!["ビール", "見る"], lemmas(mecab_tokens(output).tokens));
+        let expected = vec![Some("ビール".to_string()), Some("ミル".to_string())];
+        assert_eq!(expected, readings(mecab_tokens(output).tokens));
+    }
+
+    #[test]
+    fn lemmas_are_read_from_sudachi_output() {
+        let output = "関係者\t名詞,普通名詞,一般,*,*,*\t関係者\t関係者\tカンケイシャ\t0\t[]\n\
+                      見\t動詞,非自立可能,*,*,上一段-マ行,連用形-一般\t見る\t見る\tミ\t0\t[]\n";
+        assert_eq!(
+            vec!["関係者", "見る"],
+            lemmas(sudachi_tokens(output).tokens)
+        );
+        let expected = vec![Some("カンケイシャ".to_string()), Some("ミル".to_string())];
+        assert_eq!(expected, readings(sudachi_tokens(output).tokens));
+    }
+
+    #[test]
+    fn sudachi_is_given_user_dictionaries_in_its_config() {
+        let dictionary = env::temp_dir().join("ginkou-test-user.dic");
+        fs::write(&dictionary, "").unwrap();
+        let file = sudachi_config(&dictionary).unwrap();
+        let config = fs::read_to_string(&file.0).unwrap();
+        let expected = fs::canonicalize(&dictionary).unwrap();
+        let value = crate::json::parse(&config).unwrap();
+        let user = value.get("userDict").unwrap();
+        let path = crate::json::Value::String(expected.to_string_lossy().to_string());
+        assert_eq!(&crate::json::Value::Array(vec![path]), user);
+        assert!(!is_csv(&dictionary));
+        assert!(is_csv(Path::new("names.CSV")));
+        let path = file.0.clone();
+        drop(file);
+        assert!(!path.exists());
+        let _ = fs::remove_file(&dictionary);
+    }
+
+    // Shows how long each way takes with cargo test --release reusing_the_tagger -- --nocapture
+    #[test]
+    fn reusing_the_tagger_finds_the_same_tokens_faster() {
+        use std::time::Instant;
+        let sentences: Vec<String> = (0..200)
+            .map(|i| format!("{}匹の猫が公園で犬を見ていた。", i))
+            .collect();
+        let start = Instant::now();
+        let mut apart = Vec::new();
+        for sentence in &sentences {
+            let mut mecab = Mecab::default();
+            apart.push(mecab.tokens(sentence).unwrap());
+        }
+        let each = start.elapsed();
+        let start = Instant::now();
+        let mut mecab = Mecab::default();
+        let mut together = Vec::new();
+        for sentence in &sentences {
+            together.push(mecab.tokens(sentence).unwrap());
+        }
+        let shared = start.elapsed();
+        println!(
+            "{} sentences: {:?} with a tagger each, {:?} with one shared, {:.1}x faster",
+            sentences.len(),
+            each,
+            shared,
+            each.as_secs_f64() / shared.as_secs_f64()
+        );
+        assert_eq!(apart, together);
+    }
+
+    #[test]
+    fn dictionaries_with_spaces_are_given_in_a_resource_file() {
+        let (system, user) = (Path::new("/dic/ipadic"), Path::new("/home/me/names.dic"));
+        assert_eq!(
+            "-d /dic/ipadic -u /home/me/names.dic",
+            mecab_args(Some(system), Some(user))
+        );
+        assert_eq!("", mecab_args(None, None));
+        let spaced = Path::new("/home/My Name/names.dic");
+        assert!(has_whitespace(spaced));
+        assert!(!has_whitespace(user));
+        assert_eq!(
+            "dicdir = /dic/ipadic\nuserdic = /home/My Name/names.dic\n",
+            mecab_rc(system, Some(spaced))
+        );
+        assert_ne!(
+            TempFile::new("mecab", "rc").0,
+            TempFile::new("mecab", "rc").0
+        );
+    }
+}
@@ -0,0 +1,22 @@
+//! Downloading pages to read sentences from.
+//!
+//! Fetching is delegated to `curl`, which handles redirects, TLS, and proxies.
+use std::io;
+
+use crate::external;
+
+/// Download the contents of a URL.
+pub fn download(url: &str) -> io::Result<Vec<u8>> {
+    external::filter(
+        "curl",
+        &[
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--",
+            url,
+        ],
+        Vec::new(),
+    )
+}
@@ -0,0 +1,162 @@
+//! Just enough of the ZIP format to read the members of an archive.
+//!
+//! Only stored and deflated members are supported, which covers
+//! everything produced by ordinary EPUB tooling. ZIP64 archives, which
+//! are only needed past 4 GB or 65535 members, are refused.
+use std::io;
+
+use crate::inflate::inflate;
+
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn zip64() -> io::Error {
+    let msg = "ZIP64 archives, like those over 4 GB, aren't supported";
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn u16_at(data: &[u8], at: usize) -> io::Result<u16> {
+    match data.get(at..at + 2) {
+        None => Err(invalid("truncated zip archive")),
+        Some(b) => Ok(u16::from(b[0]) | u16::from(b[1]) << 8),
+    }
+}
+
+fn u32_at(data: &[u8], at: usize) -> io::Result<u32> {
+    let lo = u32::from(u16_at(data, at)?);
+    let hi = u32::from(u16_at(data, at + 2)?);
+    Ok(lo | hi << 16)
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    header_offset: usize,
+}
+
+/// A ZIP archive held in memory.
+pub struct Archive {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl Archive {
+    /// Read the central directory of an archive.
+    pub fn new(data: Vec<u8>) -> io::Result<Self> {
+        if data.len() < 22 {
+            return Err(invalid("file is too small to be a zip archive"));
+        }
+        let mut end = data.len() - 22;
+        while u32_at(&data, end)? != END_OF_DIRECTORY {
+            if end == 0 {
+                return Err(invalid("missing zip end of directory record"));
+            }
+            end -= 1;
+        }
+        // ZIP64 archives put a locator before the end of directory, whose
+        // fields for anything too big to fit are left at their maximum
+        if end >= 20 && u32_at(&data, end - 20)? == ZIP64_LOCATOR {
+            return Err(zip64());
+        }
+        let count = u16_at(&data, end + 10)?;
+        let at = u32_at(&data, end + 16)?;
+        if count == u16::MAX || at == u32::MAX {
+            return Err(zip64());
+        }
+        let (count, mut at) = (count as usize, at as usize);
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(&data, at)? != DIRECTORY_HEADER {
+                return Err(invalid("bad zip directory header"));
+            }
+            let name_len = u16_at(&data, at + 28)? as usize;
+            let extra_len = u16_at(&data, at + 30)? as usize;
+            let comment_len = u16_at(&data, at + 32)? as usize;
+            let name = data
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| invalid("truncated zip archive"))?;
+            let compressed_size = u32_at(&data, at + 20)?;
+            let header_offset = u32_at(&data, at + 42)?;
+            if compressed_size == u32::MAX || header_offset == u32::MAX {
+                return Err(zip64());
+            }
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(&data, at + 10)?,
+                compressed_size: compressed_size as usize,
+                header_offset: header_offset as usize,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    /// The names of every member, in archive order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// Decompress the member with a given name.
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = match self.entries.iter().find(|e| e.name == name) {
+            None => {
+                let msg = format!("no member named {} in zip archive", name);
+                return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+            }
+            Some(e) => e,
+        };
+        let at = entry.header_offset;
+        if u32_at(&self.data, at)? != LOCAL_HEADER {
+            return Err(invalid("bad zip local header"));
+        }
+        let name_len = u16_at(&self.data, at + 26)? as usize;
+        let extra_len = u16_at(&self.data, at + 28)? as usize;
+        let start = at + 30 + name_len + extra_len;
+        let raw = self
+            .data
+            .get(start..start + entry.compressed_size)
+            .ok_or_else(|| invalid("truncated zip archive"))?;
+        match entry.method {
+            0 => Ok(raw.to_vec()),
+            8 => inflate(raw),
+            _ => Err(invalid("unsupported zip compression method")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty archive's end of directory, with some number of members at some offset
+    fn end_of_directory(count: u16, offset: u32) -> Vec<u8> {
+        let mut data = END_OF_DIRECTORY.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data
+    }
+
+    #[test]
+    fn zip64_archives_are_refused() {
+        let empty = Archive::new(end_of_directory(0, 0)).unwrap();
+        assert_eq!(0, empty.names().count());
+        let err = Archive::new(end_of_directory(u16::MAX, 0)).err().unwrap();
+        assert!(err.to_string().contains("ZIP64"));
+        let mut data = ZIP64_LOCATOR.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0; 16]);
+        data.extend(end_of_directory(0, 0));
+        let err = Archive::new(data).err().unwrap();
+        assert!(err.to_string().contains("ZIP64"));
+    }
+}